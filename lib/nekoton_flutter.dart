@@ -36,6 +36,7 @@ export 'src/core/models/transaction_with_data.dart';
 export 'src/core/models/transactions_batch_info.dart';
 export 'src/core/models/transactions_batch_type.dart';
 export 'src/core/models/transactions_list.dart';
+export 'src/core/subscription_scheduler/subscription_scheduler.dart';
 export 'src/core/token_wallet/get_token_root_details.dart';
 export 'src/core/token_wallet/get_token_root_details_from_token_wallet.dart';
 export 'src/core/token_wallet/get_token_wallet_details.dart';
@@ -122,10 +123,17 @@ export 'src/crypto/verify_signature.dart';
 export 'src/external/gql_connection.dart';
 export 'src/external/jrpc_connection.dart';
 export 'src/external/ledger_connection.dart';
+export 'src/external/models/connection_exception.dart';
 export 'src/external/models/gql_network_settings.dart';
 export 'src/external/models/jrpc_network_settings.dart';
+export 'src/external/models/ledger_error_kind.dart';
+export 'src/external/models/ledger_exception.dart';
 export 'src/external/models/ledger_signature_context.dart';
+export 'src/external/models/native_connection_config.dart';
 export 'src/external/models/proto_network_settings.dart';
+export 'src/external/models/retry_error_class.dart';
+export 'src/external/models/retry_policy.dart';
+export 'src/external/models/tls_config.dart';
 export 'src/external/proto_connection.dart';
 export 'src/external/storage.dart';
 export 'src/helpers/abi/check_public_key.dart';
@@ -153,6 +161,7 @@ export 'src/helpers/abi/parse_known_payload.dart';
 export 'src/helpers/abi/run_local.dart';
 export 'src/helpers/abi/unpack_from_cell.dart';
 export 'src/helpers/code_to_tvc.dart';
+export 'src/helpers/debug_live_handles.dart';
 export 'src/helpers/extract_public_key.dart';
 export 'src/helpers/get_code_salt.dart';
 export 'src/helpers/merge_tvc.dart';
@@ -163,8 +172,14 @@ export 'src/helpers/split_tvc.dart';
 export 'src/helpers/unpack_std_smc_addr.dart';
 export 'src/helpers/validate_address.dart';
 export 'src/models/nekoton_exception.dart';
+export 'src/runtime/runtime.dart';
+export 'src/transport/caching_transport.dart';
+export 'src/transport/emulated_transport.dart';
+export 'src/transport/failover_transport.dart';
 export 'src/transport/gql_transport.dart';
 export 'src/transport/jrpc_transport.dart';
 export 'src/transport/models/models.dart';
+export 'src/transport/networks.dart';
 export 'src/transport/proto_transport.dart';
+export 'src/transport/throttled_transport.dart';
 export 'src/transport/transport.dart';
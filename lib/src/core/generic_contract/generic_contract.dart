@@ -12,7 +12,9 @@ import 'package:nekoton_flutter/src/core/models/polling_method.dart';
 import 'package:nekoton_flutter/src/core/models/transaction.dart';
 import 'package:nekoton_flutter/src/core/models/transactions_batch_info.dart';
 import 'package:nekoton_flutter/src/crypto/models/signed_message.dart';
+import 'package:nekoton_flutter/src/crypto/unsigned_message.dart';
 import 'package:nekoton_flutter/src/ffi_utils.dart';
+import 'package:nekoton_flutter/src/transport/models/fees_estimate.dart';
 import 'package:nekoton_flutter/src/transport/transport.dart';
 import 'package:tuple/tuple.dart';
 
@@ -134,6 +136,14 @@ class GenericContract implements Finalizable {
     return fees;
   }
 
+  /// Itemised fees of [unsignedMessage] including its internal cascade, see
+  /// [Transport.estimateFeesBreakdown].
+  Future<FeesEstimate> estimateFeesBreakdown(
+    UnsignedMessage unsignedMessage, {
+    int? maxTransactions,
+  }) =>
+      _transport.estimateFeesBreakdown(unsignedMessage, maxTransactions: maxTransactions);
+
   Future<PendingTransaction> send(SignedMessage signedMessage) async {
     final signedMessageStr = jsonEncode(signedMessage);
 
@@ -269,7 +279,6 @@ class GenericContract implements Finalizable {
     }).asBroadcastStream(onCancel: (subscription) => subscription.cancel());
 
     final transportPtr = _transport.ptr;
-    final transportTypeStr = jsonEncode(_transport.type.toString());
 
     final result = await executeAsync(
       (port) => NekotonFlutter.instance().bindings.nt_generic_contract_subscribe(
@@ -279,7 +288,6 @@ class GenericContract implements Finalizable {
             _onStateChangedPort.sendPort.nativePort,
             _onTransactionsFoundPort.sendPort.nativePort,
             transportPtr,
-            transportTypeStr.toNativeUtf8().cast<Char>(),
             address.toNativeUtf8().cast<Char>(),
             preloadTransactions ? 1 : 0,
           ),
@@ -0,0 +1,100 @@
+import 'dart:convert';
+import 'dart:ffi';
+
+import 'package:ffi/ffi.dart';
+import 'package:nekoton_flutter/src/bindings.dart';
+import 'package:nekoton_flutter/src/core/generic_contract/generic_contract.dart';
+import 'package:nekoton_flutter/src/core/token_wallet/token_wallet.dart';
+import 'package:nekoton_flutter/src/core/ton_wallet/ton_wallet.dart';
+import 'package:nekoton_flutter/src/ffi_utils.dart';
+import 'package:nekoton_flutter/src/models/nekoton_exception.dart';
+import 'package:nekoton_flutter/src/transport/transport.dart';
+
+final _nativeFinalizer = NativeFinalizer(
+  NekotonFlutter.instance().bindings.addresses.nt_subscription_scheduler_free_ptr,
+);
+
+/// Refreshes [TonWallet], [TokenWallet] and [GenericContract] subscriptions according to their
+/// polling methods instead of a timer per subscription. Updates are emitted by the streams
+/// of the subscriptions, the same as after [TonWallet.refresh].
+///
+/// Subscriptions of the same account share a single state check and are only refreshed
+/// when the account changed. At most [concurrency] accounts are polled at the same time.
+/// Manually polled subscriptions without changes are polled less often, from
+/// [manualInterval] up to [maxIdleInterval], but are picked up within [reliableInterval]
+/// once they send a message. Subscriptions with pending transactions follow blocks of
+/// [transport], which are emulated by polling for transports other than GQL.
+class SubscriptionScheduler implements Finalizable {
+  late final Pointer<Void> _ptr;
+  final Transport _transport;
+  int? _operationId;
+
+  SubscriptionScheduler(
+    this._transport, {
+    Duration? manualInterval,
+    Duration? reliableInterval,
+    Duration? maxIdleInterval,
+    int? concurrency,
+  }) {
+    final config = jsonEncode({
+      if (manualInterval != null) 'manualIntervalMs': manualInterval.inMilliseconds,
+      if (reliableInterval != null) 'reliableIntervalMs': reliableInterval.inMilliseconds,
+      if (maxIdleInterval != null) 'maxIdleIntervalMs': maxIdleInterval.inMilliseconds,
+      if (concurrency != null) 'concurrency': concurrency,
+    });
+
+    final result = executeSync(
+      () => NekotonFlutter.instance().bindings.nt_subscription_scheduler_create(
+            _transport.ptr,
+            config.toNativeUtf8().cast<Char>(),
+          ),
+    );
+
+    _ptr = toPtrFromAddress(result as String);
+
+    _nativeFinalizer.attach(this, _ptr);
+
+    executeAsync(
+      (port) => NekotonFlutter.instance().bindings.nt_subscription_scheduler_run(
+            port,
+            _ptr,
+          ),
+      onStarted: (id) => _operationId = id,
+    ).catchError((Object err) {
+      if (err is! OperationCancelledException) throw err;
+    });
+  }
+
+  Pointer<Void> get ptr => _ptr;
+
+  Transport get transport => _transport;
+
+  /// [subscription] is refreshed right away. A disposed subscription leaves the schedule
+  /// on its own.
+  void add(Finalizable subscription) => executeSync(
+        () => NekotonFlutter.instance().bindings.nt_subscription_scheduler_add(
+              _ptr,
+              _subscriptionPtr(subscription),
+            ),
+      );
+
+  void remove(Finalizable subscription) => executeSync(
+        () => NekotonFlutter.instance().bindings.nt_subscription_scheduler_remove(
+              _ptr,
+              _subscriptionPtr(subscription),
+            ),
+      );
+
+  Future<void> dispose() async {
+    final id = _operationId;
+    if (id != null) cancelOperation(id);
+  }
+
+  Pointer<Void> _subscriptionPtr(Finalizable subscription) {
+    if (subscription is TonWallet) return subscription.ptr;
+    if (subscription is TokenWallet) return subscription.ptr;
+    if (subscription is GenericContract) return subscription.ptr;
+
+    throw ArgumentError.value(subscription, 'subscription', 'Not a subscription');
+  }
+}
@@ -1,4 +1,3 @@
-import 'dart:convert';
 import 'dart:ffi';
 
 import 'package:ffi/ffi.dart';
@@ -13,13 +12,11 @@ Future<Tuple2<String, RootTokenContractDetails>> getTokenRootDetailsFromTokenWal
   required String tokenWalletAddress,
 }) async {
   final ptr = transport.ptr;
-  final transportTypeStr = jsonEncode(transport.type.toString());
 
   final result = await executeAsync(
     (port) => NekotonFlutter.instance().bindings.nt_get_token_wallet_details(
           port,
           ptr,
-          transportTypeStr.toNativeUtf8().cast<Char>(),
           tokenWalletAddress.toNativeUtf8().cast<Char>(),
         ),
   );
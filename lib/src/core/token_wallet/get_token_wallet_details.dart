@@ -1,4 +1,3 @@
-import 'dart:convert';
 import 'dart:ffi';
 
 import 'package:ffi/ffi.dart';
@@ -14,13 +13,11 @@ Future<Tuple2<TokenWalletDetails, RootTokenContractDetails>> getTokenWalletDetai
   required String tokenWallet,
 }) async {
   final ptr = transport.ptr;
-  final transportTypeStr = jsonEncode(transport.type.toString());
 
   final result = await executeAsync(
     (port) => NekotonFlutter.instance().bindings.nt_get_token_wallet_details(
           port,
           ptr,
-          transportTypeStr.toNativeUtf8().cast<Char>(),
           tokenWallet.toNativeUtf8().cast<Char>(),
         ),
   );
@@ -280,7 +280,6 @@ class TokenWallet implements Finalizable {
     }).asBroadcastStream(onCancel: (subscription) => subscription.cancel());
 
     final transportPtr = _transport.ptr;
-    final transportTypeStr = jsonEncode(_transport.type.toString());
 
     final result = await executeAsync(
       (port) => NekotonFlutter.instance().bindings.nt_token_wallet_subscribe(
@@ -288,7 +287,6 @@ class TokenWallet implements Finalizable {
             _onBalanceChangedPort.sendPort.nativePort,
             _onTransactionsFoundPort.sendPort.nativePort,
             transportPtr,
-            transportTypeStr.toNativeUtf8().cast<Char>(),
             owner.toNativeUtf8().cast<Char>(),
             rootTokenContract.toNativeUtf8().cast<Char>(),
           ),
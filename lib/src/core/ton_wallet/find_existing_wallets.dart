@@ -15,14 +15,12 @@ Future<List<ExistingWalletInfo>> findExistingWallets({
   required List<WalletType> walletTypes,
 }) async {
   final ptr = transport.ptr;
-  final transportTypeStr = jsonEncode(transport.type.toString());
   final walletTypesStr = jsonEncode(walletTypes);
 
   final result = await executeAsync(
     (port) => NekotonFlutter.instance().bindings.nt_find_existing_wallets(
           port,
           ptr,
-          transportTypeStr.toNativeUtf8().cast<Char>(),
           publicKey.toNativeUtf8().cast<Char>(),
           workchainId,
           walletTypesStr.toNativeUtf8().cast<Char>(),
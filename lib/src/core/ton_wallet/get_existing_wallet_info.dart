@@ -1,4 +1,3 @@
-import 'dart:convert';
 import 'dart:ffi';
 
 import 'package:ffi/ffi.dart';
@@ -12,13 +11,11 @@ Future<ExistingWalletInfo> getExistingWalletInfo({
   required String address,
 }) async {
   final ptr = transport.ptr;
-  final transportTypeStr = jsonEncode(transport.type.toString());
 
   final result = await executeAsync(
     (port) => NekotonFlutter.instance().bindings.nt_get_existing_wallet_info(
           port,
           ptr,
-          transportTypeStr.toNativeUtf8().cast<Char>(),
           address.toNativeUtf8().cast<Char>(),
         ),
   );
@@ -1,4 +1,3 @@
-import 'dart:convert';
 import 'dart:ffi';
 
 import 'package:ffi/ffi.dart';
@@ -11,13 +10,11 @@ Future<List<String>> getWalletCustodians({
   required String address,
 }) async {
   final ptr = transport.ptr;
-  final transportTypeStr = jsonEncode(transport.type.toString());
 
   final result = await executeAsync(
     (port) => NekotonFlutter.instance().bindings.nt_get_wallet_custodians(
           port,
           ptr,
-          transportTypeStr.toNativeUtf8().cast<Char>(),
           address.toNativeUtf8().cast<Char>(),
         ),
   );
@@ -21,6 +21,7 @@ import 'package:nekoton_flutter/src/core/ton_wallet/models/transaction_additiona
 import 'package:nekoton_flutter/src/crypto/models/signed_message.dart';
 import 'package:nekoton_flutter/src/crypto/unsigned_message.dart';
 import 'package:nekoton_flutter/src/ffi_utils.dart';
+import 'package:nekoton_flutter/src/transport/models/fees_estimate.dart';
 import 'package:nekoton_flutter/src/transport/transport.dart';
 import 'package:tuple/tuple.dart';
 
@@ -367,6 +368,14 @@ class TonWallet implements Finalizable {
     return fees;
   }
 
+  /// Itemised fees of [unsignedMessage] including its internal cascade, see
+  /// [Transport.estimateFeesBreakdown].
+  Future<FeesEstimate> estimateFeesBreakdown(
+    UnsignedMessage unsignedMessage, {
+    int? maxTransactions,
+  }) =>
+      _transport.estimateFeesBreakdown(unsignedMessage, maxTransactions: maxTransactions);
+
   Future<PendingTransaction> send(SignedMessage signedMessage) async {
     final signedMessageStr = jsonEncode(signedMessage);
 
@@ -437,7 +446,6 @@ class TonWallet implements Finalizable {
       _initialize(
         () async {
           final transportPtr = _transport.ptr;
-          final transportTypeStr = jsonEncode(_transport.type.toString());
           final contractStr = jsonEncode(contract);
 
           final result = await executeAsync(
@@ -448,7 +456,6 @@ class TonWallet implements Finalizable {
                   _onStateChangedPort.sendPort.nativePort,
                   _onTransactionsFoundPort.sendPort.nativePort,
                   transportPtr,
-                  transportTypeStr.toNativeUtf8().cast<Char>(),
                   workchain,
                   publicKey.toNativeUtf8().cast<Char>(),
                   contractStr.toNativeUtf8().cast<Char>(),
@@ -462,7 +469,6 @@ class TonWallet implements Finalizable {
   Future<void> _subscribeByAddress(String address) => _initialize(
         () async {
           final transportPtr = _transport.ptr;
-          final transportTypeStr = jsonEncode(_transport.type.toString());
 
           final result = await executeAsync(
             (port) => NekotonFlutter.instance().bindings.nt_ton_wallet_subscribe_by_address(
@@ -472,7 +478,6 @@ class TonWallet implements Finalizable {
                   _onStateChangedPort.sendPort.nativePort,
                   _onTransactionsFoundPort.sendPort.nativePort,
                   transportPtr,
-                  transportTypeStr.toNativeUtf8().cast<Char>(),
                   address.toNativeUtf8().cast<Char>(),
                 ),
           );
@@ -484,7 +489,6 @@ class TonWallet implements Finalizable {
   Future<void> _subscribeByExisting(ExistingWalletInfo existingWallet) => _initialize(
         () async {
           final transportPtr = _transport.ptr;
-          final transportTypeStr = jsonEncode(_transport.type.toString());
           final existingWalletStr = jsonEncode(existingWallet);
 
           final result = await executeAsync(
@@ -495,7 +499,6 @@ class TonWallet implements Finalizable {
                   _onStateChangedPort.sendPort.nativePort,
                   _onTransactionsFoundPort.sendPort.nativePort,
                   transportPtr,
-                  transportTypeStr.toNativeUtf8().cast<Char>(),
                   existingWalletStr.toNativeUtf8().cast<Char>(),
                 ),
           );
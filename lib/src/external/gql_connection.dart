@@ -7,7 +7,11 @@ import 'package:async/async.dart';
 import 'package:ffi/ffi.dart';
 import 'package:flutter/foundation.dart';
 import 'package:nekoton_flutter/src/bindings.dart';
+import 'package:nekoton_flutter/src/external/models/connection_exception.dart';
 import 'package:nekoton_flutter/src/external/models/gql_network_settings.dart';
+import 'package:nekoton_flutter/src/external/models/native_connection_config.dart';
+import 'package:nekoton_flutter/src/external/models/retry_error_class.dart';
+import 'package:nekoton_flutter/src/external/models/retry_policy.dart';
 import 'package:nekoton_flutter/src/ffi_utils.dart';
 import 'package:nekoton_flutter/src/models/nekoton_exception.dart';
 import 'package:nekoton_flutter/src/transport/models/transport_type.dart';
@@ -27,16 +31,16 @@ final _nativeFinalizer =
 class GqlConnection implements Finalizable {
   late final Pointer<Void> _ptr;
   final _postPort = ReceivePort();
-  late final StreamSubscription<Tuple2<String, String>> _postSubscription;
-  final GqlConnectionPost _post;
-  final GqlConnectionGet _get;
+  StreamSubscription<Tuple2<String, String>>? _postSubscription;
+  final GqlConnectionPost? _post;
+  final GqlConnectionGet? _get;
   final String _name;
   final int _networkId;
   final String _group;
   final _type = TransportType.gql;
-  final GqlNetworkSettings _settings;
+  final GqlNetworkSettings? _settings;
   late final _endpointCache =
-      AsyncCache<String>(Duration(milliseconds: _settings.latencyDetectionInterval));
+      AsyncCache<String>(Duration(milliseconds: _settings!.latencyDetectionInterval));
 
   GqlConnection({
     required GqlConnectionPost post,
@@ -45,6 +49,9 @@ class GqlConnection implements Finalizable {
     required int networkId,
     required String group,
     required GqlNetworkSettings settings,
+    Duration? timeout,
+    RetryPolicy? retryPolicy,
+    String? recordPath,
   })  : _post = post,
         _get = get,
         _name = name,
@@ -60,10 +67,76 @@ class GqlConnection implements Finalizable {
       return Tuple2(tx, data);
     }).listen(_postRequestHandler);
 
+    final retryPolicyStr = retryPolicy != null ? jsonEncode(retryPolicy) : null;
+
+    final result = executeSync(
+      () => recordPath != null
+          ? NekotonFlutter.instance().bindings.nt_gql_connection_create_recording(
+                settings.local ? 1 : 0,
+                _postPort.sendPort.nativePort,
+                timeout?.inMilliseconds ?? 0,
+                retryPolicyStr?.toNativeUtf8().cast<Char>() ?? nullptr,
+                recordPath.toNativeUtf8().cast<Char>(),
+              )
+          : NekotonFlutter.instance().bindings.nt_gql_connection_create(
+                settings.local ? 1 : 0,
+                _postPort.sendPort.nativePort,
+                timeout?.inMilliseconds ?? 0,
+                retryPolicyStr?.toNativeUtf8().cast<Char>() ?? nullptr,
+              ),
+    );
+
+    _ptr = toPtrFromAddress(result as String);
+
+    _nativeFinalizer.attach(this, _ptr);
+  }
+
+  /// Answers requests from a session file written by a connection created with `recordPath`
+  /// instead of the network, e.g. to reproduce a captured session locally.
+  GqlConnection.replay({
+    required String path,
+    required String name,
+    required int networkId,
+    required String group,
+    bool local = false,
+  })  : _post = null,
+        _get = null,
+        _name = name,
+        _networkId = networkId,
+        _group = group,
+        _settings = null {
+    final result = executeSync(
+      () => NekotonFlutter.instance().bindings.nt_gql_connection_create_replay(
+            local ? 1 : 0,
+            path.toNativeUtf8().cast<Char>(),
+          ),
+    );
+
+    _ptr = toPtrFromAddress(result as String);
+
+    _nativeFinalizer.attach(this, _ptr);
+  }
+
+  /// Posts requests over HTTP from the native library instead of [GqlConnectionPost],
+  /// requires the library to be built with the `native_connections` feature.
+  GqlConnection.native({
+    required NativeConnectionConfig config,
+    required String name,
+    required int networkId,
+    required String group,
+    bool local = false,
+  })  : _post = null,
+        _get = null,
+        _name = name,
+        _networkId = networkId,
+        _group = group,
+        _settings = null {
+    final configStr = jsonEncode(config);
+
     final result = executeSync(
-      () => NekotonFlutter.instance().bindings.nt_gql_connection_create(
-            settings.local ? 1 : 0,
-            _postPort.sendPort.nativePort,
+      () => NekotonFlutter.instance().bindings.nt_gql_connection_create_native(
+            local ? 1 : 0,
+            configStr.toNativeUtf8().cast<Char>(),
           ),
     );
 
@@ -83,7 +156,7 @@ class GqlConnection implements Finalizable {
   TransportType get type => _type;
 
   Future<void> dispose() async {
-    await _postSubscription.cancel();
+    await _postSubscription?.cancel();
 
     _postPort.close();
   }
@@ -97,19 +170,23 @@ class GqlConnection implements Finalizable {
     try {
       String endpoint;
 
-      if (_settings.endpoints.length == 1) {
-        endpoint = _settings.endpoints.first;
+      if (_settings!.endpoints.length == 1) {
+        endpoint = _settings!.endpoints.first;
       } else {
         endpoint = await _endpointCache.fetch(_selectQueryingEndpoint);
       }
 
-      ok = await _post(
+      ok = await _post!(
         endpoint: endpoint,
         headers: {
           'Content-Type': 'application/json',
         },
         data: event.item2,
       );
+    } on ConnectionException catch (error) {
+      err = jsonEncode(error);
+    } on TimeoutException catch (error) {
+      err = jsonEncode(ConnectionException(error.toString(), kind: RetryErrorClass.timeout));
     } catch (error) {
       err = error.toString();
     }
@@ -122,9 +199,9 @@ class GqlConnection implements Finalizable {
   }
 
   Future<String> _selectQueryingEndpoint() async {
-    final maxLatency = _settings.maxLatency;
-    final retryCount = _settings.endpointSelectionRetryCount;
-    final endpointsCount = _settings.endpoints.length;
+    final maxLatency = _settings!.maxLatency;
+    final retryCount = _settings!.endpointSelectionRetryCount;
+    final endpointsCount = _settings!.endpoints.length;
 
     for (var i = 0; i < retryCount; i++) {
       try {
@@ -132,7 +209,7 @@ class GqlConnection implements Finalizable {
 
         var checkedEndpoints = 0;
 
-        for (final e in _settings.endpoints) {
+        for (final e in _settings!.endpoints) {
           _checkLatency(e).whenComplete(() {
             checkedEndpoints++;
           }).then((v) {
@@ -155,7 +232,7 @@ class GqlConnection implements Finalizable {
   }
 
   Future<int> _checkLatency(String endpoint) async {
-    final response = await _get('$endpoint?query=%7Binfo%7Bversion%20time%20latency%7D%7D');
+    final response = await _get!('$endpoint?query=%7Binfo%7Bversion%20time%20latency%7D%7D');
 
     final json = jsonDecode(response) as Map<String, dynamic>;
 
@@ -5,7 +5,11 @@ import 'dart:isolate';
 
 import 'package:ffi/ffi.dart';
 import 'package:nekoton_flutter/src/bindings.dart';
+import 'package:nekoton_flutter/src/external/models/connection_exception.dart';
 import 'package:nekoton_flutter/src/external/models/jrpc_network_settings.dart';
+import 'package:nekoton_flutter/src/external/models/native_connection_config.dart';
+import 'package:nekoton_flutter/src/external/models/retry_error_class.dart';
+import 'package:nekoton_flutter/src/external/models/retry_policy.dart';
 import 'package:nekoton_flutter/src/ffi_utils.dart';
 import 'package:nekoton_flutter/src/transport/models/transport_type.dart';
 import 'package:tuple/tuple.dart';
@@ -22,13 +26,13 @@ final _nativeFinalizer =
 class JrpcConnection implements Finalizable {
   late final Pointer<Void> _ptr;
   final _postPort = ReceivePort();
-  late final StreamSubscription<Tuple2<String, String>> _postSubscription;
-  final JrpcConnectionPost _post;
+  StreamSubscription<Tuple2<String, String>>? _postSubscription;
+  final JrpcConnectionPost? _post;
   final String _name;
   final int _networkId;
   final String _group;
   final _type = TransportType.jrpc;
-  final JrpcNetworkSettings _settings;
+  final JrpcNetworkSettings? _settings;
 
   JrpcConnection({
     required JrpcConnectionPost post,
@@ -36,6 +40,9 @@ class JrpcConnection implements Finalizable {
     required int networkId,
     required String group,
     required JrpcNetworkSettings settings,
+    Duration? timeout,
+    RetryPolicy? retryPolicy,
+    String? recordPath,
   })  : _post = post,
         _name = name,
         _networkId = networkId,
@@ -50,9 +57,68 @@ class JrpcConnection implements Finalizable {
       return Tuple2(tx, data);
     }).listen(_postRequestHandler);
 
+    final retryPolicyStr = retryPolicy != null ? jsonEncode(retryPolicy) : null;
+
+    final result = executeSync(
+      () => recordPath != null
+          ? NekotonFlutter.instance().bindings.nt_jrpc_connection_create_recording(
+                _postPort.sendPort.nativePort,
+                timeout?.inMilliseconds ?? 0,
+                retryPolicyStr?.toNativeUtf8().cast<Char>() ?? nullptr,
+                recordPath.toNativeUtf8().cast<Char>(),
+              )
+          : NekotonFlutter.instance().bindings.nt_jrpc_connection_create(
+                _postPort.sendPort.nativePort,
+                timeout?.inMilliseconds ?? 0,
+                retryPolicyStr?.toNativeUtf8().cast<Char>() ?? nullptr,
+              ),
+    );
+
+    _ptr = toPtrFromAddress(result as String);
+
+    _nativeFinalizer.attach(this, _ptr);
+  }
+
+  /// Answers requests from a session file written by a connection created with `recordPath`
+  /// instead of the network, e.g. to reproduce a captured session locally.
+  JrpcConnection.replay({
+    required String path,
+    required String name,
+    required int networkId,
+    required String group,
+  })  : _post = null,
+        _name = name,
+        _networkId = networkId,
+        _group = group,
+        _settings = null {
+    final result = executeSync(
+      () => NekotonFlutter.instance().bindings.nt_jrpc_connection_create_replay(
+            path.toNativeUtf8().cast<Char>(),
+          ),
+    );
+
+    _ptr = toPtrFromAddress(result as String);
+
+    _nativeFinalizer.attach(this, _ptr);
+  }
+
+  /// Posts requests over HTTP from the native library instead of [JrpcConnectionPost],
+  /// requires the library to be built with the `native_connections` feature.
+  JrpcConnection.native({
+    required NativeConnectionConfig config,
+    required String name,
+    required int networkId,
+    required String group,
+  })  : _post = null,
+        _name = name,
+        _networkId = networkId,
+        _group = group,
+        _settings = null {
+    final configStr = jsonEncode(config);
+
     final result = executeSync(
-      () => NekotonFlutter.instance().bindings.nt_jrpc_connection_create(
-            _postPort.sendPort.nativePort,
+      () => NekotonFlutter.instance().bindings.nt_jrpc_connection_create_native(
+            configStr.toNativeUtf8().cast<Char>(),
           ),
     );
 
@@ -72,7 +138,7 @@ class JrpcConnection implements Finalizable {
   TransportType get type => _type;
 
   Future<void> dispose() async {
-    await _postSubscription.cancel();
+    await _postSubscription?.cancel();
 
     _postPort.close();
   }
@@ -84,13 +150,17 @@ class JrpcConnection implements Finalizable {
     String? err;
 
     try {
-      ok = await _post(
-        endpoint: _settings.endpoint,
+      ok = await _post!(
+        endpoint: _settings!.endpoint,
         headers: {
           'Content-Type': 'application/json',
         },
         data: event.item2,
       );
+    } on ConnectionException catch (error) {
+      err = jsonEncode(error);
+    } on TimeoutException catch (error) {
+      err = jsonEncode(ConnectionException(error.toString(), kind: RetryErrorClass.timeout));
     } catch (error) {
       err = error.toString();
     }
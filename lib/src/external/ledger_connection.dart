@@ -5,14 +5,21 @@ import 'dart:isolate';
 
 import 'package:ffi/ffi.dart';
 import 'package:nekoton_flutter/src/bindings.dart';
+import 'package:nekoton_flutter/src/external/models/ledger_exception.dart';
 import 'package:nekoton_flutter/src/external/models/ledger_signature_context.dart';
 import 'package:nekoton_flutter/src/ffi_utils.dart';
 import 'package:tuple/tuple.dart';
 
 typedef LedgerConnectionGetPublicKey = Future<String> Function(int accountId);
 
+/// Returns the hex encoded signature of [message]. [wallet] and [context] are only set
+/// for transactions, so that the device can display the transfer details.
+///
+/// Throw [LedgerException] when the device refuses to sign.
 typedef LedgerConnectionSign = Future<String> Function({
   required int account,
+  int? wallet,
+  int? signatureId,
   required List<int> message,
   LedgerSignatureContext? context,
 });
@@ -25,14 +32,14 @@ class LedgerConnection implements Finalizable {
   final _getPublicKeyPort = ReceivePort();
   final _signPort = ReceivePort();
   late final StreamSubscription<Tuple2<String, int>> _getPublicKeySubscription;
-  late final StreamSubscription<Tuple4<String, int, List<int>, LedgerSignatureContext?>>
-      _signSubscription;
+  late final StreamSubscription<Tuple2<String, Map<String, dynamic>>> _signSubscription;
   final LedgerConnectionGetPublicKey _getPublicKey;
   final LedgerConnectionSign _sign;
 
   LedgerConnection({
     required LedgerConnectionGetPublicKey getPublicKey,
     required LedgerConnectionSign sign,
+    Duration? timeout,
   })  : _getPublicKey = getPublicKey,
         _sign = sign {
     _getPublicKeySubscription = _getPublicKeyPort.cast<String>().map((e) {
@@ -48,24 +55,16 @@ class LedgerConnection implements Finalizable {
       final json = jsonDecode(e) as List<dynamic>;
 
       final tx = json.first as String;
-      final account = json[1] as int;
-      final messageJson = json[2] as List<dynamic>;
-      final message = messageJson.cast<int>();
-      final contextJson = json.last as Map<String, dynamic>?;
-      final context = contextJson != null ? LedgerSignatureContext.fromJson(contextJson) : null;
-
-      return Tuple4(
-        tx,
-        account,
-        message,
-        context,
-      );
+      final request = json.last as Map<String, dynamic>;
+
+      return Tuple2(tx, request);
     }).listen(_signRequestHandler);
 
     final result = executeSync(
       () => NekotonFlutter.instance().bindings.nt_ledger_connection_create(
             _getPublicKeyPort.sendPort.nativePort,
             _signPort.sendPort.nativePort,
+            timeout?.inMilliseconds ?? 0,
           ),
     );
 
@@ -103,20 +102,25 @@ class LedgerConnection implements Finalizable {
         );
   }
 
-  Future<void> _signRequestHandler(
-    Tuple4<String, int, List<int>, LedgerSignatureContext?> event,
-  ) async {
+  Future<void> _signRequestHandler(Tuple2<String, Map<String, dynamic>> event) async {
     final tx = toPtrFromAddress(event.item1);
+    final request = event.item2;
 
     String? ok;
     String? err;
 
     try {
+      final contextJson = request['context'] as Map<String, dynamic>?;
+
       ok = await _sign(
-        account: event.item2,
-        message: event.item3,
-        context: event.item4,
+        account: request['account'] as int,
+        wallet: request['wallet'] as int?,
+        signatureId: request['signatureId'] as int?,
+        message: (request['message'] as List<dynamic>).cast<int>(),
+        context: contextJson != null ? LedgerSignatureContext.fromJson(contextJson) : null,
       );
+    } on LedgerException catch (error) {
+      err = jsonEncode(error);
     } catch (error) {
       err = error.toString();
     }
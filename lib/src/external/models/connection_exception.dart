@@ -0,0 +1,27 @@
+import 'package:nekoton_flutter/src/external/models/retry_error_class.dart';
+
+/// Thrown from the post function of a connection when a request failed, the retry policy
+/// of the connection only retries requests failed with a known [kind] or [statusCode].
+///
+/// [statusCode] is the HTTP status of the response, the kind is detected from it when set.
+/// A `TimeoutException` is reported with the `timeout` kind, other errors are never retried.
+class ConnectionException implements Exception {
+  final String message;
+  final RetryErrorClass? kind;
+  final int? statusCode;
+
+  ConnectionException(
+    this.message, {
+    this.kind,
+    this.statusCode,
+  });
+
+  Map<String, dynamic> toJson() => {
+        'message': message,
+        if (kind != null) 'kind': kind.toString(),
+        if (statusCode != null) 'statusCode': statusCode,
+      };
+
+  @override
+  String toString() => message;
+}
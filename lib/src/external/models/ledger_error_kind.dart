@@ -0,0 +1,13 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+
+part 'ledger_error_kind.g.dart';
+
+@JsonEnum(alwaysCreate: true)
+enum LedgerErrorKind {
+  userRejected,
+  deviceLocked,
+  wrongApp;
+
+  @override
+  String toString() => _$LedgerErrorKindEnumMap[this]!;
+}
@@ -0,0 +1,27 @@
+import 'package:nekoton_flutter/src/external/models/ledger_error_kind.dart';
+
+/// Thrown from [LedgerConnectionSign] when the device refused to sign, the signing call
+/// then fails with the `ledgerUserRejected`, `ledgerDeviceLocked` or `ledgerWrongApp` code.
+///
+/// [statusCode] is the status word returned by the device, the kind is detected from it
+/// when [kind] is not set.
+class LedgerException implements Exception {
+  final String message;
+  final LedgerErrorKind? kind;
+  final int? statusCode;
+
+  LedgerException(
+    this.message, {
+    this.kind,
+    this.statusCode,
+  });
+
+  Map<String, dynamic> toJson() => {
+        'message': message,
+        if (kind != null) 'kind': kind.toString(),
+        if (statusCode != null) 'statusCode': statusCode,
+      };
+
+  @override
+  String toString() => message;
+}
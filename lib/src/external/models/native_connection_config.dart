@@ -0,0 +1,25 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+import 'package:nekoton_flutter/src/external/models/retry_policy.dart';
+import 'package:nekoton_flutter/src/external/models/tls_config.dart';
+
+part 'native_connection_config.freezed.dart';
+part 'native_connection_config.g.dart';
+
+/// Settings of a connection which posts requests over HTTP from the native library,
+/// available when it is built with the `native_connections` feature.
+@freezed
+abstract class NativeConnectionConfig with _$NativeConnectionConfig {
+  const factory NativeConnectionConfig({
+    required String endpoint,
+    @Default(<String, String>{}) Map<String, String> headers,
+
+    /// Requests never time out if not set.
+    int? timeoutMs,
+    int? connectTimeoutMs,
+    @Default(TlsConfig()) TlsConfig tls,
+    RetryPolicy? retryPolicy,
+  }) = _NativeConnectionConfig;
+
+  factory NativeConnectionConfig.fromJson(Map<String, dynamic> json) =>
+      _$NativeConnectionConfigFromJson(json);
+}
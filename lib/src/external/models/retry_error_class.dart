@@ -0,0 +1,16 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+
+part 'retry_error_class.g.dart';
+
+@JsonEnum(alwaysCreate: true)
+enum RetryErrorClass {
+  timeout,
+  connect,
+  network,
+  server,
+  rateLimited,
+  other;
+
+  @override
+  String toString() => _$RetryErrorClassEnumMap[this]!;
+}
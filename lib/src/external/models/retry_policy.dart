@@ -0,0 +1,29 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+import 'package:nekoton_flutter/src/external/models/retry_error_class.dart';
+
+part 'retry_policy.freezed.dart';
+part 'retry_policy.g.dart';
+
+/// Failed requests are retried with an exponential backoff.
+///
+/// Sent messages are only retried when the endpoint surely didn't receive them.
+@freezed
+abstract class RetryPolicy with _$RetryPolicy {
+  const factory RetryPolicy({
+    required int maxAttempts,
+    @Default(200) int initialBackoffMs,
+    @Default(5000) int maxBackoffMs,
+    @Default(2.0) double multiplier,
+    @Default(0.2) double jitter,
+    @Default([
+      RetryErrorClass.timeout,
+      RetryErrorClass.connect,
+      RetryErrorClass.network,
+      RetryErrorClass.server,
+      RetryErrorClass.rateLimited,
+    ])
+        List<RetryErrorClass> retryOn,
+  }) = _RetryPolicy;
+
+  factory RetryPolicy.fromJson(Map<String, dynamic> json) => _$RetryPolicyFromJson(json);
+}
@@ -0,0 +1,18 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+
+part 'tls_config.freezed.dart';
+part 'tls_config.g.dart';
+
+@freezed
+abstract class TlsConfig with _$TlsConfig {
+  const factory TlsConfig({
+    /// PEM certificates which are trusted in addition to the bundled Mozilla roots.
+    /// The certificate store of the OS is not used, so corporate CAs must be added here.
+    @Default(<String>[]) List<String> rootCertificates,
+
+    /// Only for local development nodes with self-signed certificates.
+    @Default(false) bool acceptInvalidCertificates,
+  }) = _TlsConfig;
+
+  factory TlsConfig.fromJson(Map<String, dynamic> json) => _$TlsConfigFromJson(json);
+}
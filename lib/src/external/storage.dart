@@ -52,6 +52,7 @@ class Storage implements Finalizable {
     required StorageSetUnchecked setUnchecked,
     required StorageRemove remove,
     required StorageRemoveUnchecked removeUnchecked,
+    Duration? timeout,
   })  : _get = get,
         _set = set,
         _setUnchecked = setUnchecked,
@@ -104,6 +105,7 @@ class Storage implements Finalizable {
             _setUncheckedPort.sendPort.nativePort,
             _removePort.sendPort.nativePort,
             _removeUncheckedPort.sendPort.nativePort,
+            timeout?.inMilliseconds ?? 0,
           ),
     );
 
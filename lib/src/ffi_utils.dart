@@ -26,7 +26,11 @@ dynamic executeSync(Pointer<Char> Function() function) {
   return result;
 }
 
-Future<dynamic> executeAsync(void Function(int port) function) async {
+/// [onStarted] receives the id of the native operation which can be passed to [cancelOperation].
+Future<dynamic> executeAsync(
+  int Function(int port) function, {
+  void Function(int operationId)? onStarted,
+}) async {
   final receivePort = ReceivePort();
   final completer = Completer<dynamic>();
   final st = StackTrace.current;
@@ -50,7 +54,12 @@ Future<dynamic> executeAsync(void Function(int port) function) async {
     }
   });
 
-  function(receivePort.sendPort.nativePort);
+  final operationId = function(receivePort.sendPort.nativePort);
+  onStarted?.call(operationId);
 
   return completer.future;
 }
+
+/// Aborts a running native operation, its future completes with [OperationCancelledException].
+void cancelOperation(int operationId) =>
+    NekotonFlutter.instance().bindings.nt_cancel_operation(operationId);
@@ -0,0 +1,11 @@
+import 'package:nekoton_flutter/src/bindings.dart';
+import 'package:nekoton_flutter/src/ffi_utils.dart';
+
+/// Number of native objects currently alive, grouped by type. Useful to find leaked handles.
+Map<String, int> debugLiveHandles() {
+  final result = executeSync(
+    () => NekotonFlutter.instance().bindings.nt_debug_live_handles(),
+  ) as Map<String, dynamic>;
+
+  return result.cast<String, int>();
+}
@@ -8,13 +8,16 @@ part 'execution_result.g.dart';
 abstract class ExecutionResult with _$ExecutionResult {
   const factory ExecutionResult.ok(dynamic data) = _Ok;
 
-  const factory ExecutionResult.err(String data) = _Err;
+  const factory ExecutionResult.err(Map<String, dynamic> data) = _Err;
+
+  const factory ExecutionResult.cancelled() = _Cancelled;
 
   const ExecutionResult._();
 
   dynamic handle() => when(
         ok: (data) => data,
-        err: (data) => throw NekotonException(data),
+        err: (data) => throw NekotonException.fromJson(data),
+        cancelled: () => throw OperationCancelledException(),
       );
 
   factory ExecutionResult.fromJson(Map<String, dynamic> json) =>
@@ -1,8 +1,31 @@
 class NekotonException implements Exception {
-  final String _message;
+  final String code;
+  final String category;
+  final String message;
+  final List<String> causes;
+  final Map<String, String> context;
 
-  NekotonException(this._message);
+  NekotonException(
+    this.message, {
+    this.code = 'internal',
+    this.category = 'internal',
+    this.causes = const [],
+    this.context = const {},
+  });
 
+  factory NekotonException.fromJson(Map<String, dynamic> json) => NekotonException(
+        json['message'] as String,
+        code: json['code'] as String,
+        category: json['category'] as String,
+        causes: (json['causes'] as List<dynamic>? ?? []).cast<String>(),
+        context: (json['context'] as Map<String, dynamic>? ?? {}).cast<String, String>(),
+      );
+
+  @override
+  String toString() => causes.isEmpty ? message : '$message: ${causes.join(': ')}';
+}
+
+class OperationCancelledException implements Exception {
   @override
-  String toString() => _message;
+  String toString() => 'Operation was cancelled';
 }
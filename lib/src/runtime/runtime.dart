@@ -0,0 +1,36 @@
+import 'dart:convert';
+import 'dart:ffi';
+
+import 'package:ffi/ffi.dart';
+import 'package:nekoton_flutter/src/bindings.dart';
+import 'package:nekoton_flutter/src/ffi_utils.dart';
+
+/// Starts the native runtime. A runtime with default settings is started by the first
+/// asynchronous call if this wasn't called before, it is replaced by this call as long as
+/// no operations are running on it and no native objects, e.g. transports or subscriptions,
+/// were created. Throws a [NekotonException] with the `runtimeFailed` code if the runtime
+/// can't be replaced.
+///
+/// [clockOffsetMs] is added to the system time for devices with a wrong clock.
+void initRuntime({
+  int? workerThreads,
+  String? threadName,
+  int? maxBlockingThreads,
+  int? clockOffsetMs,
+}) {
+  final config = jsonEncode({
+    if (workerThreads != null) 'workerThreads': workerThreads,
+    if (threadName != null) 'threadName': threadName,
+    if (maxBlockingThreads != null) 'maxBlockingThreads': maxBlockingThreads,
+    if (clockOffsetMs != null) 'clockOffsetMs': clockOffsetMs,
+  });
+
+  executeSync(
+    () => NekotonFlutter.instance().bindings.nt_runtime_init(config.toNativeUtf8().cast<Char>()),
+  );
+}
+
+/// Stops the native runtime. Operations still running after [timeout] are cancelled
+/// and all native objects are released, so existing instances can't be used anymore.
+void shutdownRuntime({Duration timeout = const Duration(seconds: 5)}) =>
+    NekotonFlutter.instance().bindings.nt_runtime_shutdown(timeout.inMilliseconds);
@@ -0,0 +1,90 @@
+import 'dart:convert';
+import 'dart:ffi';
+
+import 'package:ffi/ffi.dart';
+import 'package:nekoton_flutter/src/bindings.dart';
+import 'package:nekoton_flutter/src/ffi_utils.dart';
+import 'package:nekoton_flutter/src/transport/models/cache_kind.dart';
+import 'package:nekoton_flutter/src/transport/models/cache_stats.dart';
+import 'package:nekoton_flutter/src/transport/models/transport_type.dart';
+import 'package:nekoton_flutter/src/transport/transport.dart';
+
+final _nativeFinalizer =
+    NativeFinalizer(NekotonFlutter.instance().bindings.addresses.nt_caching_transport_free_ptr);
+
+/// Caches reads of [transport]: contract states for [contractStateTtl], transactions
+/// until evicted, blockchain config and capabilities for the whole session.
+class CachingTransport extends Transport implements Finalizable {
+  late final Pointer<Void> _ptr;
+  final Transport _transport;
+
+  CachingTransport(
+    this._transport, {
+    Duration? contractStateTtl,
+    int? contractStateCapacity,
+    int? transactionCapacity,
+  }) {
+    final config = jsonEncode({
+      if (contractStateTtl != null) 'contractStateTtlMs': contractStateTtl.inMilliseconds,
+      if (contractStateCapacity != null) 'contractStateCapacity': contractStateCapacity,
+      if (transactionCapacity != null) 'transactionCapacity': transactionCapacity,
+    });
+
+    final result = executeSync(
+      () => NekotonFlutter.instance().bindings.nt_caching_transport_create(
+            _transport.ptr,
+            config.toNativeUtf8().cast<Char>(),
+          ),
+    );
+
+    _ptr = toPtrFromAddress(result as String);
+
+    _nativeFinalizer.attach(this, _ptr);
+  }
+
+  @override
+  Pointer<Void> get ptr => _ptr;
+
+  @override
+  String get name => _transport.name;
+
+  @override
+  int get networkId => _transport.networkId;
+
+  @override
+  String get group => _transport.group;
+
+  @override
+  TransportType get type => TransportType.caching;
+
+  Transport get transport => _transport;
+
+  CacheStats get stats {
+    final result = executeSync(
+      () => NekotonFlutter.instance().bindings.nt_caching_transport_stats(ptr),
+    );
+
+    return CacheStats.fromJson(result as Map<String, dynamic>);
+  }
+
+  void invalidateAll() => _invalidate({'type': 'all'});
+
+  void invalidateKind(CacheKind kind) => _invalidate({'type': 'kind', 'data': kind.toString()});
+
+  void invalidateContractState(String address) =>
+      _invalidate({'type': 'contractState', 'data': address});
+
+  @override
+  Future<void> dispose() => _transport.dispose();
+
+  void _invalidate(Map<String, dynamic> invalidation) {
+    final invalidationStr = jsonEncode(invalidation);
+
+    executeSync(
+      () => NekotonFlutter.instance().bindings.nt_caching_transport_invalidate(
+            ptr,
+            invalidationStr.toNativeUtf8().cast<Char>(),
+          ),
+    );
+  }
+}
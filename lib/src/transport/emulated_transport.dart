@@ -0,0 +1,84 @@
+import 'dart:convert';
+import 'dart:ffi';
+
+import 'package:ffi/ffi.dart';
+import 'package:nekoton_flutter/src/bindings.dart';
+import 'package:nekoton_flutter/src/ffi_utils.dart';
+import 'package:nekoton_flutter/src/transport/models/emulated_block.dart';
+import 'package:nekoton_flutter/src/transport/models/transport_type.dart';
+import 'package:nekoton_flutter/src/transport/transport.dart';
+
+final _nativeFinalizer =
+    NativeFinalizer(NekotonFlutter.instance().bindings.addresses.nt_emulated_transport_free_ptr);
+
+/// Local in-memory blockchain for offline development and tests.
+///
+/// Sent messages are executed immediately together with all internal messages they produce.
+/// [accounts] are base64 encoded accounts which exist from the start, e.g. givers.
+/// [config] is a base64 encoded blockchain config, the default one is used if not set.
+/// The default [globalId] is not used by any public network, so the emulator is never
+/// detected as one.
+class EmulatedTransport extends Transport implements Finalizable {
+  late final Pointer<Void> _ptr;
+  final int _globalId;
+
+  EmulatedTransport({
+    int globalId = 31337,
+    String? config,
+    List<String> accounts = const [],
+  }) : _globalId = globalId {
+    final params = jsonEncode({
+      'globalId': globalId,
+      if (config != null) 'config': config,
+      'accounts': accounts,
+    });
+
+    final result = executeSync(
+      () => NekotonFlutter.instance().bindings.nt_emulated_transport_create(
+            params.toNativeUtf8().cast<Char>(),
+          ),
+    );
+
+    _ptr = toPtrFromAddress(result as String);
+
+    _nativeFinalizer.attach(this, _ptr);
+  }
+
+  @override
+  Pointer<Void> get ptr => _ptr;
+
+  @override
+  String get name => 'emulated';
+
+  @override
+  int get networkId => _globalId;
+
+  @override
+  String get group => 'emulated';
+
+  @override
+  TransportType get type => TransportType.emulated;
+
+  /// Puts the account into the state replacing an existing one, returns its address.
+  String addAccount(String account) {
+    final result = executeSync(
+      () => NekotonFlutter.instance().bindings.nt_emulated_transport_add_account(
+            ptr,
+            account.toNativeUtf8().cast<Char>(),
+          ),
+    );
+
+    return result as String;
+  }
+
+  EmulatedBlock? get latestBlock {
+    final result = executeSync(
+      () => NekotonFlutter.instance().bindings.nt_emulated_transport_latest_block(ptr),
+    );
+
+    return result != null ? EmulatedBlock.fromJson(result as Map<String, dynamic>) : null;
+  }
+
+  @override
+  Future<void> dispose() async {}
+}
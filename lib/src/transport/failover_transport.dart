@@ -0,0 +1,67 @@
+import 'dart:convert';
+import 'dart:ffi';
+
+import 'package:ffi/ffi.dart';
+import 'package:nekoton_flutter/src/bindings.dart';
+import 'package:nekoton_flutter/src/ffi_utils.dart';
+import 'package:nekoton_flutter/src/transport/models/endpoint_status.dart';
+import 'package:nekoton_flutter/src/transport/models/transport_type.dart';
+import 'package:nekoton_flutter/src/transport/transport.dart';
+
+final _nativeFinalizer =
+    NativeFinalizer(NekotonFlutter.instance().bindings.addresses.nt_failover_transport_free_ptr);
+
+/// Routes every call to the healthiest of [transports] and falls back to the others on failure.
+///
+/// All transports must belong to the same network, name, network id and group are taken
+/// from the first one.
+class FailoverTransport extends Transport implements Finalizable {
+  late final Pointer<Void> _ptr;
+  final List<Transport> _transports;
+
+  FailoverTransport(this._transports) {
+    final transports = jsonEncode(_transports.map((e) => toAddressFromPtr(e.ptr)).toList());
+
+    final result = executeSync(
+      () => NekotonFlutter.instance().bindings.nt_failover_transport_create(
+            transports.toNativeUtf8().cast<Char>(),
+          ),
+    );
+
+    _ptr = toPtrFromAddress(result as String);
+
+    _nativeFinalizer.attach(this, _ptr);
+  }
+
+  @override
+  Pointer<Void> get ptr => _ptr;
+
+  @override
+  String get name => _transports.first.name;
+
+  @override
+  int get networkId => _transports.first.networkId;
+
+  @override
+  String get group => _transports.first.group;
+
+  @override
+  TransportType get type => TransportType.failover;
+
+  List<Transport> get transports => _transports;
+
+  /// Health of every transport in the order they were passed.
+  List<EndpointStatus> get status {
+    final result = executeSync(
+      () => NekotonFlutter.instance().bindings.nt_failover_transport_status(ptr),
+    );
+
+    return (result as List<dynamic>)
+        .cast<Map<String, dynamic>>()
+        .map((e) => EndpointStatus.fromJson(e))
+        .toList();
+  }
+
+  @override
+  Future<void> dispose() => Future.wait(_transports.map((e) => e.dispose()));
+}
@@ -0,0 +1,28 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+
+part 'accounts_stream_options.freezed.dart';
+part 'accounts_stream_options.g.dart';
+
+@freezed
+abstract class AccountsStreamOptions with _$AccountsStreamOptions {
+  const factory AccountsStreamOptions({
+    /// Address of the last received account, the enumeration starts after it.
+    String? continuation,
+
+    /// The enumeration stops once this many accounts were emitted.
+    int? limit,
+
+    /// Accounts per request, at most 255.
+    int? batchSize,
+
+    /// Fetch the state of every account.
+    @Default(false) bool withState,
+
+    /// Limits requests of this enumeration, not limited if not set.
+    double? requestsPerSecond,
+    int? burst,
+  }) = _AccountsStreamOptions;
+
+  factory AccountsStreamOptions.fromJson(Map<String, dynamic> json) =>
+      _$AccountsStreamOptionsFromJson(json);
+}
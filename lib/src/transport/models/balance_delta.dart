@@ -0,0 +1,15 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+
+part 'balance_delta.freezed.dart';
+part 'balance_delta.g.dart';
+
+@freezed
+abstract class BalanceDelta with _$BalanceDelta {
+  const factory BalanceDelta({
+    required String address,
+    required String delta,
+  }) = _BalanceDelta;
+
+  factory BalanceDelta.fromJson(Map<String, dynamic> json) =>
+      _$BalanceDeltaFromJson(json);
+}
@@ -0,0 +1,20 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+
+part 'bounced_message.freezed.dart';
+part 'bounced_message.g.dart';
+
+@freezed
+abstract class BouncedMessage with _$BouncedMessage {
+  const factory BouncedMessage({
+    /// Account which failed to process the message and sent it back.
+    required String src,
+    required String dst,
+    required String value,
+
+    /// Transaction which produced the bounced message.
+    required String transactionHash,
+  }) = _BouncedMessage;
+
+  factory BouncedMessage.fromJson(Map<String, dynamic> json) =>
+      _$BouncedMessageFromJson(json);
+}
@@ -0,0 +1,14 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+
+part 'cache_kind.g.dart';
+
+@JsonEnum(alwaysCreate: true)
+enum CacheKind {
+  contractStates,
+  transactions,
+  config,
+  capabilities;
+
+  @override
+  String toString() => _$CacheKindEnumMap[this]!;
+}
@@ -0,0 +1,26 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+
+part 'cache_stats.freezed.dart';
+part 'cache_stats.g.dart';
+
+@freezed
+abstract class CacheStats with _$CacheStats {
+  const factory CacheStats({
+    required CacheHits contractStates,
+    required CacheHits transactions,
+    required CacheHits config,
+    required CacheHits capabilities,
+  }) = _CacheStats;
+
+  factory CacheStats.fromJson(Map<String, dynamic> json) => _$CacheStatsFromJson(json);
+}
+
+@freezed
+abstract class CacheHits with _$CacheHits {
+  const factory CacheHits({
+    required int hits,
+    required int misses,
+  }) = _CacheHits;
+
+  factory CacheHits.fromJson(Map<String, dynamic> json) => _$CacheHitsFromJson(json);
+}
@@ -0,0 +1,18 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+import 'package:nekoton_flutter/src/core/models/full_contract_state.dart';
+
+part 'code_hash_account.freezed.dart';
+part 'code_hash_account.g.dart';
+
+@freezed
+abstract class CodeHashAccount with _$CodeHashAccount {
+  const factory CodeHashAccount({
+    required String address,
+
+    /// Only set with `withState`, null if the account was deleted meanwhile.
+    FullContractState? state,
+  }) = _CodeHashAccount;
+
+  factory CodeHashAccount.fromJson(Map<String, dynamic> json) =>
+      _$CodeHashAccountFromJson(json);
+}
@@ -0,0 +1,18 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+import 'package:nekoton_flutter/src/transport/models/code_hash_account.dart';
+
+part 'code_hash_accounts_batch.freezed.dart';
+part 'code_hash_accounts_batch.g.dart';
+
+@freezed
+abstract class CodeHashAccountsBatch with _$CodeHashAccountsBatch {
+  const factory CodeHashAccountsBatch({
+    required List<CodeHashAccount> accounts,
+
+    /// Address to resume the enumeration after this batch.
+    required String continuation,
+  }) = _CodeHashAccountsBatch;
+
+  factory CodeHashAccountsBatch.fromJson(Map<String, dynamic> json) =>
+      _$CodeHashAccountsBatchFromJson(json);
+}
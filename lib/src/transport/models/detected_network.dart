@@ -0,0 +1,22 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+import 'package:nekoton_flutter/src/transport/models/network_descriptor.dart';
+import 'package:nekoton_flutter/src/transport/models/network_status.dart';
+
+part 'detected_network.freezed.dart';
+part 'detected_network.g.dart';
+
+@freezed
+abstract class DetectedNetwork with _$DetectedNetwork {
+  const factory DetectedNetwork({
+    required int globalId,
+
+    /// Signature ID the network currently requires.
+    int? signatureId,
+    required NetworkStatus status,
+
+    /// Not set for unknown networks.
+    NetworkDescriptor? descriptor,
+  }) = _DetectedNetwork;
+
+  factory DetectedNetwork.fromJson(Map<String, dynamic> json) => _$DetectedNetworkFromJson(json);
+}
@@ -0,0 +1,17 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+
+part 'emulated_block.freezed.dart';
+part 'emulated_block.g.dart';
+
+@freezed
+abstract class EmulatedBlock with _$EmulatedBlock {
+  const factory EmulatedBlock({
+    required int seqno,
+    required int genUtime,
+    required String startLt,
+    required String endLt,
+    required List<String> transactions,
+  }) = _EmulatedBlock;
+
+  factory EmulatedBlock.fromJson(Map<String, dynamic> json) => _$EmulatedBlockFromJson(json);
+}
@@ -0,0 +1,17 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+
+part 'endpoint_status.freezed.dart';
+part 'endpoint_status.g.dart';
+
+@freezed
+abstract class EndpointStatus with _$EndpointStatus {
+  const factory EndpointStatus({
+    double? latencyMs,
+    required double errorRate,
+    int? genUtime,
+    required bool isHealthy,
+    required double score,
+  }) = _EndpointStatus;
+
+  factory EndpointStatus.fromJson(Map<String, dynamic> json) => _$EndpointStatusFromJson(json);
+}
@@ -0,0 +1,27 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+
+part 'fees_breakdown.freezed.dart';
+part 'fees_breakdown.g.dart';
+
+@freezed
+abstract class FeesBreakdown with _$FeesBreakdown {
+  const factory FeesBreakdown({
+    required String account,
+    required String storageFees,
+    required String gasFees,
+    required String gasUsed,
+    required String gasLimit,
+    required String forwardFees,
+    required String actionFees,
+    required String totalFees,
+
+    /// Value of all outgoing internal messages.
+    required String outgoingValue,
+
+    /// Transactions of the outgoing messages.
+    required List<FeesBreakdown> children,
+  }) = _FeesBreakdown;
+
+  factory FeesBreakdown.fromJson(Map<String, dynamic> json) =>
+      _$FeesBreakdownFromJson(json);
+}
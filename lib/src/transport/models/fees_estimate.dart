@@ -0,0 +1,22 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+import 'package:nekoton_flutter/src/transport/models/fees_breakdown.dart';
+
+part 'fees_estimate.freezed.dart';
+part 'fees_estimate.g.dart';
+
+@freezed
+abstract class FeesEstimate with _$FeesEstimate {
+  const factory FeesEstimate({
+    /// Fees of the transaction of the external message and its cascade.
+    required FeesBreakdown fees,
+
+    /// Balance of the message destination after the whole cascade.
+    required String balanceAfter,
+
+    /// Set when the cascade was cut by the transactions limit.
+    required bool isTruncated,
+  }) = _FeesEstimate;
+
+  factory FeesEstimate.fromJson(Map<String, dynamic> json) =>
+      _$FeesEstimateFromJson(json);
+}
@@ -1,5 +1,25 @@
+export 'accounts_stream_options.dart';
+export 'balance_delta.dart';
+export 'bounced_message.dart';
+export 'cache_kind.dart';
+export 'cache_stats.dart';
+export 'code_hash_account.dart';
+export 'code_hash_accounts_batch.dart';
+export 'detected_network.dart';
+export 'emulated_block.dart';
+export 'endpoint_status.dart';
 export 'connection_data.dart';
+export 'fees_breakdown.dart';
+export 'fees_estimate.dart';
+export 'native_currency.dart';
+export 'network_descriptor.dart';
+export 'network_status.dart';
+export 'sent_message_status.dart';
+export 'transactions_stream_options.dart';
 export 'transport_type.dart';
+export 'tx_tree_report.dart';
+export 'tx_tree_report_item.dart';
+export 'tx_tree_report_options.dart';
 export 'tx_tree_simulation_error.dart';
 export 'tx_tree_simulation_error_item.dart';
 export 'tx_tree_simulation_error_type.dart';
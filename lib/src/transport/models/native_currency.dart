@@ -0,0 +1,14 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+
+part 'native_currency.freezed.dart';
+part 'native_currency.g.dart';
+
+@freezed
+abstract class NativeCurrency with _$NativeCurrency {
+  const factory NativeCurrency({
+    required String symbol,
+    required int decimals,
+  }) = _NativeCurrency;
+
+  factory NativeCurrency.fromJson(Map<String, dynamic> json) => _$NativeCurrencyFromJson(json);
+}
@@ -0,0 +1,32 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+import 'package:nekoton_flutter/src/transport/models/native_currency.dart';
+
+part 'network_descriptor.freezed.dart';
+part 'network_descriptor.g.dart';
+
+@freezed
+abstract class NetworkDescriptor with _$NetworkDescriptor {
+  const factory NetworkDescriptor({
+    required String name,
+    required int globalId,
+
+    /// Group of the `AccountsStorage` assets of the network.
+    required String networkGroup,
+
+    /// Set when signatures on the network may include the signature ID.
+    required bool supportsSignatureId,
+    required NativeCurrency currency,
+
+    /// Template with an `{address}` placeholder.
+    String? accountExplorerUrl,
+
+    /// Template with a `{hash}` placeholder.
+    String? transactionExplorerUrl,
+
+    /// Manifest with the tokens which are shown by default.
+    String? tokenListUrl,
+  }) = _NetworkDescriptor;
+
+  factory NetworkDescriptor.fromJson(Map<String, dynamic> json) =>
+      _$NetworkDescriptorFromJson(json);
+}
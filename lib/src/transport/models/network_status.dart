@@ -0,0 +1,15 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+
+part 'network_status.g.dart';
+
+@JsonEnum(alwaysCreate: true)
+enum NetworkStatus {
+  builtin,
+
+  /// Registered by the app.
+  custom,
+  unknown;
+
+  @override
+  String toString() => _$NetworkStatusEnumMap[this]!;
+}
@@ -0,0 +1,15 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+import 'package:nekoton_flutter/src/core/models/transaction.dart';
+
+part 'sent_message_status.freezed.dart';
+part 'sent_message_status.g.dart';
+
+@Freezed(unionKey: 'type')
+abstract class SentMessageStatus with _$SentMessageStatus {
+  const factory SentMessageStatus.confirmed(Transaction data) = _Confirmed;
+
+  const factory SentMessageStatus.expired() = _Expired;
+
+  factory SentMessageStatus.fromJson(Map<String, dynamic> json) =>
+      _$SentMessageStatusFromJson(json);
+}
@@ -0,0 +1,31 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+
+part 'transactions_stream_options.freezed.dart';
+part 'transactions_stream_options.g.dart';
+
+@freezed
+abstract class TransactionsStreamOptions with _$TransactionsStreamOptions {
+  const factory TransactionsStreamOptions({
+    /// Lt of the newest transaction to start from, the latest transaction by default.
+    String? fromLt,
+
+    /// Expected hash of the [fromLt] transaction, requires [fromLt].
+    String? fromHash,
+
+    /// The walk stops at the first transaction older than this lt.
+    String? minLt,
+
+    /// The walk stops at the first transaction older than this time, in seconds.
+    int? minUtime,
+
+    /// Newer transactions are skipped, in seconds.
+    int? maxUtime,
+
+    /// The walk stops once this many transactions were emitted.
+    int? limit,
+    int? batchSize,
+  }) = _TransactionsStreamOptions;
+
+  factory TransactionsStreamOptions.fromJson(Map<String, dynamic> json) =>
+      _$TransactionsStreamOptionsFromJson(json);
+}
@@ -6,7 +6,11 @@ part 'transport_type.g.dart';
 enum TransportType {
   jrpc,
   gql,
-  proto;
+  proto,
+  emulated,
+  failover,
+  caching,
+  throttled;
 
   @override
   String toString() => _$TransportTypeEnumMap[this]!;
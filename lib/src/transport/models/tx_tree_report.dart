@@ -0,0 +1,26 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+import 'package:nekoton_flutter/src/transport/models/balance_delta.dart';
+import 'package:nekoton_flutter/src/transport/models/bounced_message.dart';
+import 'package:nekoton_flutter/src/transport/models/tx_tree_report_item.dart';
+
+part 'tx_tree_report.freezed.dart';
+part 'tx_tree_report.g.dart';
+
+@freezed
+abstract class TxTreeReport with _$TxTreeReport {
+  const factory TxTreeReport({
+    /// Transactions in the order they were executed.
+    required List<TxTreeReportItem> transactions,
+    required String totalFees,
+
+    /// Balance change of every account touched by the tree, including fees.
+    required List<BalanceDelta> balanceDeltas,
+    required List<BouncedMessage> bouncedMessages,
+
+    /// Set when transactions were left out because of the limits.
+    required bool isTruncated,
+  }) = _TxTreeReport;
+
+  factory TxTreeReport.fromJson(Map<String, dynamic> json) =>
+      _$TxTreeReportFromJson(json);
+}
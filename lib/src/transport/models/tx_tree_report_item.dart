@@ -0,0 +1,22 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+import 'package:nekoton_flutter/src/core/models/transaction.dart';
+import 'package:nekoton_flutter/src/helpers/abi/models/decoded_event.dart';
+import 'package:nekoton_flutter/src/helpers/abi/models/decoded_input.dart';
+
+part 'tx_tree_report_item.freezed.dart';
+part 'tx_tree_report_item.g.dart';
+
+@freezed
+abstract class TxTreeReportItem with _$TxTreeReportItem {
+  const factory TxTreeReportItem({
+    required int depth,
+    required String account,
+    required Transaction transaction,
+    DecodedInput? decodedInput,
+    required List<DecodedEvent> events,
+    required String balanceDelta,
+  }) = _TxTreeReportItem;
+
+  factory TxTreeReportItem.fromJson(Map<String, dynamic> json) =>
+      _$TxTreeReportItemFromJson(json);
+}
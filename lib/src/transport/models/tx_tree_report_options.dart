@@ -0,0 +1,21 @@
+import 'package:freezed_annotation/freezed_annotation.dart';
+
+part 'tx_tree_report_options.freezed.dart';
+part 'tx_tree_report_options.g.dart';
+
+@freezed
+abstract class TxTreeReportOptions with _$TxTreeReportOptions {
+  const factory TxTreeReportOptions({
+    /// ABIs to decode in-messages and events with, tried in order.
+    @Default(<String>[]) List<String> abis,
+
+    /// Deeper transactions are not simulated, the transaction of the external message is at 0.
+    int? maxDepth,
+
+    /// The simulation stops after this many transactions within [maxDepth], 100 by default.
+    int? maxTransactions,
+  }) = _TxTreeReportOptions;
+
+  factory TxTreeReportOptions.fromJson(Map<String, dynamic> json) =>
+      _$TxTreeReportOptionsFromJson(json);
+}
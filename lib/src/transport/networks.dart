@@ -0,0 +1,32 @@
+import 'dart:convert';
+import 'dart:ffi';
+
+import 'package:ffi/ffi.dart';
+import 'package:nekoton_flutter/src/bindings.dart';
+import 'package:nekoton_flutter/src/ffi_utils.dart';
+import 'package:nekoton_flutter/src/transport/models/network_descriptor.dart';
+
+/// Networks which [Transport.detectNetwork] recognizes, only Everscale is built in and
+/// other networks are added with [registerNetwork].
+List<NetworkDescriptor> getKnownNetworks() {
+  final result = executeSync(
+    () => NekotonFlutter.instance().bindings.nt_networks_get_all(),
+  );
+
+  final json = result as List<dynamic>;
+  final list = json.cast<Map<String, dynamic>>();
+  final networks = list.map((e) => NetworkDescriptor.fromJson(e)).toList();
+
+  return networks;
+}
+
+/// Adds a custom network, a network with the same global ID is replaced.
+void registerNetwork(NetworkDescriptor descriptor) {
+  final descriptorStr = jsonEncode(descriptor);
+
+  executeSync(
+    () => NekotonFlutter.instance().bindings.nt_networks_register(
+          descriptorStr.toNativeUtf8().cast<Char>(),
+        ),
+  );
+}
@@ -0,0 +1,69 @@
+import 'dart:convert';
+import 'dart:ffi';
+
+import 'package:ffi/ffi.dart';
+import 'package:nekoton_flutter/src/bindings.dart';
+import 'package:nekoton_flutter/src/ffi_utils.dart';
+import 'package:nekoton_flutter/src/transport/models/transport_type.dart';
+import 'package:nekoton_flutter/src/transport/transport.dart';
+
+final _nativeFinalizer =
+    NativeFinalizer(NekotonFlutter.instance().bindings.addresses.nt_throttled_transport_free_ptr);
+
+/// Merges identical in-flight requests to [transport] and limits their rate with a token bucket
+/// refilled at [requestsPerSecond] and holding up to [burst] tokens. [requestsPerSecond] must be
+/// at least 0.001.
+///
+/// Sent messages and reads made through this transport, e.g. [getContractState], are served
+/// before queued background requests like polling of subscriptions. When more than [maxQueued]
+/// requests are waiting, new ones fail with the `rateLimited` error code.
+class ThrottledTransport extends Transport implements Finalizable {
+  late final Pointer<Void> _ptr;
+  final Transport _transport;
+
+  ThrottledTransport(
+    this._transport, {
+    bool coalesce = true,
+    double? requestsPerSecond,
+    int? burst,
+    int? maxQueued,
+  }) {
+    final config = jsonEncode({
+      'coalesce': coalesce,
+      if (requestsPerSecond != null) 'requestsPerSecond': requestsPerSecond,
+      if (burst != null) 'burst': burst,
+      if (maxQueued != null) 'maxQueued': maxQueued,
+    });
+
+    final result = executeSync(
+      () => NekotonFlutter.instance().bindings.nt_throttled_transport_create(
+            _transport.ptr,
+            config.toNativeUtf8().cast<Char>(),
+          ),
+    );
+
+    _ptr = toPtrFromAddress(result as String);
+
+    _nativeFinalizer.attach(this, _ptr);
+  }
+
+  @override
+  Pointer<Void> get ptr => _ptr;
+
+  @override
+  String get name => _transport.name;
+
+  @override
+  int get networkId => _transport.networkId;
+
+  @override
+  String get group => _transport.group;
+
+  @override
+  TransportType get type => TransportType.throttled;
+
+  Transport get transport => _transport;
+
+  @override
+  Future<void> dispose() => _transport.dispose();
+}
@@ -1,6 +1,7 @@
 import 'dart:async';
 import 'dart:convert';
 import 'dart:ffi';
+import 'dart:isolate';
 import 'dart:typed_data';
 
 import 'package:ffi/ffi.dart';
@@ -11,8 +12,18 @@ import 'package:nekoton_flutter/src/core/models/raw_contract_state.dart';
 import 'package:nekoton_flutter/src/core/models/transaction.dart';
 import 'package:nekoton_flutter/src/core/models/transactions_list.dart';
 import 'package:nekoton_flutter/src/crypto/models/signed_message.dart';
+import 'package:nekoton_flutter/src/crypto/unsigned_message.dart';
 import 'package:nekoton_flutter/src/ffi_utils.dart';
+import 'package:nekoton_flutter/src/models/nekoton_exception.dart';
+import 'package:nekoton_flutter/src/transport/models/accounts_stream_options.dart';
+import 'package:nekoton_flutter/src/transport/models/code_hash_accounts_batch.dart';
+import 'package:nekoton_flutter/src/transport/models/detected_network.dart';
+import 'package:nekoton_flutter/src/transport/models/fees_estimate.dart';
+import 'package:nekoton_flutter/src/transport/models/sent_message_status.dart';
+import 'package:nekoton_flutter/src/transport/models/transactions_stream_options.dart';
 import 'package:nekoton_flutter/src/transport/models/transport_type.dart';
+import 'package:nekoton_flutter/src/transport/models/tx_tree_report.dart';
+import 'package:nekoton_flutter/src/transport/models/tx_tree_report_options.dart';
 import 'package:nekoton_flutter/src/transport/models/tx_tree_simulation_error_item.dart';
 
 abstract class Transport {
@@ -27,14 +38,11 @@ abstract class Transport {
   TransportType get type;
 
   Future<RawContractState> getContractState(String address) async {
-    final transportTypeStr = jsonEncode(type.toString());
-
     final result = await executeAsync(
       (port) =>
           NekotonFlutter.instance().bindings.nt_transport_get_contract_state(
                 port,
                 ptr,
-                transportTypeStr.toNativeUtf8().cast<Char>(),
                 address.toNativeUtf8().cast<Char>(),
               ),
     );
@@ -46,15 +54,12 @@ abstract class Transport {
   }
 
   Future<FullContractState?> getFullContractState(String address) async {
-    final transportTypeStr = jsonEncode(type.toString());
-
     final result = await executeAsync(
       (port) => NekotonFlutter.instance()
           .bindings
           .nt_transport_get_full_contract_state(
             port,
             ptr,
-            transportTypeStr.toNativeUtf8().cast<Char>(),
             address.toNativeUtf8().cast<Char>(),
           ),
     );
@@ -71,15 +76,12 @@ abstract class Transport {
     required int limit,
     String? continuation,
   }) async {
-    final transportTypeStr = jsonEncode(type.toString());
-
     final result = await executeAsync(
       (port) => NekotonFlutter.instance()
           .bindings
           .nt_transport_get_accounts_by_code_hash(
             port,
             ptr,
-            transportTypeStr.toNativeUtf8().cast<Char>(),
             codeHash.toNativeUtf8().cast<Char>(),
             limit,
             continuation?.toNativeUtf8().cast<Char>() ?? nullptr,
@@ -97,14 +99,11 @@ abstract class Transport {
     String? fromLt,
     required int limit,
   }) async {
-    final transportTypeStr = jsonEncode(type.toString());
-
     final result = await executeAsync(
       (port) =>
           NekotonFlutter.instance().bindings.nt_transport_get_transactions(
                 port,
                 ptr,
-                transportTypeStr.toNativeUtf8().cast<Char>(),
                 address.toNativeUtf8().cast<Char>(),
                 fromLt?.toNativeUtf8().cast<Char>() ?? nullptr,
                 limit,
@@ -118,13 +117,10 @@ abstract class Transport {
   }
 
   Future<Transaction?> getTransaction(String hash) async {
-    final transportTypeStr = jsonEncode(type.toString());
-
     final result = await executeAsync(
       (port) => NekotonFlutter.instance().bindings.nt_transport_get_transaction(
             port,
             ptr,
-            transportTypeStr.toNativeUtf8().cast<Char>(),
             hash.toNativeUtf8().cast<Char>(),
           ),
     );
@@ -136,14 +132,11 @@ abstract class Transport {
   }
 
   Future<String?> getSignatureId() async {
-    final transportTypeStr = jsonEncode(type.toString());
-
     final result = await executeAsync(
       (port) =>
           NekotonFlutter.instance().bindings.nt_transport_get_signature_id(
                 port,
                 ptr,
-                transportTypeStr.toNativeUtf8().cast<Char>(),
               ),
     );
     final value = result as int?;
@@ -152,25 +145,36 @@ abstract class Transport {
   }
 
   Future<int> getNetworkId() async {
-    final transportTypeStr = jsonEncode(type.toString());
-
     final result = await executeAsync(
       (port) => NekotonFlutter.instance().bindings.nt_transport_get_network_id(
             port,
             ptr,
-            transportTypeStr.toNativeUtf8().cast<Char>(),
           ),
     );
     final value = result as int;
     return value;
   }
 
+  /// Matches the network of the transport against [getKnownNetworks].
+  Future<DetectedNetwork> detectNetwork() async {
+    final result = await executeAsync(
+      (port) => NekotonFlutter.instance().bindings.nt_transport_detect_network(
+            port,
+            ptr,
+          ),
+    );
+
+    final json = result as Map<String, dynamic>;
+    final network = DetectedNetwork.fromJson(json);
+
+    return network;
+  }
+
   Future<List<TxTreeSimulationErrorItem>> simulateTransactionTree({
     required SignedMessage signedMessage,
     required Int32List ignoredComputePhaseCodes,
     required Int32List ignoredActionPhaseCodes,
   }) async {
-    final transportTypeStr = jsonEncode(type.toString());
     final signedMessageStr = jsonEncode(signedMessage);
     final ignoredComputePhaseCodesStr = jsonEncode(ignoredComputePhaseCodes);
     final ignoredActionPhaseCodesStr = jsonEncode(ignoredActionPhaseCodes);
@@ -181,7 +185,6 @@ abstract class Transport {
           .nt_transport_simulate_transaction_tree(
             port,
             ptr,
-            transportTypeStr.toNativeUtf8().cast<Char>(),
             signedMessageStr.toNativeUtf8().cast<Char>(),
             ignoredComputePhaseCodesStr.toNativeUtf8().cast<Char>(),
             ignoredActionPhaseCodesStr.toNativeUtf8().cast<Char>(),
@@ -197,5 +200,226 @@ abstract class Transport {
         .toList();
   }
 
+  /// Estimates fees of [unsignedMessage] and of every transaction in its cascade. The message
+  /// is signed with a dummy signature, so no key is needed for the preview. The simulation of
+  /// the cascade stops after [maxTransactions], 100 by default.
+  Future<FeesEstimate> estimateFeesBreakdown(
+    UnsignedMessage unsignedMessage, {
+    int? maxTransactions,
+  }) async {
+    final options = jsonEncode({
+      if (maxTransactions != null) 'maxTransactions': maxTransactions,
+    });
+
+    final result = await executeAsync(
+      (port) => NekotonFlutter.instance().bindings.nt_transport_estimate_fees_breakdown(
+            port,
+            ptr,
+            unsignedMessage.ptr,
+            options.toNativeUtf8().cast<Char>(),
+          ),
+    );
+
+    final json = result as Map<String, dynamic>;
+    final estimate = FeesEstimate.fromJson(json);
+
+    return estimate;
+  }
+
+  /// Simulates the whole tree of transactions produced by [signedMessage] without
+  /// sending it, e.g. to show the cascade to the user before signing.
+  Future<TxTreeReport> simulateTransactionTreeReport({
+    required SignedMessage signedMessage,
+    TxTreeReportOptions options = const TxTreeReportOptions(),
+  }) async {
+    final signedMessageStr = jsonEncode(signedMessage);
+    final optionsStr = jsonEncode(options);
+
+    final result = await executeAsync(
+      (port) => NekotonFlutter.instance()
+          .bindings
+          .nt_transport_simulate_transaction_tree_report(
+            port,
+            ptr,
+            signedMessageStr.toNativeUtf8().cast<Char>(),
+            optionsStr.toNativeUtf8().cast<Char>(),
+          ),
+    );
+
+    final json = result as Map<String, dynamic>;
+    final report = TxTreeReport.fromJson(json);
+
+    return report;
+  }
+
+  /// Sends [signedMessage] and waits for its transaction on the destination account
+  /// until the message expires, no subscription is required.
+  Future<SentMessageStatus> sendAndConfirm(SignedMessage signedMessage) async {
+    final signedMessageStr = jsonEncode(signedMessage);
+
+    final result = await executeAsync(
+      (port) => NekotonFlutter.instance().bindings.nt_transport_send_and_confirm(
+            port,
+            ptr,
+            signedMessageStr.toNativeUtf8().cast<Char>(),
+          ),
+    );
+
+    final json = result as Map<String, dynamic>;
+    final status = SentMessageStatus.fromJson(json);
+
+    return status;
+  }
+
+  /// Enumerates all accounts with [codeHash], batches are emitted as soon as they are
+  /// fetched. The enumeration ends after the last page of accounts.
+  ///
+  /// Cancelling the subscription stops the enumeration.
+  Stream<CodeHashAccountsBatch> streamAccountsByCodeHash({
+    required String codeHash,
+    AccountsStreamOptions options = const AccountsStreamOptions(),
+  }) {
+    final batchPort = ReceivePort();
+    int? operationId;
+
+    late final StreamController<CodeHashAccountsBatch> controller;
+    controller = StreamController<CodeHashAccountsBatch>(
+      onListen: () {
+        batchPort.cast<String>().listen((e) {
+          final json = jsonDecode(e) as Map<String, dynamic>;
+          controller.add(CodeHashAccountsBatch.fromJson(json));
+        });
+
+        final optionsStr = jsonEncode(options);
+
+        executeAsync(
+          (port) => NekotonFlutter.instance()
+              .bindings
+              .nt_transport_stream_accounts_by_code_hash(
+                port,
+                batchPort.sendPort.nativePort,
+                ptr,
+                codeHash.toNativeUtf8().cast<Char>(),
+                optionsStr.toNativeUtf8().cast<Char>(),
+              ),
+          onStarted: (id) => operationId = id,
+        ).catchError(
+          (Object err, StackTrace st) {
+            if (err is! OperationCancelledException) controller.addError(err, st);
+          },
+        ).whenComplete(() {
+          batchPort.close();
+          controller.close();
+        });
+      },
+      onCancel: () {
+        final id = operationId;
+        if (id != null) cancelOperation(id);
+      },
+    );
+
+    return controller.stream;
+  }
+
+  /// Walks the transaction history of [address] from the newest transaction down,
+  /// pages are emitted as soon as they are fetched.
+  ///
+  /// Cancelling the subscription stops the walk.
+  Stream<TransactionsList> streamTransactions({
+    required String address,
+    TransactionsStreamOptions options = const TransactionsStreamOptions(),
+  }) {
+    final batchPort = ReceivePort();
+    int? operationId;
+
+    late final StreamController<TransactionsList> controller;
+    controller = StreamController<TransactionsList>(
+      onListen: () {
+        batchPort.cast<String>().listen((e) {
+          final json = jsonDecode(e) as Map<String, dynamic>;
+          controller.add(TransactionsList.fromJson(json));
+        });
+
+        final optionsStr = jsonEncode(options);
+
+        executeAsync(
+          (port) => NekotonFlutter.instance().bindings.nt_transport_stream_transactions(
+                port,
+                batchPort.sendPort.nativePort,
+                ptr,
+                address.toNativeUtf8().cast<Char>(),
+                optionsStr.toNativeUtf8().cast<Char>(),
+              ),
+          onStarted: (id) => operationId = id,
+        ).catchError(
+          (Object err, StackTrace st) {
+            if (err is! OperationCancelledException) controller.addError(err, st);
+          },
+        ).whenComplete(() {
+          batchPort.close();
+          controller.close();
+        });
+      },
+      onCancel: () {
+        final id = operationId;
+        if (id != null) cancelOperation(id);
+      },
+    );
+
+    return controller.stream;
+  }
+
+  /// Block ids and blocks for block-driven updates of subscriptions, see `handleBlock`.
+  ///
+  /// Transports without block APIs emulate them by polling transactions of the address,
+  /// their blocks only contain transactions of that address.
+  Future<String> getLatestBlockId(String address) async {
+    final result = await executeAsync(
+      (port) => NekotonFlutter.instance().bindings.nt_transport_get_latest_block_id(
+            port,
+            ptr,
+            address.toNativeUtf8().cast<Char>(),
+          ),
+    );
+
+    final id = result as String;
+
+    return id;
+  }
+
+  Future<String> getBlock(String id) async {
+    final result = await executeAsync(
+      (port) => NekotonFlutter.instance().bindings.nt_transport_get_block(
+            port,
+            ptr,
+            id.toNativeUtf8().cast<Char>(),
+          ),
+    );
+
+    final block = result as String;
+
+    return block;
+  }
+
+  Future<String> waitForNextBlockId({
+    required String currentBlockId,
+    required String address,
+    required int timeout,
+  }) async {
+    final result = await executeAsync(
+      (port) => NekotonFlutter.instance().bindings.nt_transport_wait_for_next_block_id(
+            port,
+            ptr,
+            currentBlockId.toNativeUtf8().cast<Char>(),
+            address.toNativeUtf8().cast<Char>(),
+            timeout,
+          ),
+    );
+
+    final id = result as String;
+
+    return id;
+  }
+
   Future<void> dispose();
 }
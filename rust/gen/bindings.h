@@ -1,3 +1,5 @@
+typedef bool (*HostCallback)(long long port, const char *payload);
+
 void nt_store_dart_post_cobject(void *ptr);
 
 void *nt_cstring_to_void_ptr(char *ptr);
@@ -8,6 +10,18 @@ void nt_free_cstring(char *ptr);
 
 void nt_init_logging(void);
 
+char *nt_debug_live_handles(void);
+
+void nt_cancel_operation(unsigned long long id);
+
+char *nt_runtime_init(char *config);
+
+void nt_runtime_shutdown(unsigned long long timeout_ms);
+
+char *nt_set_host_callbacks(HostCallback post_result,
+                            HostCallback post_event,
+                            HostCallback post_request);
+
 void nt_channel_err_free_ptr(void *ptr);
 
 void nt_channel_option_free_ptr(void *ptr);
@@ -16,105 +30,104 @@ void nt_channel_result_option_free_ptr(void *ptr);
 
 void nt_channel_result_unit_free_ptr(void *ptr);
 
-void nt_accounts_storage_create(long long result_port, void *storage);
+unsigned long long nt_accounts_storage_create(long long result_port, void *storage);
 
-void nt_accounts_storage_entries(long long result_port, void *accounts_storage);
+unsigned long long nt_accounts_storage_entries(long long result_port, void *accounts_storage);
 
-void nt_accounts_storage_add_account(long long result_port,
+unsigned long long nt_accounts_storage_add_account(long long result_port,
                                      void *accounts_storage,
                                      char *new_account);
 
-void nt_accounts_storage_add_accounts(long long result_port,
+unsigned long long nt_accounts_storage_add_accounts(long long result_port,
                                       void *accounts_storage,
                                       char *new_accounts);
 
-void nt_accounts_storage_rename_account(long long result_port,
+unsigned long long nt_accounts_storage_rename_account(long long result_port,
                                         void *accounts_storage,
                                         char *account,
                                         char *name);
 
-void nt_accounts_storage_add_token_wallet(long long result_port,
+unsigned long long nt_accounts_storage_add_token_wallet(long long result_port,
                                           void *accounts_storage,
                                           char *account,
                                           char *network_group,
                                           char *root_token_contract);
 
-void nt_accounts_storage_remove_token_wallet(long long result_port,
+unsigned long long nt_accounts_storage_remove_token_wallet(long long result_port,
                                              void *accounts_storage,
                                              char *account,
                                              char *network_group,
                                              char *root_token_contract);
 
-void nt_accounts_storage_remove_account(long long result_port,
+unsigned long long nt_accounts_storage_remove_account(long long result_port,
                                         void *accounts_storage,
                                         char *account);
 
-void nt_accounts_storage_remove_accounts(long long result_port,
+unsigned long long nt_accounts_storage_remove_accounts(long long result_port,
                                          void *accounts_storage,
                                          char *accounts);
 
-void nt_accounts_storage_clear(long long result_port, void *accounts_storage);
+unsigned long long nt_accounts_storage_clear(long long result_port, void *accounts_storage);
 
-void nt_accounts_storage_reload(long long result_port, void *accounts_storage);
+unsigned long long nt_accounts_storage_reload(long long result_port, void *accounts_storage);
 
 char *nt_accounts_storage_verify_data(char *data);
 
 void nt_accounts_storage_free_ptr(void *ptr);
 
-void nt_generic_contract_subscribe(long long result_port,
+unsigned long long nt_generic_contract_subscribe(long long result_port,
                                    long long on_message_sent_port,
                                    long long on_message_expired_port,
                                    long long on_state_changed_port,
                                    long long on_transactions_found_port,
                                    void *transport,
-                                   char *transport_type,
                                    char *address,
                                    unsigned int preload_transactions);
 
-void nt_generic_contract_address(long long result_port, void *generic_contract);
+unsigned long long nt_generic_contract_address(long long result_port, void *generic_contract);
 
-void nt_generic_contract_contract_state(long long result_port, void *generic_contract);
+unsigned long long nt_generic_contract_contract_state(long long result_port, void *generic_contract);
 
-void nt_generic_contract_pending_transactions(long long result_port, void *generic_contract);
+unsigned long long nt_generic_contract_pending_transactions(long long result_port, void *generic_contract);
 
-void nt_generic_contract_polling_method(long long result_port, void *generic_contract);
+unsigned long long nt_generic_contract_polling_method(long long result_port, void *generic_contract);
 
-void nt_generic_contract_estimate_fees(long long result_port,
+unsigned long long nt_generic_contract_estimate_fees(long long result_port,
                                        void *generic_contract,
                                        char *signed_message);
 
-void nt_generic_contract_send(long long result_port, void *generic_contract, char *signed_message);
+unsigned long long nt_generic_contract_send(long long result_port, void *generic_contract, char *signed_message);
 
-void nt_generic_contract_execute_transaction_locally(long long result_port,
+unsigned long long nt_generic_contract_execute_transaction_locally(long long result_port,
                                                      void *generic_contract,
                                                      char *signed_message,
                                                      char *options);
 
-void nt_generic_contract_refresh(long long result_port, void *generic_contract);
+unsigned long long nt_generic_contract_refresh(long long result_port, void *generic_contract);
 
-void nt_generic_contract_preload_transactions(long long result_port,
+unsigned long long nt_generic_contract_preload_transactions(long long result_port,
                                               void *generic_contract,
                                               char *from_lt);
 
-void nt_generic_contract_handle_block(long long result_port, void *generic_contract, char *block);
+unsigned long long nt_generic_contract_handle_block(long long result_port, void *generic_contract, char *block);
 
 void nt_generic_contract_free_ptr(void *ptr);
 
-void nt_keystore_create(long long result_port, void *storage, void *connection, char *signers);
+unsigned long long nt_keystore_create(long long result_port, void *storage, void *connection, char *signers);
 
-void nt_keystore_entries(long long result_port, void *keystore);
+unsigned long long nt_keystore_entries(long long result_port, void *keystore);
 
-void nt_keystore_add_key(long long result_port, void *keystore, char *signer, char *input);
+unsigned long long nt_keystore_add_key(long long result_port, void *keystore, char *signer, char *input);
 
-void nt_keystore_add_keys(long long result_port, void *keystore, char *signer, char *input);
+unsigned long long nt_keystore_add_keys(long long result_port, void *keystore, char *signer, char *input);
 
-void nt_keystore_update_key(long long result_port, void *keystore, char *signer, char *input);
+unsigned long long nt_keystore_update_key(long long result_port, void *keystore, char *signer, char *input);
 
-void nt_keystore_export_key(long long result_port, void *keystore, char *signer, char *input);
+unsigned long long nt_keystore_export_key(long long result_port, void *keystore, char *signer, char *input);
 
-void nt_keystore_get_public_keys(long long result_port, void *keystore, char *signer, char *input);
+unsigned long long nt_keystore_get_public_keys(long long result_port, void *keystore, char *signer, char *input);
 
-void nt_keystore_encrypt(long long result_port,
+unsigned long long nt_keystore_encrypt(long long result_port,
                          void *keystore,
                          char *signer,
                          char *data,
@@ -122,77 +135,84 @@ void nt_keystore_encrypt(long long result_port,
                          char *algorithm,
                          char *input);
 
-void nt_keystore_decrypt(long long result_port,
+unsigned long long nt_keystore_decrypt(long long result_port,
                          void *keystore,
                          char *signer,
                          char *data,
                          char *input);
 
-void nt_keystore_sign(long long result_port,
+unsigned long long nt_keystore_sign(long long result_port,
                       void *keystore,
                       char *signer,
                       char *data,
                       char *input,
                       char *signature_id);
 
-void nt_keystore_sign_data(long long result_port,
+unsigned long long nt_keystore_sign_data(long long result_port,
                            void *keystore,
                            char *signer,
                            char *data,
                            char *input,
                            char *signature_id);
 
-void nt_keystore_sign_data_raw(long long result_port,
+unsigned long long nt_keystore_sign_data_raw(long long result_port,
                                void *keystore,
                                char *signer,
                                char *data,
                                char *input,
                                char *signature_id);
 
-void nt_keystore_remove_key(long long result_port, void *keystore, char *public_key);
+unsigned long long nt_keystore_remove_key(long long result_port, void *keystore, char *public_key);
 
-void nt_keystore_remove_keys(long long result_port, void *keystore, char *public_keys);
+unsigned long long nt_keystore_remove_keys(long long result_port, void *keystore, char *public_keys);
 
 char *nt_keystore_is_password_cached(void *keystore, char *public_key, unsigned long long duration);
 
-void nt_keystore_clear(long long result_port, void *keystore);
+unsigned long long nt_keystore_clear(long long result_port, void *keystore);
 
-void nt_keystore_reload(long long result_port, void *keystore);
+unsigned long long nt_keystore_reload(long long result_port, void *keystore);
 
 char *nt_keystore_verify_data(void *connection, char *signers, char *data);
 
 void nt_keystore_free_ptr(void *ptr);
 
-void nt_storage_impl_free_ptr(void *ptr);
+char *nt_subscription_scheduler_create(void *transport, char *config);
 
-void nt_token_wallet_subscribe(long long result_port,
+char *nt_subscription_scheduler_add(void *subscription_scheduler, void *subscription);
+
+char *nt_subscription_scheduler_remove(void *subscription_scheduler, void *subscription);
+
+unsigned long long nt_subscription_scheduler_run(long long result_port, void *subscription_scheduler);
+
+void nt_subscription_scheduler_free_ptr(void *ptr);
+
+unsigned long long nt_token_wallet_subscribe(long long result_port,
                                long long on_balance_changed_port,
                                long long on_transactions_found_port,
                                void *transport,
-                               char *transport_type,
                                char *owner,
                                char *root_token_contract);
 
-void nt_token_wallet_owner(long long result_port, void *token_wallet);
+unsigned long long nt_token_wallet_owner(long long result_port, void *token_wallet);
 
-void nt_token_wallet_address(long long result_port, void *token_wallet);
+unsigned long long nt_token_wallet_address(long long result_port, void *token_wallet);
 
-void nt_token_wallet_symbol(long long result_port, void *token_wallet);
+unsigned long long nt_token_wallet_symbol(long long result_port, void *token_wallet);
 
-void nt_token_wallet_version(long long result_port, void *token_wallet);
+unsigned long long nt_token_wallet_version(long long result_port, void *token_wallet);
 
-void nt_token_wallet_balance(long long result_port, void *token_wallet);
+unsigned long long nt_token_wallet_balance(long long result_port, void *token_wallet);
 
-void nt_token_wallet_contract_state(long long result_port, void *token_wallet);
+unsigned long long nt_token_wallet_contract_state(long long result_port, void *token_wallet);
 
-void nt_token_wallet_estimate_min_attached_amount(long long result_port,
+unsigned long long nt_token_wallet_estimate_min_attached_amount(long long result_port,
                                                   void *token_wallet,
                                                   char *destination,
                                                   char *amount,
                                                   unsigned int notify_receiver,
                                                   char *payload);
 
-void nt_token_wallet_prepare_transfer(long long result_port,
+unsigned long long nt_token_wallet_prepare_transfer(long long result_port,
                                       void *token_wallet,
                                       char *destination,
                                       char *tokens,
@@ -200,87 +220,81 @@ void nt_token_wallet_prepare_transfer(long long result_port,
                                       char *attached_amount,
                                       char *payload);
 
-void nt_token_wallet_refresh(long long result_port, void *token_wallet);
+unsigned long long nt_token_wallet_refresh(long long result_port, void *token_wallet);
 
-void nt_token_wallet_preload_transactions(long long result_port, void *token_wallet, char *from_lt);
+unsigned long long nt_token_wallet_preload_transactions(long long result_port, void *token_wallet, char *from_lt);
 
-void nt_token_wallet_handle_block(long long result_port, void *token_wallet, char *block);
+unsigned long long nt_token_wallet_handle_block(long long result_port, void *token_wallet, char *block);
 
-void nt_get_token_root_details(long long result_port,
+unsigned long long nt_get_token_root_details(long long result_port,
                                void *transport,
-                               char *transport_type,
                                char *root_token_contract);
 
-void nt_get_token_wallet_details(long long result_port,
+unsigned long long nt_get_token_wallet_details(long long result_port,
                                  void *transport,
-                                 char *transport_type,
                                  char *token_wallet);
 
-void nt_get_token_root_details_from_token_wallet(long long result_port,
+unsigned long long nt_get_token_root_details_from_token_wallet(long long result_port,
                                                  void *transport,
-                                                 char *transport_type,
                                                  char *token_wallet_address);
 
 void nt_token_wallet_free_ptr(void *ptr);
 
-void nt_ton_wallet_subscribe(long long result_port,
+unsigned long long nt_ton_wallet_subscribe(long long result_port,
                              long long on_message_sent_port,
                              long long on_message_expired_port,
                              long long on_state_changed_port,
                              long long on_transactions_found_port,
                              void *transport,
-                             char *transport_type,
                              signed char workchain,
                              char *public_key,
                              char *contract);
 
-void nt_ton_wallet_subscribe_by_address(long long result_port,
+unsigned long long nt_ton_wallet_subscribe_by_address(long long result_port,
                                         long long on_message_sent_port,
                                         long long on_message_expired_port,
                                         long long on_state_changed_port,
                                         long long on_transactions_found_port,
                                         void *transport,
-                                        char *transport_type,
                                         char *address);
 
-void nt_ton_wallet_subscribe_by_existing(long long result_port,
+unsigned long long nt_ton_wallet_subscribe_by_existing(long long result_port,
                                          long long on_message_sent_port,
                                          long long on_message_expired_port,
                                          long long on_state_changed_port,
                                          long long on_transactions_found_port,
                                          void *transport,
-                                         char *transport_type,
                                          char *existing_wallet);
 
-void nt_ton_wallet_workchain(long long result_port, void *ton_wallet);
+unsigned long long nt_ton_wallet_workchain(long long result_port, void *ton_wallet);
 
-void nt_ton_wallet_address(long long result_port, void *ton_wallet);
+unsigned long long nt_ton_wallet_address(long long result_port, void *ton_wallet);
 
-void nt_ton_wallet_public_key(long long result_port, void *ton_wallet);
+unsigned long long nt_ton_wallet_public_key(long long result_port, void *ton_wallet);
 
-void nt_ton_wallet_wallet_type(long long result_port, void *ton_wallet);
+unsigned long long nt_ton_wallet_wallet_type(long long result_port, void *ton_wallet);
 
-void nt_ton_wallet_contract_state(long long result_port, void *ton_wallet);
+unsigned long long nt_ton_wallet_contract_state(long long result_port, void *ton_wallet);
 
-void nt_ton_wallet_pending_transactions(long long result_port, void *ton_wallet);
+unsigned long long nt_ton_wallet_pending_transactions(long long result_port, void *ton_wallet);
 
-void nt_ton_wallet_polling_method(long long result_port, void *ton_wallet);
+unsigned long long nt_ton_wallet_polling_method(long long result_port, void *ton_wallet);
 
-void nt_ton_wallet_details(long long result_port, void *ton_wallet);
+unsigned long long nt_ton_wallet_details(long long result_port, void *ton_wallet);
 
-void nt_ton_wallet_unconfirmed_transactions(long long result_port, void *ton_wallet);
+unsigned long long nt_ton_wallet_unconfirmed_transactions(long long result_port, void *ton_wallet);
 
-void nt_ton_wallet_custodians(long long result_port, void *ton_wallet);
+unsigned long long nt_ton_wallet_custodians(long long result_port, void *ton_wallet);
 
-void nt_ton_wallet_prepare_deploy(long long result_port, void *ton_wallet, char *expiration);
+unsigned long long nt_ton_wallet_prepare_deploy(long long result_port, void *ton_wallet, char *expiration);
 
-void nt_ton_wallet_prepare_deploy_with_multiple_owners(long long result_port,
+unsigned long long nt_ton_wallet_prepare_deploy_with_multiple_owners(long long result_port,
                                                        void *ton_wallet,
                                                        char *expiration,
                                                        char *custodians,
                                                        unsigned char req_confirms);
 
-void nt_ton_wallet_prepare_transfer(long long result_port,
+unsigned long long nt_ton_wallet_prepare_transfer(long long result_port,
                                     void *ton_wallet,
                                     char *contract_state,
                                     char *public_key,
@@ -290,51 +304,48 @@ void nt_ton_wallet_prepare_transfer(long long result_port,
                                     char *body,
                                     char *expiration);
 
-void nt_ton_wallet_prepare_confirm_transaction(long long result_port,
+unsigned long long nt_ton_wallet_prepare_confirm_transaction(long long result_port,
                                                void *ton_wallet,
                                                char *contract_state,
                                                char *public_key,
                                                char *transaction_id,
                                                char *expiration);
 
-void nt_ton_wallet_estimate_fees(long long result_port, void *ton_wallet, char *signed_message);
+unsigned long long nt_ton_wallet_estimate_fees(long long result_port, void *ton_wallet, char *signed_message);
 
-void nt_ton_wallet_send(long long result_port, void *ton_wallet, char *signed_message);
+unsigned long long nt_ton_wallet_send(long long result_port, void *ton_wallet, char *signed_message);
 
-void nt_ton_wallet_refresh(long long result_port, void *ton_wallet);
+unsigned long long nt_ton_wallet_refresh(long long result_port, void *ton_wallet);
 
-void nt_ton_wallet_preload_transactions(long long result_port, void *ton_wallet, char *from_lt);
+unsigned long long nt_ton_wallet_preload_transactions(long long result_port, void *ton_wallet, char *from_lt);
 
-void nt_ton_wallet_handle_block(long long result_port, void *ton_wallet, char *block);
+unsigned long long nt_ton_wallet_handle_block(long long result_port, void *ton_wallet, char *block);
 
-void nt_find_existing_wallets(long long result_port,
+unsigned long long nt_find_existing_wallets(long long result_port,
                               void *transport,
-                              char *transport_type,
                               char *public_key,
                               signed char workchain_id,
                               char *wallet_types);
 
-void nt_get_existing_wallet_info(long long result_port,
+unsigned long long nt_get_existing_wallet_info(long long result_port,
                                  void *transport,
-                                 char *transport_type,
                                  char *address);
 
-void nt_get_wallet_custodians(long long result_port,
+unsigned long long nt_get_wallet_custodians(long long result_port,
                               void *transport,
-                              char *transport_type,
                               char *address);
 
 void nt_ton_wallet_free_ptr(void *ptr);
 
-void nt_unsigned_message_refresh_timeout(long long result_port, void *unsigned_message);
+unsigned long long nt_unsigned_message_refresh_timeout(long long result_port, void *unsigned_message);
 
-void nt_unsigned_message_expire_at(long long result_port, void *unsigned_message);
+unsigned long long nt_unsigned_message_expire_at(long long result_port, void *unsigned_message);
 
-void nt_unsigned_message_hash(long long result_port, void *unsigned_message);
+unsigned long long nt_unsigned_message_hash(long long result_port, void *unsigned_message);
 
-void nt_unsigned_message_sign(long long result_port, void *unsigned_message, char *signature);
+unsigned long long nt_unsigned_message_sign(long long result_port, void *unsigned_message, char *signature);
 
-void nt_unsigned_message_sign_fake(long long result_port, void *unsigned_message);
+unsigned long long nt_unsigned_message_sign_fake(long long result_port, void *unsigned_message);
 
 char *nt_verify_signature(char *public_key, char *data_hash, char *signature);
 
@@ -352,17 +363,54 @@ void nt_external_resolve_request_with_optional_string(void *tx, char *ok, char *
 
 void nt_external_resolve_request_with_unit(void *tx, char *err);
 
-char *nt_gql_connection_create(unsigned int is_local, long long port);
+char *nt_gql_connection_create(unsigned int is_local,
+                               long long port,
+                               unsigned long long timeout_ms,
+                               char *retry_policy);
+
+char *nt_gql_connection_create_recording(unsigned int is_local,
+                                         long long port,
+                                         unsigned long long timeout_ms,
+                                         char *retry_policy,
+                                         char *path);
+
+char *nt_gql_connection_create_replay(unsigned int is_local, char *path);
 
-char *nt_jrpc_connection_create(long long port);
+char *nt_gql_connection_create_native(unsigned int is_local, char *config);
+
+char *nt_jrpc_connection_create(long long port,
+                                unsigned long long timeout_ms,
+                                char *retry_policy);
+
+char *nt_jrpc_connection_create_recording(long long port,
+                                          unsigned long long timeout_ms,
+                                          char *retry_policy,
+                                          char *path);
+
+char *nt_jrpc_connection_create_replay(char *path);
+
+char *nt_jrpc_connection_create_native(char *config);
 
 void nt_jrpc_connection_free_ptr(void *ptr);
 
-char *nt_ledger_connection_create(long long get_public_key_port, long long sign_port);
+char *nt_ledger_connection_create(long long get_public_key_port,
+                                  long long sign_port,
+                                  unsigned long long timeout_ms);
 
 void nt_ledger_connection_free_ptr(void *ptr);
 
-char *nt_proto_connection_create(long long port);
+char *nt_proto_connection_create(long long port,
+                                 unsigned long long timeout_ms,
+                                 char *retry_policy);
+
+char *nt_proto_connection_create_recording(long long port,
+                                           unsigned long long timeout_ms,
+                                           char *retry_policy,
+                                           char *path);
+
+char *nt_proto_connection_create_replay(char *path);
+
+char *nt_proto_connection_create_native(char *config);
 
 void nt_proto_connection_free_ptr(void *ptr);
 
@@ -370,7 +418,8 @@ char *nt_storage_create(long long get_port,
                         long long set_port,
                         long long set_unchecked_port,
                         long long remove_port,
-                        long long remove_unchecked_port);
+                        long long remove_unchecked_port,
+                        unsigned long long timeout_ms);
 
 void nt_storage_free_ptr(void *ptr);
 
@@ -443,64 +492,114 @@ char *nt_pack_into_cell(char *params, char *tokens);
 
 char *nt_unpack_from_cell(char *params, char *boc, unsigned int allow_partial);
 
-void nt_transport_get_contract_state(long long result_port,
+char *nt_transport_get_type(void *transport);
+
+unsigned long long nt_transport_get_contract_state(long long result_port,
                                      void *transport,
-                                     char *transport_type,
                                      char *address);
 
-void nt_transport_get_full_contract_state(long long result_port,
+unsigned long long nt_transport_get_full_contract_state(long long result_port,
                                           void *transport,
-                                          char *transport_type,
                                           char *address);
 
-void nt_transport_get_accounts_by_code_hash(long long result_port,
+unsigned long long nt_transport_get_accounts_by_code_hash(long long result_port,
                                             void *transport,
-                                            char *transport_type,
                                             char *code_hash,
                                             unsigned char limit,
                                             char *continuation);
 
-void nt_transport_get_transactions(long long result_port,
+unsigned long long nt_transport_get_transactions(long long result_port,
                                    void *transport,
-                                   char *transport_type,
                                    char *address,
                                    char *from_lt,
                                    unsigned char limit);
 
-void nt_transport_get_transaction(long long result_port,
+unsigned long long nt_transport_get_transaction(long long result_port,
                                   void *transport,
-                                  char *transport_type,
                                   char *hash);
 
-void nt_transport_get_signature_id(long long result_port, void *transport, char *transport_type);
+unsigned long long nt_transport_get_signature_id(long long result_port, void *transport);
+
+unsigned long long nt_transport_get_network_id(long long result_port, void *transport);
 
-void nt_transport_get_network_id(long long result_port, void *transport, char *transport_type);
+char *nt_networks_get_all(void);
 
-void nt_transport_simulate_transaction_tree(long long result_port,
+char *nt_networks_register(char *descriptor);
+
+unsigned long long nt_transport_detect_network(long long result_port, void *transport);
+
+unsigned long long nt_transport_simulate_transaction_tree(long long result_port,
                                             void *transport,
-                                            char *transport_type,
                                             char *signed_message,
                                             char *ignored_compute_phase_codes,
                                             char *ignored_action_phase_codes);
 
-char *nt_gql_transport_create(void *gql_connection);
+unsigned long long nt_transport_get_latest_block_id(long long result_port,
+                                      void *transport,
+                                      char *address);
 
-void nt_gql_transport_get_latest_block_id(long long result_port,
-                                          void *gql_transport,
-                                          char *address);
+unsigned long long nt_transport_get_block(long long result_port, void *transport, char *id);
+
+unsigned long long nt_transport_wait_for_next_block_id(long long result_port,
+                                         void *transport,
+                                         char *current_block_id,
+                                         char *address,
+                                         unsigned long long timeout);
+
+unsigned long long nt_transport_estimate_fees_breakdown(long long result_port,
+                                          void *transport,
+                                          void *unsigned_message,
+                                          char *options);
+
+unsigned long long nt_transport_simulate_transaction_tree_report(long long result_port,
+                                                   void *transport,
+                                                   char *signed_message,
+                                                   char *options);
 
-void nt_gql_transport_get_block(long long result_port, void *gql_transport, char *id);
+unsigned long long nt_transport_send_and_confirm(long long result_port,
+                                   void *transport,
+                                   char *signed_message);
+
+unsigned long long nt_transport_stream_accounts_by_code_hash(long long result_port,
+                                               long long batch_port,
+                                               void *transport,
+                                               char *code_hash,
+                                               char *options);
+
+unsigned long long nt_transport_stream_transactions(long long result_port,
+                                      long long batch_port,
+                                      void *transport,
+                                      char *address,
+                                      char *options);
 
-void nt_gql_transport_wait_for_next_block_id(long long result_port,
-                                             void *gql_transport,
-                                             char *current_block_id,
-                                             char *address,
-                                             unsigned long long timeout);
+char *nt_gql_transport_create(void *gql_connection);
 
 void nt_gql_connection_free_ptr(void *ptr);
 
 void nt_gql_transport_free_ptr(void *ptr);
 
+char *nt_caching_transport_create(void *transport, char *config);
+
+char *nt_caching_transport_stats(void *caching_transport);
+
+char *nt_caching_transport_invalidate(void *caching_transport, char *invalidation);
+
+void nt_caching_transport_free_ptr(void *ptr);
+
+char *nt_emulated_transport_create(char *config);
+
+char *nt_emulated_transport_add_account(void *emulated_transport, char *account);
+
+char *nt_emulated_transport_latest_block(void *emulated_transport);
+
+void nt_emulated_transport_free_ptr(void *ptr);
+
+char *nt_failover_transport_create(char *transports);
+
+char *nt_failover_transport_status(void *failover_transport);
+
+void nt_failover_transport_free_ptr(void *ptr);
+
 char *nt_jrpc_transport_create(void *jrpc_connection);
 
 void nt_jrpc_transport_free_ptr(void *ptr);
@@ -508,3 +607,7 @@ void nt_jrpc_transport_free_ptr(void *ptr);
 char *nt_proto_transport_create(void *proto_connection);
 
 void nt_proto_transport_free_ptr(void *ptr);
+
+char *nt_throttled_transport_create(void *transport, char *config);
+
+void nt_throttled_transport_free_ptr(void *ptr);
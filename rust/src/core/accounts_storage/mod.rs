@@ -1,40 +1,43 @@
 use std::{
-    os::raw::{c_char, c_longlong, c_void},
+    os::raw::{c_char, c_longlong, c_ulonglong, c_void},
     sync::Arc,
 };
 
-use allo_isolate::Isolate;
 use nekoton::{core::accounts_storage::AccountsStorage, external::Storage};
 
 use crate::{
-    core::{
-        accounts_storage::models::{AccountToAddHelper, AssetsListHelper},
-        keystore::storage_impl_from_native_ptr,
-    },
-    ffi_box, parse_address, runtime, HandleError, MatchResult, PostWithResult, ToPtrAddress,
-    ToStringFromPtr, RUNTIME,
+    core::accounts_storage::models::{AccountToAddHelper, AssetsListHelper},
+    errors::{ErrorCode, ExecutionError},
+    execute_async, execute_sync,
+    external::storage::storage_from_native_ptr,
+    ffi_handle, parse_address, HandleError, ToPtrAddress, ToStringFromPtr,
 };
 
 mod models;
 
 #[no_mangle]
-pub unsafe extern "C" fn nt_accounts_storage_create(result_port: c_longlong, storage: *mut c_void) {
-    let storage = storage_impl_from_native_ptr(storage).clone();
-
-    runtime!().spawn(async move {
-        async fn internal_fn(storage: Arc<dyn Storage>) -> Result<serde_json::Value, String> {
-            let accounts_storage = AccountsStorage::load(storage).await.handle_error()?;
-
-            let ptr = accounts_storage_new(accounts_storage);
-            serde_json::to_value(ptr.to_ptr_address()).handle_error()
-        }
-
-        let result = internal_fn(storage).await.match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+pub unsafe extern "C" fn nt_accounts_storage_create(
+    result_port: c_longlong,
+    storage: *mut c_void,
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let storage = storage_from_native_ptr(storage)?;
+
+        Ok(async move {
+            async fn internal_fn(
+                storage: Arc<dyn Storage>,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let accounts_storage = AccountsStorage::load(storage)
+                    .await
+                    .handle_error_as(ErrorCode::StorageFailed)?;
+
+                let ptr = accounts_storage_new(Arc::new(accounts_storage));
+                serde_json::to_value(ptr.to_ptr_address()).handle_error()
+            }
+
+            internal_fn(storage).await
+        })
+    })
 }
 
 #[allow(clippy::useless_conversion)]
@@ -42,32 +45,30 @@ pub unsafe extern "C" fn nt_accounts_storage_create(result_port: c_longlong, sto
 pub unsafe extern "C" fn nt_accounts_storage_entries(
     result_port: c_longlong,
     accounts_storage: *mut c_void,
-) {
-    let accounts_storage = accounts_storage_from_native_ptr(accounts_storage);
-
-    runtime!().spawn(async move {
-        async fn internal_fn(
-            accounts_storage: &AccountsStorage,
-        ) -> Result<serde_json::Value, String> {
-            let entries = accounts_storage
-                .stored_data()
-                .await
-                .accounts()
-                .values()
-                .into_iter()
-                .cloned()
-                .map(AssetsListHelper)
-                .collect::<Vec<_>>();
-
-            serde_json::to_value(entries).handle_error()
-        }
-
-        let result = internal_fn(accounts_storage).await.match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let accounts_storage = accounts_storage_from_native_ptr(accounts_storage)?;
+
+        Ok(async move {
+            async fn internal_fn(
+                accounts_storage: &AccountsStorage,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let entries = accounts_storage
+                    .stored_data()
+                    .await
+                    .accounts()
+                    .values()
+                    .into_iter()
+                    .cloned()
+                    .map(AssetsListHelper)
+                    .collect::<Vec<_>>();
+
+                serde_json::to_value(entries).handle_error()
+            }
+
+            internal_fn(&accounts_storage).await
+        })
+    })
 }
 
 #[no_mangle]
@@ -75,36 +76,32 @@ pub unsafe extern "C" fn nt_accounts_storage_add_account(
     result_port: c_longlong,
     accounts_storage: *mut c_void,
     new_account: *mut c_char,
-) {
-    let accounts_storage = accounts_storage_from_native_ptr(accounts_storage);
-
-    let new_account = new_account.to_string_from_ptr();
-
-    runtime!().spawn(async move {
-        async fn internal_fn(
-            accounts_storage: &AccountsStorage,
-            new_account: String,
-        ) -> Result<serde_json::Value, String> {
-            let new_account = serde_json::from_str::<AccountToAddHelper>(&new_account)
-                .map(|AccountToAddHelper(account_to_add)| account_to_add)
-                .handle_error()?;
-
-            let entry = accounts_storage
-                .add_account(new_account)
-                .await
-                .handle_error()?;
-
-            serde_json::to_value(AssetsListHelper(entry)).handle_error()
-        }
-
-        let result = internal_fn(accounts_storage, new_account)
-            .await
-            .match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let accounts_storage = accounts_storage_from_native_ptr(accounts_storage)?;
+
+        let new_account = new_account.to_string_from_ptr();
+
+        Ok(async move {
+            async fn internal_fn(
+                accounts_storage: &AccountsStorage,
+                new_account: String,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let new_account = serde_json::from_str::<AccountToAddHelper>(&new_account)
+                    .map(|AccountToAddHelper(account_to_add)| account_to_add)
+                    .handle_error()?;
+
+                let entry = accounts_storage
+                    .add_account(new_account)
+                    .await
+                    .handle_error_as(ErrorCode::StorageFailed)?;
+
+                serde_json::to_value(AssetsListHelper(entry)).handle_error()
+            }
+
+            internal_fn(&accounts_storage, new_account).await
+        })
+    })
 }
 
 #[no_mangle]
@@ -112,41 +109,37 @@ pub unsafe extern "C" fn nt_accounts_storage_add_accounts(
     result_port: c_longlong,
     accounts_storage: *mut c_void,
     new_accounts: *mut c_char,
-) {
-    let accounts_storage = accounts_storage_from_native_ptr(accounts_storage);
-
-    let new_accounts = new_accounts.to_string_from_ptr();
-
-    runtime!().spawn(async move {
-        async fn internal_fn(
-            accounts_storage: &AccountsStorage,
-            new_accounts: String,
-        ) -> Result<serde_json::Value, String> {
-            let new_accounts = serde_json::from_str::<Vec<AccountToAddHelper>>(&new_accounts)
-                .handle_error()?
-                .into_iter()
-                .map(|AccountToAddHelper(account_to_add)| account_to_add)
-                .collect::<Vec<_>>();
-
-            let entries = accounts_storage
-                .add_accounts(new_accounts)
-                .await
-                .handle_error()?
-                .into_iter()
-                .map(AssetsListHelper)
-                .collect::<Vec<_>>();
-
-            serde_json::to_value(entries).handle_error()
-        }
-
-        let result = internal_fn(accounts_storage, new_accounts)
-            .await
-            .match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let accounts_storage = accounts_storage_from_native_ptr(accounts_storage)?;
+
+        let new_accounts = new_accounts.to_string_from_ptr();
+
+        Ok(async move {
+            async fn internal_fn(
+                accounts_storage: &AccountsStorage,
+                new_accounts: String,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let new_accounts = serde_json::from_str::<Vec<AccountToAddHelper>>(&new_accounts)
+                    .handle_error()?
+                    .into_iter()
+                    .map(|AccountToAddHelper(account_to_add)| account_to_add)
+                    .collect::<Vec<_>>();
+
+                let entries = accounts_storage
+                    .add_accounts(new_accounts)
+                    .await
+                    .handle_error_as(ErrorCode::StorageFailed)?
+                    .into_iter()
+                    .map(AssetsListHelper)
+                    .collect::<Vec<_>>();
+
+                serde_json::to_value(entries).handle_error()
+            }
+
+            internal_fn(&accounts_storage, new_accounts).await
+        })
+    })
 }
 
 #[no_mangle]
@@ -155,34 +148,30 @@ pub unsafe extern "C" fn nt_accounts_storage_rename_account(
     accounts_storage: *mut c_void,
     account: *mut c_char,
     name: *mut c_char,
-) {
-    let accounts_storage = accounts_storage_from_native_ptr(accounts_storage);
-
-    let account = account.to_string_from_ptr();
-    let name = name.to_string_from_ptr();
-
-    runtime!().spawn(async move {
-        async fn internal_fn(
-            accounts_storage: &AccountsStorage,
-            account: String,
-            name: String,
-        ) -> Result<serde_json::Value, String> {
-            let entry = accounts_storage
-                .rename_account(&account, name)
-                .await
-                .handle_error()?;
-
-            serde_json::to_value(AssetsListHelper(entry)).handle_error()
-        }
-
-        let result = internal_fn(accounts_storage, account, name)
-            .await
-            .match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let accounts_storage = accounts_storage_from_native_ptr(accounts_storage)?;
+
+        let account = account.to_string_from_ptr();
+        let name = name.to_string_from_ptr();
+
+        Ok(async move {
+            async fn internal_fn(
+                accounts_storage: &AccountsStorage,
+                account: String,
+                name: String,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let entry = accounts_storage
+                    .rename_account(&account, name)
+                    .await
+                    .handle_error_as(ErrorCode::StorageFailed)?;
+
+                serde_json::to_value(AssetsListHelper(entry)).handle_error()
+            }
+
+            internal_fn(&accounts_storage, account, name).await
+        })
+    })
 }
 
 #[no_mangle]
@@ -192,43 +181,40 @@ pub unsafe extern "C" fn nt_accounts_storage_add_token_wallet(
     account: *mut c_char,
     network_group: *mut c_char,
     root_token_contract: *mut c_char,
-) {
-    let accounts_storage = accounts_storage_from_native_ptr(accounts_storage);
-
-    let account = account.to_string_from_ptr();
-    let network_group = network_group.to_string_from_ptr();
-    let root_token_contract = root_token_contract.to_string_from_ptr();
-
-    runtime!().spawn(async move {
-        async fn internal_fn(
-            accounts_storage: &AccountsStorage,
-            account: String,
-            network_group: String,
-            root_token_contract: String,
-        ) -> Result<serde_json::Value, String> {
-            let root_token_contract = parse_address(&root_token_contract)?;
-
-            let entry = accounts_storage
-                .add_token_wallet(&account, &network_group, root_token_contract)
-                .await
-                .handle_error()?;
-
-            serde_json::to_value(AssetsListHelper(entry)).handle_error()
-        }
-
-        let result = internal_fn(
-            accounts_storage,
-            account,
-            network_group,
-            root_token_contract,
-        )
-        .await
-        .match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let accounts_storage = accounts_storage_from_native_ptr(accounts_storage)?;
+
+        let account = account.to_string_from_ptr();
+        let network_group = network_group.to_string_from_ptr();
+        let root_token_contract = root_token_contract.to_string_from_ptr();
+
+        Ok(async move {
+            async fn internal_fn(
+                accounts_storage: &AccountsStorage,
+                account: String,
+                network_group: String,
+                root_token_contract: String,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let root_token_contract = parse_address(&root_token_contract)?;
+
+                let entry = accounts_storage
+                    .add_token_wallet(&account, &network_group, root_token_contract)
+                    .await
+                    .handle_error_as(ErrorCode::StorageFailed)?;
+
+                serde_json::to_value(AssetsListHelper(entry)).handle_error()
+            }
+
+            internal_fn(
+                &accounts_storage,
+                account,
+                network_group,
+                root_token_contract,
+            )
+            .await
+        })
+    })
 }
 
 #[no_mangle]
@@ -238,43 +224,40 @@ pub unsafe extern "C" fn nt_accounts_storage_remove_token_wallet(
     account: *mut c_char,
     network_group: *mut c_char,
     root_token_contract: *mut c_char,
-) {
-    let accounts_storage = accounts_storage_from_native_ptr(accounts_storage);
-
-    let account = account.to_string_from_ptr();
-    let network_group = network_group.to_string_from_ptr();
-    let root_token_contract = root_token_contract.to_string_from_ptr();
-
-    runtime!().spawn(async move {
-        async fn internal_fn(
-            accounts_storage: &AccountsStorage,
-            account: String,
-            network_group: String,
-            root_token_contract: String,
-        ) -> Result<serde_json::Value, String> {
-            let root_token_contract = parse_address(&root_token_contract)?;
-
-            let entry = accounts_storage
-                .remove_token_wallet(&account, &network_group, &root_token_contract)
-                .await
-                .handle_error()?;
-
-            serde_json::to_value(AssetsListHelper(entry)).handle_error()
-        }
-
-        let result = internal_fn(
-            accounts_storage,
-            account,
-            network_group,
-            root_token_contract,
-        )
-        .await
-        .match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let accounts_storage = accounts_storage_from_native_ptr(accounts_storage)?;
+
+        let account = account.to_string_from_ptr();
+        let network_group = network_group.to_string_from_ptr();
+        let root_token_contract = root_token_contract.to_string_from_ptr();
+
+        Ok(async move {
+            async fn internal_fn(
+                accounts_storage: &AccountsStorage,
+                account: String,
+                network_group: String,
+                root_token_contract: String,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let root_token_contract = parse_address(&root_token_contract)?;
+
+                let entry = accounts_storage
+                    .remove_token_wallet(&account, &network_group, &root_token_contract)
+                    .await
+                    .handle_error_as(ErrorCode::StorageFailed)?;
+
+                serde_json::to_value(AssetsListHelper(entry)).handle_error()
+            }
+
+            internal_fn(
+                &accounts_storage,
+                account,
+                network_group,
+                root_token_contract,
+            )
+            .await
+        })
+    })
 }
 
 #[no_mangle]
@@ -282,31 +265,29 @@ pub unsafe extern "C" fn nt_accounts_storage_remove_account(
     result_port: c_longlong,
     accounts_storage: *mut c_void,
     account: *mut c_char,
-) {
-    let accounts_storage = accounts_storage_from_native_ptr(accounts_storage);
-
-    let account = account.to_string_from_ptr();
-
-    runtime!().spawn(async move {
-        async fn internal_fn(
-            accounts_storage: &AccountsStorage,
-            account: String,
-        ) -> Result<serde_json::Value, String> {
-            let entry = accounts_storage
-                .remove_account(&account)
-                .await
-                .handle_error()?
-                .map(AssetsListHelper);
-
-            serde_json::to_value(entry).handle_error()
-        }
-
-        let result = internal_fn(accounts_storage, account).await.match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let accounts_storage = accounts_storage_from_native_ptr(accounts_storage)?;
+
+        let account = account.to_string_from_ptr();
+
+        Ok(async move {
+            async fn internal_fn(
+                accounts_storage: &AccountsStorage,
+                account: String,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let entry = accounts_storage
+                    .remove_account(&account)
+                    .await
+                    .handle_error_as(ErrorCode::StorageFailed)?
+                    .map(AssetsListHelper);
+
+                serde_json::to_value(entry).handle_error()
+            }
+
+            internal_fn(&accounts_storage, account).await
+        })
+    })
 }
 
 #[no_mangle]
@@ -314,96 +295,98 @@ pub unsafe extern "C" fn nt_accounts_storage_remove_accounts(
     result_port: c_longlong,
     accounts_storage: *mut c_void,
     accounts: *mut c_char,
-) {
-    let accounts_storage = accounts_storage_from_native_ptr(accounts_storage);
-
-    let accounts = accounts.to_string_from_ptr();
-
-    runtime!().spawn(async move {
-        async fn internal_fn(
-            accounts_storage: &AccountsStorage,
-            accounts: String,
-        ) -> Result<serde_json::Value, String> {
-            let accounts = serde_json::from_str::<Vec<&str>>(&accounts).handle_error()?;
-
-            let entries = accounts_storage
-                .remove_accounts(accounts)
-                .await
-                .handle_error()?
-                .into_iter()
-                .map(AssetsListHelper)
-                .collect::<Vec<_>>();
-
-            serde_json::to_value(entries).handle_error()
-        }
-
-        let result = internal_fn(accounts_storage, accounts).await.match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let accounts_storage = accounts_storage_from_native_ptr(accounts_storage)?;
+
+        let accounts = accounts.to_string_from_ptr();
+
+        Ok(async move {
+            async fn internal_fn(
+                accounts_storage: &AccountsStorage,
+                accounts: String,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let accounts = serde_json::from_str::<Vec<&str>>(&accounts).handle_error()?;
+
+                let entries = accounts_storage
+                    .remove_accounts(accounts)
+                    .await
+                    .handle_error_as(ErrorCode::StorageFailed)?
+                    .into_iter()
+                    .map(AssetsListHelper)
+                    .collect::<Vec<_>>();
+
+                serde_json::to_value(entries).handle_error()
+            }
+
+            internal_fn(&accounts_storage, accounts).await
+        })
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_accounts_storage_clear(
     result_port: c_longlong,
     accounts_storage: *mut c_void,
-) {
-    let accounts_storage = accounts_storage_from_native_ptr(accounts_storage);
-
-    runtime!().spawn(async move {
-        async fn internal_fn(
-            accounts_storage: &AccountsStorage,
-        ) -> Result<serde_json::Value, String> {
-            accounts_storage.clear().await.handle_error()?;
-
-            Ok(serde_json::Value::Null)
-        }
-
-        let result = internal_fn(accounts_storage).await.match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let accounts_storage = accounts_storage_from_native_ptr(accounts_storage)?;
+
+        Ok(async move {
+            async fn internal_fn(
+                accounts_storage: &AccountsStorage,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                accounts_storage
+                    .clear()
+                    .await
+                    .handle_error_as(ErrorCode::StorageFailed)?;
+
+                Ok(serde_json::Value::Null)
+            }
+
+            internal_fn(&accounts_storage).await
+        })
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_accounts_storage_reload(
     result_port: c_longlong,
     accounts_storage: *mut c_void,
-) {
-    let accounts_storage = accounts_storage_from_native_ptr(accounts_storage);
-
-    runtime!().spawn(async move {
-        async fn internal_fn(
-            accounts_storage: &AccountsStorage,
-        ) -> Result<serde_json::Value, String> {
-            accounts_storage.reload().await.handle_error()?;
-
-            Ok(serde_json::Value::Null)
-        }
-
-        let result = internal_fn(accounts_storage).await.match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let accounts_storage = accounts_storage_from_native_ptr(accounts_storage)?;
+
+        Ok(async move {
+            async fn internal_fn(
+                accounts_storage: &AccountsStorage,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                accounts_storage
+                    .reload()
+                    .await
+                    .handle_error_as(ErrorCode::StorageFailed)?;
+
+                Ok(serde_json::Value::Null)
+            }
+
+            internal_fn(&accounts_storage).await
+        })
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_accounts_storage_verify_data(data: *mut c_char) -> *mut c_char {
-    let data = data.to_string_from_ptr();
+    execute_sync(|| {
+        let data = data.to_string_from_ptr();
 
-    fn internal_fn(data: String) -> Result<serde_json::Value, String> {
-        let is_valid = AccountsStorage::verify(&data).is_ok();
+        fn internal_fn(data: String) -> Result<serde_json::Value, ExecutionError> {
+            let is_valid = AccountsStorage::verify(&data).is_ok();
 
-        serde_json::to_value(is_valid).handle_error()
-    }
+            serde_json::to_value(is_valid).handle_error()
+        }
 
-    internal_fn(data).match_result()
+        internal_fn(data)
+    })
 }
 
-ffi_box!(accounts_storage, AccountsStorage);
+ffi_handle!(accounts_storage, Arc<AccountsStorage>);
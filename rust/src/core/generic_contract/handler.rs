@@ -1,15 +1,16 @@
-use allo_isolate::Isolate;
 use async_trait::async_trait;
 use nekoton::core::{
     generic_contract::GenericContractSubscriptionHandler,
     models::{ContractState, PendingTransaction, Transaction, TransactionsBatchInfo},
 };
 
+use crate::{host::HostPort, PostSerialized};
+
 pub struct GenericContractSubscriptionHandlerImpl {
-    on_message_sent_port: Isolate,
-    on_message_expired_port: Isolate,
-    on_state_changed_port: Isolate,
-    on_transactions_found_port: Isolate,
+    on_message_sent_port: HostPort,
+    on_message_expired_port: HostPort,
+    on_state_changed_port: HostPort,
+    on_transactions_found_port: HostPort,
 }
 
 impl GenericContractSubscriptionHandlerImpl {
@@ -20,10 +21,10 @@ impl GenericContractSubscriptionHandlerImpl {
         on_transactions_found_port: i64,
     ) -> Self {
         Self {
-            on_message_sent_port: Isolate::new(on_message_sent_port),
-            on_message_expired_port: Isolate::new(on_message_expired_port),
-            on_state_changed_port: Isolate::new(on_state_changed_port),
-            on_transactions_found_port: Isolate::new(on_transactions_found_port),
+            on_message_sent_port: HostPort::event(on_message_sent_port),
+            on_message_expired_port: HostPort::event(on_message_expired_port),
+            on_state_changed_port: HostPort::event(on_state_changed_port),
+            on_transactions_found_port: HostPort::event(on_transactions_found_port),
         }
     }
 }
@@ -35,21 +36,17 @@ impl GenericContractSubscriptionHandler for GenericContractSubscriptionHandlerIm
         pending_transaction: PendingTransaction,
         transaction: Option<Transaction>,
     ) {
-        let payload = serde_json::to_string(&(pending_transaction, transaction)).unwrap();
-
-        self.on_message_sent_port.post(payload);
+        self.on_message_sent_port
+            .post_serialized(&(pending_transaction, transaction));
     }
 
     fn on_message_expired(&self, pending_transaction: PendingTransaction) {
-        let payload = serde_json::to_string(&pending_transaction).unwrap();
-
-        self.on_message_expired_port.post(payload);
+        self.on_message_expired_port
+            .post_serialized(&pending_transaction);
     }
 
     fn on_state_changed(&self, new_state: ContractState) {
-        let payload = serde_json::to_string(&new_state).unwrap();
-
-        self.on_state_changed_port.post(payload);
+        self.on_state_changed_port.post_serialized(&new_state);
     }
 
     fn on_transactions_found(
@@ -57,8 +54,7 @@ impl GenericContractSubscriptionHandler for GenericContractSubscriptionHandlerIm
         transactions: Vec<Transaction>,
         batch_info: TransactionsBatchInfo,
     ) {
-        let payload = serde_json::to_string(&(transactions, batch_info)).unwrap();
-
-        self.on_transactions_found_port.post(payload);
+        self.on_transactions_found_port
+            .post_serialized(&(transactions, batch_info));
     }
 }
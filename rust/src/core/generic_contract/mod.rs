@@ -1,11 +1,10 @@
 mod handler;
 
 use std::{
-    os::raw::{c_char, c_longlong, c_uint, c_void},
+    os::raw::{c_char, c_longlong, c_uint, c_ulonglong, c_void},
     sync::Arc,
 };
 
-use allo_isolate::Isolate;
 use nekoton::{
     core::{generic_contract::GenericContract, TransactionExecutionOptions},
     crypto::SignedMessage,
@@ -15,9 +14,12 @@ use tokio::sync::RwLock;
 use ton_block::{Block, Deserializable};
 
 use crate::{
-    clock, core::generic_contract::handler::GenericContractSubscriptionHandlerImpl, ffi_box,
-    parse_address, runtime, transport::match_transport, HandleError, MatchResult, PostWithResult,
-    ToPtrAddress, ToStringFromPtr, CLOCK, RUNTIME,
+    clock,
+    core::generic_contract::handler::GenericContractSubscriptionHandlerImpl,
+    errors::{ErrorCode, ExecutionError},
+    execute_async, ffi_handle, parse_address,
+    transport::transport_from_ptr,
+    HandleError, ToPtrAddress, ToStringFromPtr, CLOCK,
 };
 
 #[no_mangle]
@@ -28,163 +30,158 @@ pub unsafe extern "C" fn nt_generic_contract_subscribe(
     on_state_changed_port: c_longlong,
     on_transactions_found_port: c_longlong,
     transport: *mut c_void,
-    transport_type: *mut c_char,
     address: *mut c_char,
     preload_transactions: c_uint,
-) {
-    let transport_type = transport_type.to_string_from_ptr();
-    let address = address.to_string_from_ptr();
-    let preload_transactions = preload_transactions != 0;
-
-    let transport = match_transport(transport, &transport_type);
-
-    runtime!().spawn(async move {
-        async fn internal_fn(
-            on_message_sent_port: i64,
-            on_message_expired_port: i64,
-            on_state_changed_port: i64,
-            on_transactions_found_port: i64,
-            transport: Arc<dyn Transport>,
-            address: String,
-            preload_transactions: bool,
-        ) -> Result<serde_json::Value, String> {
-            let address = parse_address(&address)?;
-
-            let handler = Arc::new(GenericContractSubscriptionHandlerImpl::new(
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let address = address.to_string_from_ptr();
+        let preload_transactions = preload_transactions != 0;
+
+        let transport = transport_from_ptr(transport)?;
+
+        Ok(async move {
+            async fn internal_fn(
+                on_message_sent_port: i64,
+                on_message_expired_port: i64,
+                on_state_changed_port: i64,
+                on_transactions_found_port: i64,
+                transport: Arc<dyn Transport>,
+                address: String,
+                preload_transactions: bool,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let address = parse_address(&address)?;
+
+                let handler = Arc::new(GenericContractSubscriptionHandlerImpl::new(
+                    on_message_sent_port,
+                    on_message_expired_port,
+                    on_state_changed_port,
+                    on_transactions_found_port,
+                ));
+
+                let generic_contract = GenericContract::subscribe(
+                    clock!(),
+                    transport,
+                    address,
+                    handler,
+                    preload_transactions,
+                )
+                .await
+                .handle_error_as(ErrorCode::SubscriptionFailed)?;
+
+                let ptr = Arc::new(RwLock::new(generic_contract));
+                let ptr = generic_contract_new(ptr);
+
+                serde_json::to_value(ptr.to_ptr_address()).handle_error()
+            }
+
+            internal_fn(
                 on_message_sent_port,
                 on_message_expired_port,
                 on_state_changed_port,
                 on_transactions_found_port,
-            ));
-
-            let generic_contract = GenericContract::subscribe(
-                clock!(),
                 transport,
                 address,
-                handler,
                 preload_transactions,
             )
             .await
-            .handle_error()?;
-
-            let ptr = Arc::new(RwLock::new(generic_contract));
-            let ptr = generic_contract_new(ptr);
-
-            serde_json::to_value(ptr.to_ptr_address()).handle_error()
-        }
-
-        let result = internal_fn(
-            on_message_sent_port,
-            on_message_expired_port,
-            on_state_changed_port,
-            on_transactions_found_port,
-            transport,
-            address,
-            preload_transactions,
-        )
-        .await
-        .match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+        })
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_generic_contract_address(
     result_port: c_longlong,
     generic_contract: *mut c_void,
-) {
-    let generic_contract = generic_contract_from_native_ptr(generic_contract);
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let generic_contract = generic_contract_from_native_ptr(generic_contract)?;
 
-    runtime!().spawn(async move {
-        fn internal_fn(generic_contract: &GenericContract) -> Result<serde_json::Value, String> {
-            let address = generic_contract.address().to_string();
+        Ok(async move {
+            fn internal_fn(
+                generic_contract: &GenericContract,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let address = generic_contract.address().to_string();
 
-            serde_json::to_value(address).handle_error()
-        }
+                serde_json::to_value(address).handle_error()
+            }
 
-        let generic_contract = generic_contract.read().await;
+            let generic_contract = generic_contract.read().await;
 
-        let result = internal_fn(&generic_contract).match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+            internal_fn(&generic_contract)
+        })
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_generic_contract_contract_state(
     result_port: c_longlong,
     generic_contract: *mut c_void,
-) {
-    let generic_contract = generic_contract_from_native_ptr(generic_contract);
-
-    runtime!().spawn(async move {
-        fn internal_fn(generic_contract: &GenericContract) -> Result<serde_json::Value, String> {
-            let contract_state = generic_contract.contract_state();
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let generic_contract = generic_contract_from_native_ptr(generic_contract)?;
 
-            serde_json::to_value(contract_state).handle_error()
-        }
+        Ok(async move {
+            fn internal_fn(
+                generic_contract: &GenericContract,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let contract_state = generic_contract.contract_state();
 
-        let generic_contract = generic_contract.read().await;
+                serde_json::to_value(contract_state).handle_error()
+            }
 
-        let result = internal_fn(&generic_contract).match_result();
+            let generic_contract = generic_contract.read().await;
 
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+            internal_fn(&generic_contract)
+        })
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_generic_contract_pending_transactions(
     result_port: c_longlong,
     generic_contract: *mut c_void,
-) {
-    let generic_contract = generic_contract_from_native_ptr(generic_contract);
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let generic_contract = generic_contract_from_native_ptr(generic_contract)?;
 
-    runtime!().spawn(async move {
-        fn internal_fn(generic_contract: &GenericContract) -> Result<serde_json::Value, String> {
-            let pending_transactions = generic_contract.pending_transactions();
+        Ok(async move {
+            fn internal_fn(
+                generic_contract: &GenericContract,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let pending_transactions = generic_contract.pending_transactions();
 
-            serde_json::to_value(pending_transactions).handle_error()
-        }
+                serde_json::to_value(pending_transactions).handle_error()
+            }
 
-        let generic_contract = generic_contract.read().await;
+            let generic_contract = generic_contract.read().await;
 
-        let result = internal_fn(&generic_contract).match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+            internal_fn(&generic_contract)
+        })
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_generic_contract_polling_method(
     result_port: c_longlong,
     generic_contract: *mut c_void,
-) {
-    let generic_contract = generic_contract_from_native_ptr(generic_contract);
-
-    runtime!().spawn(async move {
-        fn internal_fn(generic_contract: &GenericContract) -> Result<serde_json::Value, String> {
-            let polling_method = generic_contract.polling_method();
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let generic_contract = generic_contract_from_native_ptr(generic_contract)?;
 
-            serde_json::to_value(polling_method).handle_error()
-        }
+        Ok(async move {
+            fn internal_fn(
+                generic_contract: &GenericContract,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let polling_method = generic_contract.polling_method();
 
-        let generic_contract = generic_contract.read().await;
+                serde_json::to_value(polling_method).handle_error()
+            }
 
-        let result = internal_fn(&generic_contract).match_result();
+            let generic_contract = generic_contract.read().await;
 
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+            internal_fn(&generic_contract)
+        })
+    })
 }
 
 #[no_mangle]
@@ -192,39 +189,35 @@ pub unsafe extern "C" fn nt_generic_contract_estimate_fees(
     result_port: c_longlong,
     generic_contract: *mut c_void,
     signed_message: *mut c_char,
-) {
-    let generic_contract = generic_contract_from_native_ptr(generic_contract);
-
-    let signed_message = signed_message.to_string_from_ptr();
-
-    runtime!().spawn(async move {
-        async fn internal_fn(
-            generic_contract: &mut GenericContract,
-            signed_message: String,
-        ) -> Result<serde_json::Value, String> {
-            let message = serde_json::from_str::<SignedMessage>(&signed_message)
-                .handle_error()?
-                .message;
-
-            let fees = generic_contract
-                .estimate_fees(&message)
-                .await
-                .handle_error()?
-                .to_string();
-
-            serde_json::to_value(fees).handle_error()
-        }
-
-        let mut generic_contract = generic_contract.write().await;
-
-        let result = internal_fn(&mut generic_contract, signed_message)
-            .await
-            .match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let generic_contract = generic_contract_from_native_ptr(generic_contract)?;
+
+        let signed_message = signed_message.to_string_from_ptr();
+
+        Ok(async move {
+            async fn internal_fn(
+                generic_contract: &mut GenericContract,
+                signed_message: String,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let message = serde_json::from_str::<SignedMessage>(&signed_message)
+                    .handle_error()?
+                    .message;
+
+                let fees = generic_contract
+                    .estimate_fees(&message)
+                    .await
+                    .handle_error_as(ErrorCode::ExecutionFailed)?
+                    .to_string();
+
+                serde_json::to_value(fees).handle_error()
+            }
+
+            let mut generic_contract = generic_contract.write().await;
+
+            internal_fn(&mut generic_contract, signed_message).await
+        })
+    })
 }
 
 #[no_mangle]
@@ -232,37 +225,33 @@ pub unsafe extern "C" fn nt_generic_contract_send(
     result_port: c_longlong,
     generic_contract: *mut c_void,
     signed_message: *mut c_char,
-) {
-    let generic_contract = generic_contract_from_native_ptr(generic_contract);
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let generic_contract = generic_contract_from_native_ptr(generic_contract)?;
 
-    let signed_message = signed_message.to_string_from_ptr();
+        let signed_message = signed_message.to_string_from_ptr();
 
-    runtime!().spawn(async move {
-        async fn internal_fn(
-            generic_contract: &mut GenericContract,
-            signed_message: String,
-        ) -> Result<serde_json::Value, String> {
-            let signed_message =
-                serde_json::from_str::<SignedMessage>(&signed_message).handle_error()?;
-
-            let pending_transaction = generic_contract
-                .send(&signed_message.message, signed_message.expire_at)
-                .await
-                .handle_error()?;
+        Ok(async move {
+            async fn internal_fn(
+                generic_contract: &mut GenericContract,
+                signed_message: String,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let signed_message =
+                    serde_json::from_str::<SignedMessage>(&signed_message).handle_error()?;
 
-            serde_json::to_value(pending_transaction).handle_error()
-        }
+                let pending_transaction = generic_contract
+                    .send(&signed_message.message, signed_message.expire_at)
+                    .await
+                    .handle_error_as(ErrorCode::SendFailed)?;
 
-        let mut generic_contract = generic_contract.write().await;
+                serde_json::to_value(pending_transaction).handle_error()
+            }
 
-        let result = internal_fn(&mut generic_contract, signed_message)
-            .await
-            .match_result();
+            let mut generic_contract = generic_contract.write().await;
 
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+            internal_fn(&mut generic_contract, signed_message).await
+        })
+    })
 }
 
 #[no_mangle]
@@ -271,69 +260,66 @@ pub unsafe extern "C" fn nt_generic_contract_execute_transaction_locally(
     generic_contract: *mut c_void,
     signed_message: *mut c_char,
     options: *mut c_char,
-) {
-    let generic_contract = generic_contract_from_native_ptr(generic_contract);
-
-    let signed_message = signed_message.to_string_from_ptr();
-    let options = options.to_string_from_ptr();
-
-    runtime!().spawn(async move {
-        async fn internal_fn(
-            generic_contract: &mut GenericContract,
-            signed_message: String,
-            options: String,
-        ) -> Result<serde_json::Value, String> {
-            let message = serde_json::from_str::<SignedMessage>(&signed_message)
-                .handle_error()?
-                .message;
-
-            let options =
-                serde_json::from_str::<TransactionExecutionOptions>(&options).handle_error()?;
-
-            let transaction = generic_contract
-                .execute_transaction_locally(&message, options)
-                .await
-                .handle_error()?;
-
-            serde_json::to_value(transaction).handle_error()
-        }
-
-        let mut generic_contract = generic_contract.write().await;
-
-        let result = internal_fn(&mut generic_contract, signed_message, options)
-            .await
-            .match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let generic_contract = generic_contract_from_native_ptr(generic_contract)?;
+
+        let signed_message = signed_message.to_string_from_ptr();
+        let options = options.to_string_from_ptr();
+
+        Ok(async move {
+            async fn internal_fn(
+                generic_contract: &mut GenericContract,
+                signed_message: String,
+                options: String,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let message = serde_json::from_str::<SignedMessage>(&signed_message)
+                    .handle_error()?
+                    .message;
+
+                let options =
+                    serde_json::from_str::<TransactionExecutionOptions>(&options).handle_error()?;
+
+                let transaction = generic_contract
+                    .execute_transaction_locally(&message, options)
+                    .await
+                    .handle_error_as(ErrorCode::ExecutionFailed)?;
+
+                serde_json::to_value(transaction).handle_error()
+            }
+
+            let mut generic_contract = generic_contract.write().await;
+
+            internal_fn(&mut generic_contract, signed_message, options).await
+        })
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_generic_contract_refresh(
     result_port: c_longlong,
     generic_contract: *mut c_void,
-) {
-    let generic_contract = generic_contract_from_native_ptr(generic_contract);
-
-    runtime!().spawn(async move {
-        async fn internal_fn(
-            generic_contract: &mut GenericContract,
-        ) -> Result<serde_json::Value, String> {
-            generic_contract.refresh().await.handle_error()?;
-
-            Ok(serde_json::Value::Null)
-        }
-
-        let mut generic_contract = generic_contract.write().await;
-
-        let result = internal_fn(&mut generic_contract).await.match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let generic_contract = generic_contract_from_native_ptr(generic_contract)?;
+
+        Ok(async move {
+            async fn internal_fn(
+                generic_contract: &mut GenericContract,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                generic_contract
+                    .refresh()
+                    .await
+                    .handle_error_as(ErrorCode::RequestFailed)?;
+
+                Ok(serde_json::Value::Null)
+            }
+
+            let mut generic_contract = generic_contract.write().await;
+
+            internal_fn(&mut generic_contract).await
+        })
+    })
 }
 
 #[no_mangle]
@@ -341,36 +327,32 @@ pub unsafe extern "C" fn nt_generic_contract_preload_transactions(
     result_port: c_longlong,
     generic_contract: *mut c_void,
     from_lt: *mut c_char,
-) {
-    let generic_contract = generic_contract_from_native_ptr(generic_contract);
-
-    let from_lt = from_lt.to_string_from_ptr();
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let generic_contract = generic_contract_from_native_ptr(generic_contract)?;
 
-    runtime!().spawn(async move {
-        async fn internal_fn(
-            generic_contract: &mut GenericContract,
-            from_lt: String,
-        ) -> Result<serde_json::Value, String> {
-            let from_lt = from_lt.parse::<u64>().handle_error()?;
+        let from_lt = from_lt.to_string_from_ptr();
 
-            generic_contract
-                .preload_transactions(from_lt)
-                .await
-                .handle_error()?;
+        Ok(async move {
+            async fn internal_fn(
+                generic_contract: &mut GenericContract,
+                from_lt: String,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let from_lt = from_lt.parse::<u64>().handle_error()?;
 
-            Ok(serde_json::Value::Null)
-        }
+                generic_contract
+                    .preload_transactions(from_lt)
+                    .await
+                    .handle_error_as(ErrorCode::RequestFailed)?;
 
-        let mut generic_contract = generic_contract.write().await;
+                Ok(serde_json::Value::Null)
+            }
 
-        let result = internal_fn(&mut generic_contract, from_lt)
-            .await
-            .match_result();
+            let mut generic_contract = generic_contract.write().await;
 
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+            internal_fn(&mut generic_contract, from_lt).await
+        })
+    })
 }
 
 #[no_mangle]
@@ -378,33 +360,33 @@ pub unsafe extern "C" fn nt_generic_contract_handle_block(
     result_port: c_longlong,
     generic_contract: *mut c_void,
     block: *mut c_char,
-) {
-    let generic_contract = generic_contract_from_native_ptr(generic_contract);
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let generic_contract = generic_contract_from_native_ptr(generic_contract)?;
 
-    let block = block.to_string_from_ptr();
+        let block = block.to_string_from_ptr();
 
-    runtime!().spawn(async move {
-        async fn internal_fn(
-            generic_contract: &mut GenericContract,
-            block: String,
-        ) -> Result<serde_json::Value, String> {
-            let block = Block::construct_from_base64(&block).handle_error()?;
+        Ok(async move {
+            async fn internal_fn(
+                generic_contract: &mut GenericContract,
+                block: String,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let block =
+                    Block::construct_from_base64(&block).handle_error_as(ErrorCode::InvalidBoc)?;
 
-            generic_contract.handle_block(&block).await.handle_error()?;
+                generic_contract
+                    .handle_block(&block)
+                    .await
+                    .handle_error_as(ErrorCode::RequestFailed)?;
 
-            Ok(serde_json::Value::Null)
-        }
+                Ok(serde_json::Value::Null)
+            }
 
-        let mut generic_contract = generic_contract.write().await;
-
-        let result = internal_fn(&mut generic_contract, block)
-            .await
-            .match_result();
+            let mut generic_contract = generic_contract.write().await;
 
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+            internal_fn(&mut generic_contract, block).await
+        })
+    })
 }
 
-ffi_box!(generic_contract, Arc<RwLock<GenericContract>>);
+ffi_handle!(generic_contract, Arc<RwLock<GenericContract>>);
@@ -1,5 +1,6 @@
 mod accounts_storage;
 mod generic_contract;
 mod keystore;
+mod subscription_scheduler;
 mod token_wallet;
 mod ton_wallet;
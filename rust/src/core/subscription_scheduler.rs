@@ -0,0 +1,604 @@
+use std::{
+    collections::HashMap,
+    os::raw::{c_char, c_longlong, c_ulonglong, c_void},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use nekoton::{
+    core::{
+        generic_contract::GenericContract, models::PollingMethod, token_wallet::TokenWallet,
+        ton_wallet::TonWallet,
+    },
+    transport::models::RawContractState,
+};
+use serde::Deserialize;
+use tokio::{
+    sync::{Notify, RwLock, Semaphore},
+    task::JoinHandle,
+};
+use ton_block::{Block, MsgAddressInt};
+
+use crate::{
+    core::{
+        generic_contract::generic_contract_from_native_ptr,
+        token_wallet::token_wallet_from_native_ptr, ton_wallet::ton_wallet_from_native_ptr,
+    },
+    errors::{ErrorCode, ExecutionError},
+    execute_async, execute_sync, ffi_handle,
+    handles::{handle_to_ptr, handles, ptr_to_handle},
+    transport::block_walker::BlockWalker,
+    HandleError, ToPtrAddress, ToStringFromPtr,
+};
+
+const DEFAULT_MANUAL_INTERVAL: Duration = Duration::from_secs(10);
+
+const DEFAULT_RELIABLE_INTERVAL: Duration = Duration::from_secs(1);
+
+const DEFAULT_MAX_IDLE_INTERVAL: Duration = Duration::from_secs(60);
+
+const DEFAULT_CONCURRENCY: usize = 10;
+
+const BLOCK_WAIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionSchedulerConfig {
+    /// Interval of subscriptions with `PollingMethod::Manual`.
+    pub manual_interval_ms: Option<u64>,
+    /// Polling methods are re-checked this often, so that a subscription which sent a message
+    /// starts following blocks. Following blocks is retried after this interval when it fails.
+    pub reliable_interval_ms: Option<u64>,
+    /// The manual interval doubles after every poll without changes up to this value.
+    pub max_idle_interval_ms: Option<u64>,
+    /// At most this many accounts are polled at the same time.
+    pub concurrency: Option<usize>,
+}
+
+#[derive(Clone, Copy)]
+enum SubscriptionKind {
+    TonWallet,
+    TokenWallet,
+    GenericContract,
+}
+
+impl SubscriptionKind {
+    fn from_handle_kind(kind: &str) -> Option<Self> {
+        match kind {
+            "ton_wallet" => Some(Self::TonWallet),
+            "token_wallet" => Some(Self::TokenWallet),
+            "generic_contract" => Some(Self::GenericContract),
+            _ => None,
+        }
+    }
+}
+
+enum Subscription {
+    TonWallet(Arc<RwLock<TonWallet>>),
+    TokenWallet(Arc<RwLock<TokenWallet>>),
+    GenericContract(Arc<RwLock<GenericContract>>),
+}
+
+impl Subscription {
+    /// Subscriptions are resolved on every poll, so that a freed handle leaves the schedule.
+    fn resolve(kind: SubscriptionKind, handle: usize) -> Result<Self, ExecutionError> {
+        let ptr = handle_to_ptr(handle);
+
+        unsafe {
+            match kind {
+                SubscriptionKind::TonWallet => ton_wallet_from_native_ptr(ptr).map(Self::TonWallet),
+                SubscriptionKind::TokenWallet => {
+                    token_wallet_from_native_ptr(ptr).map(Self::TokenWallet)
+                },
+                SubscriptionKind::GenericContract => {
+                    generic_contract_from_native_ptr(ptr).map(Self::GenericContract)
+                },
+            }
+        }
+    }
+
+    /// Token wallets have no pending transactions.
+    async fn polling_method(&self) -> PollingMethod {
+        match self {
+            Self::TonWallet(ton_wallet) => ton_wallet.read().await.polling_method(),
+            Self::TokenWallet(_) => PollingMethod::Manual,
+            Self::GenericContract(generic_contract) => {
+                generic_contract.read().await.polling_method()
+            },
+        }
+    }
+
+    async fn address(&self) -> MsgAddressInt {
+        match self {
+            Self::TonWallet(ton_wallet) => ton_wallet.read().await.address().clone(),
+            Self::TokenWallet(token_wallet) => token_wallet.read().await.address().clone(),
+            Self::GenericContract(generic_contract) => {
+                generic_contract.read().await.address().clone()
+            },
+        }
+    }
+
+    async fn last_lt(&self) -> u64 {
+        match self {
+            Self::TonWallet(ton_wallet) => ton_wallet.read().await.contract_state().last_lt,
+            Self::TokenWallet(token_wallet) => token_wallet.read().await.contract_state().last_lt,
+            Self::GenericContract(generic_contract) => {
+                generic_contract.read().await.contract_state().last_lt
+            },
+        }
+    }
+
+    async fn refresh(&self) -> Result<()> {
+        match self {
+            Self::TonWallet(ton_wallet) => ton_wallet.write().await.refresh().await,
+            Self::TokenWallet(token_wallet) => token_wallet.write().await.refresh().await,
+            Self::GenericContract(generic_contract) => {
+                generic_contract.write().await.refresh().await
+            },
+        }
+    }
+
+    async fn handle_block(&self, block: &Block) -> Result<()> {
+        match self {
+            Self::TonWallet(ton_wallet) => ton_wallet.write().await.handle_block(block).await,
+            Self::TokenWallet(token_wallet) => token_wallet.write().await.handle_block(block).await,
+            Self::GenericContract(generic_contract) => {
+                generic_contract.write().await.handle_block(block).await
+            },
+        }
+    }
+}
+
+struct ScheduleEntry {
+    kind: SubscriptionKind,
+    next_poll_at: Instant,
+    /// Polls in a row which found no changes.
+    idle_polls: u32,
+    /// Set while a poll of the subscription is in flight.
+    is_polling: bool,
+    /// Set while the subscription follows blocks instead of being polled.
+    block_walk: Option<JoinHandle<()>>,
+}
+
+/// Refreshes a set of subscriptions according to their polling methods. Updates are
+/// posted by the handlers of the subscriptions as with manual refreshes.
+///
+/// Due subscriptions of the same account share a single state check per round and only
+/// those behind the state are refreshed. Every account is polled by its own task, so a slow
+/// refresh doesn't hold back the rest of the schedule.
+pub struct SubscriptionScheduler {
+    walker: BlockWalker,
+    manual_interval: Duration,
+    reliable_interval: Duration,
+    max_idle_interval: Duration,
+    concurrency: usize,
+    entries: Mutex<HashMap<usize, ScheduleEntry>>,
+    schedule_changed: Notify,
+    is_running: AtomicBool,
+    /// Polls in flight, they are aborted when the run is cancelled.
+    polls: Mutex<Vec<JoinHandle<()>>>,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl SubscriptionScheduler {
+    pub fn new(
+        walker: BlockWalker,
+        config: SubscriptionSchedulerConfig,
+    ) -> Result<Self, ExecutionError> {
+        let concurrency = config.concurrency.unwrap_or(DEFAULT_CONCURRENCY);
+        if concurrency == 0 {
+            return Err(ExecutionError::new(
+                ErrorCode::InvalidInput,
+                "Concurrency must not be zero",
+            ));
+        }
+
+        let interval = |ms: Option<u64>, default| ms.map(Duration::from_millis).unwrap_or(default);
+        let manual_interval = interval(config.manual_interval_ms, DEFAULT_MANUAL_INTERVAL);
+
+        Ok(Self {
+            walker,
+            manual_interval,
+            reliable_interval: interval(config.reliable_interval_ms, DEFAULT_RELIABLE_INTERVAL),
+            max_idle_interval: interval(config.max_idle_interval_ms, DEFAULT_MAX_IDLE_INTERVAL)
+                .max(manual_interval),
+            concurrency,
+            entries: Default::default(),
+            schedule_changed: Notify::new(),
+            is_running: AtomicBool::new(false),
+            polls: Default::default(),
+        })
+    }
+
+    /// The subscription is polled right away.
+    fn add(&self, kind: SubscriptionKind, handle: usize) {
+        lock(&self.entries).entry(handle).or_insert(ScheduleEntry {
+            kind,
+            next_poll_at: Instant::now(),
+            idle_polls: 0,
+            is_polling: false,
+            block_walk: None,
+        });
+
+        self.schedule_changed.notify_one();
+    }
+
+    fn remove(&self, handle: usize) {
+        let entry = lock(&self.entries).remove(&handle);
+
+        if let Some(block_walk) = entry.and_then(|entry| entry.block_walk) {
+            block_walk.abort();
+        }
+    }
+
+    /// Runs until the operation is cancelled.
+    async fn run(self: Arc<Self>) -> Result<(), ExecutionError> {
+        if self.is_running.swap(true, Ordering::AcqRel) {
+            return Err(ExecutionError::new(
+                ErrorCode::InvalidInput,
+                "Scheduler is already running",
+            ));
+        }
+        let _guard = RunGuard(&self);
+
+        let permits = Arc::new(Semaphore::new(self.concurrency));
+
+        loop {
+            for (address, subscriptions) in self.take_due().await {
+                let poll = tokio::spawn(self.clone().poll(address, subscriptions, permits.clone()));
+
+                let mut polls = lock(&self.polls);
+                polls.retain(|poll| !poll.is_finished());
+                polls.push(poll);
+            }
+
+            let next_poll_at = lock(&self.entries)
+                .values()
+                .filter(|entry| !entry.is_polling && entry.block_walk.is_none())
+                .map(|entry| entry.next_poll_at)
+                .min();
+
+            // Woken up earlier when subscriptions are added, finish their polls or stop
+            // following blocks, idle subscriptions are checked for sent messages every
+            // reliable interval
+            let timeout = match next_poll_at {
+                Some(next_poll_at) => next_poll_at
+                    .saturating_duration_since(Instant::now())
+                    .min(self.reliable_interval),
+                None => self.max_idle_interval,
+            };
+            tokio::time::timeout(timeout, self.schedule_changed.notified())
+                .await
+                .ok();
+        }
+    }
+
+    /// Due subscriptions grouped by account, they are marked as polled.
+    ///
+    /// A subscription which switched to `PollingMethod::Reliable`, e.g. after sending
+    /// a message, is due right away instead of waiting out its idle interval.
+    async fn take_due(&self) -> HashMap<MsgAddressInt, Vec<(usize, Subscription)>> {
+        let now = Instant::now();
+
+        let scheduled = lock(&self.entries)
+            .iter()
+            .filter(|(_, entry)| !entry.is_polling && entry.block_walk.is_none())
+            .map(|(&handle, entry)| (handle, entry.kind, entry.next_poll_at <= now))
+            .collect::<Vec<_>>();
+
+        let mut due = HashMap::<_, Vec<_>>::new();
+        for (handle, kind, is_due) in scheduled {
+            let subscription = match Subscription::resolve(kind, handle) {
+                Ok(subscription) => subscription,
+                // Freed handles leave the schedule
+                Err(_) => {
+                    self.remove(handle);
+                    continue;
+                },
+            };
+
+            let is_due =
+                is_due || matches!(subscription.polling_method().await, PollingMethod::Reliable);
+            if !is_due {
+                continue;
+            }
+
+            // The subscription could have been removed in the meantime
+            match lock(&self.entries).get_mut(&handle) {
+                Some(entry) => entry.is_polling = true,
+                None => continue,
+            }
+
+            let address = subscription.address().await;
+            due.entry(address).or_default().push((handle, subscription));
+        }
+
+        due
+    }
+
+    /// Checks the state of the account once and refreshes only the subscriptions which
+    /// are behind it.
+    async fn poll(
+        self: Arc<Self>,
+        address: MsgAddressInt,
+        subscriptions: Vec<(usize, Subscription)>,
+        permits: Arc<Semaphore>,
+    ) {
+        let _permit = permits.acquire_owned().await;
+
+        let latest_lt = match self.walker.transport().get_contract_state(&address).await {
+            Ok(RawContractState::Exists(contract)) => Some(contract.last_transaction_id.lt()),
+            Ok(RawContractState::NotExists { .. }) => Some(0),
+            Err(e) => {
+                log::warn!("Failed to get contract state: {:?}", e);
+                None
+            },
+        };
+
+        for (handle, subscription) in subscriptions {
+            let last_lt = subscription.last_lt().await;
+
+            let is_changed = match latest_lt {
+                Some(latest_lt) if latest_lt != last_lt => match subscription.refresh().await {
+                    Ok(()) => subscription.last_lt().await != last_lt,
+                    Err(e) => {
+                        log::warn!("Failed to refresh subscription: {:?}", e);
+                        false
+                    },
+                },
+                _ => false,
+            };
+
+            self.reschedule(handle, subscription, is_changed).await;
+        }
+
+        self.schedule_changed.notify_one();
+    }
+
+    async fn reschedule(
+        self: &Arc<Self>,
+        handle: usize,
+        subscription: Subscription,
+        is_changed: bool,
+    ) {
+        let polling_method = subscription.polling_method().await;
+
+        let mut entries = lock(&self.entries);
+        let entry = match entries.get_mut(&handle) {
+            Some(entry) => entry,
+            None => return,
+        };
+        entry.is_polling = false;
+
+        let interval = match polling_method {
+            PollingMethod::Reliable => {
+                entry.idle_polls = 0;
+
+                // A poll which outlived a cancelled run must not start following blocks
+                if self.is_running.load(Ordering::Acquire) {
+                    let block_walk = tokio::spawn(self.clone().walk_blocks(handle, subscription));
+                    entry.block_walk = Some(block_walk);
+                    return;
+                }
+
+                Duration::ZERO
+            },
+            PollingMethod::Manual => self.idle_interval(entry, is_changed),
+        };
+
+        entry.next_poll_at = Instant::now() + interval;
+    }
+
+    /// Counts the polls without changes, the first change resets the interval.
+    fn idle_interval(&self, entry: &mut ScheduleEntry, is_changed: bool) -> Duration {
+        entry.idle_polls = match is_changed {
+            true => 0,
+            false => entry.idle_polls.saturating_add(1),
+        };
+
+        let factor = 2u32.saturating_pow(entry.idle_polls.min(16));
+
+        self.manual_interval
+            .saturating_mul(factor)
+            .min(self.max_idle_interval)
+    }
+
+    /// Follows the blocks of the subscription until its pending transactions are resolved,
+    /// then the subscription is polled again.
+    async fn walk_blocks(self: Arc<Self>, handle: usize, subscription: Subscription) {
+        let retry_after = match self.follow_blocks(&subscription).await {
+            Ok(()) => Duration::ZERO,
+            Err(e) => {
+                log::warn!("Failed to follow blocks of subscription: {:?}", e);
+                self.reliable_interval
+            },
+        };
+
+        if let Some(entry) = lock(&self.entries).get_mut(&handle) {
+            entry.block_walk = None;
+            entry.next_poll_at = Instant::now() + retry_after;
+        }
+
+        self.schedule_changed.notify_one();
+    }
+
+    async fn follow_blocks(&self, subscription: &Subscription) -> Result<()> {
+        let address = subscription.address().await;
+        let mut current = self.walker.get_latest_block_id(&address).await?;
+
+        while let PollingMethod::Reliable = subscription.polling_method().await {
+            let next = self
+                .walker
+                .wait_for_next_block_id(&current, &address, BLOCK_WAIT_TIMEOUT)
+                .await?;
+
+            let block = self.walker.get_block(&next).await?;
+            subscription.handle_block(&block).await?;
+
+            current = next;
+        }
+
+        Ok(())
+    }
+}
+
+/// Stops the polls and following blocks when the run is cancelled.
+struct RunGuard<'a>(&'a SubscriptionScheduler);
+
+impl Drop for RunGuard<'_> {
+    fn drop(&mut self) {
+        for poll in lock(&self.0.polls).drain(..) {
+            poll.abort();
+        }
+
+        for entry in lock(&self.0.entries).values_mut() {
+            if let Some(block_walk) = entry.block_walk.take() {
+                block_walk.abort();
+            }
+            entry.is_polling = false;
+            entry.next_poll_at = Instant::now();
+        }
+
+        self.0.is_running.store(false, Ordering::Release);
+    }
+}
+
+unsafe fn subscription_kind_from_ptr(
+    subscription: *mut c_void,
+) -> Result<SubscriptionKind, ExecutionError> {
+    let kind = handles().kind(ptr_to_handle(subscription))?;
+
+    SubscriptionKind::from_handle_kind(kind).ok_or_else(|| {
+        ExecutionError::new(ErrorCode::InvalidHandle, "Handle is not a subscription")
+            .with_context("actual", kind)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_subscription_scheduler_create(
+    transport: *mut c_void,
+    config: *mut c_char,
+) -> *mut c_char {
+    execute_sync(|| {
+        let walker = BlockWalker::from_ptr(transport)?;
+        let config = config.to_string_from_ptr();
+
+        fn internal_fn(
+            walker: BlockWalker,
+            config: String,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let config = serde_json::from_str::<SubscriptionSchedulerConfig>(&config)
+                .handle_error_as(ErrorCode::InvalidInput)?;
+
+            let scheduler = SubscriptionScheduler::new(walker, config)?;
+
+            let ptr = subscription_scheduler_new(Arc::new(scheduler));
+
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
+        }
+
+        internal_fn(walker, config)
+    })
+}
+
+/// Adds a `TonWallet`, `TokenWallet` or `GenericContract` handle to the schedule.
+/// A freed handle leaves the schedule when it's due.
+#[no_mangle]
+pub unsafe extern "C" fn nt_subscription_scheduler_add(
+    subscription_scheduler: *mut c_void,
+    subscription: *mut c_void,
+) -> *mut c_char {
+    execute_sync(|| {
+        let subscription_scheduler =
+            subscription_scheduler_from_native_ptr(subscription_scheduler)?;
+        let kind = subscription_kind_from_ptr(subscription)?;
+
+        subscription_scheduler.add(kind, ptr_to_handle(subscription));
+
+        Ok(serde_json::Value::Null)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_subscription_scheduler_remove(
+    subscription_scheduler: *mut c_void,
+    subscription: *mut c_void,
+) -> *mut c_char {
+    execute_sync(|| {
+        let subscription_scheduler =
+            subscription_scheduler_from_native_ptr(subscription_scheduler)?;
+
+        subscription_scheduler.remove(ptr_to_handle(subscription));
+
+        Ok(serde_json::Value::Null)
+    })
+}
+
+/// Polls the subscriptions until the operation is cancelled, only one run at a time
+/// is allowed.
+#[no_mangle]
+pub unsafe extern "C" fn nt_subscription_scheduler_run(
+    result_port: c_longlong,
+    subscription_scheduler: *mut c_void,
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let subscription_scheduler =
+            subscription_scheduler_from_native_ptr(subscription_scheduler)?;
+
+        Ok(async move {
+            async fn internal_fn(
+                subscription_scheduler: Arc<SubscriptionScheduler>,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                subscription_scheduler.run().await?;
+
+                Ok(serde_json::Value::Null)
+            }
+
+            internal_fn(subscription_scheduler).await
+        })
+    })
+}
+
+ffi_handle!(subscription_scheduler, Arc<SubscriptionScheduler>);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tests::{emulated_transport, raw_transport};
+
+    #[test]
+    fn idle_interval_grows_until_changed() {
+        let scheduler = SubscriptionScheduler::new(
+            BlockWalker::Polling(raw_transport(&emulated_transport())),
+            SubscriptionSchedulerConfig {
+                manual_interval_ms: Some(100),
+                reliable_interval_ms: None,
+                max_idle_interval_ms: Some(1000),
+                concurrency: None,
+            },
+        )
+        .unwrap();
+
+        let mut entry = ScheduleEntry {
+            kind: SubscriptionKind::TonWallet,
+            next_poll_at: Instant::now(),
+            idle_polls: 0,
+            is_polling: false,
+            block_walk: None,
+        };
+        let mut poll = |is_changed| scheduler.idle_interval(&mut entry, is_changed).as_millis();
+
+        let intervals = (0..6).map(|_| poll(false)).collect::<Vec<_>>();
+        assert_eq!(intervals, [200, 400, 800, 1000, 1000, 1000]);
+
+        assert_eq!(poll(true), 100);
+        assert_eq!(poll(false), 200);
+    }
+}
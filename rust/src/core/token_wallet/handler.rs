@@ -1,4 +1,3 @@
-use allo_isolate::Isolate;
 use async_trait::async_trait;
 use nekoton::core::{
     models::{TokenWalletTransaction, TransactionWithData, TransactionsBatchInfo},
@@ -6,16 +5,18 @@ use nekoton::core::{
 };
 use nekoton_abi::num_bigint::BigUint;
 
+use crate::{host::HostPort, PostSerialized};
+
 pub struct TokenWalletSubscriptionHandlerImpl {
-    on_balance_changed_port: Isolate,
-    on_transactions_found_port: Isolate,
+    on_balance_changed_port: HostPort,
+    on_transactions_found_port: HostPort,
 }
 
 impl TokenWalletSubscriptionHandlerImpl {
     pub fn new(on_balance_changed_port: i64, on_transactions_found_port: i64) -> Self {
         Self {
-            on_balance_changed_port: Isolate::new(on_balance_changed_port),
-            on_transactions_found_port: Isolate::new(on_transactions_found_port),
+            on_balance_changed_port: HostPort::event(on_balance_changed_port),
+            on_transactions_found_port: HostPort::event(on_transactions_found_port),
         }
     }
 }
@@ -33,8 +34,7 @@ impl TokenWalletSubscriptionHandler for TokenWalletSubscriptionHandlerImpl {
         transactions: Vec<TransactionWithData<TokenWalletTransaction>>,
         batch_info: TransactionsBatchInfo,
     ) {
-        let payload = serde_json::to_string(&(transactions, batch_info)).unwrap();
-
-        self.on_transactions_found_port.post(payload);
+        self.on_transactions_found_port
+            .post_serialized(&(transactions, batch_info));
     }
 }
@@ -1,4 +1,3 @@
-use allo_isolate::Isolate;
 use async_trait::async_trait;
 use nekoton::core::{
     models::{
@@ -8,11 +7,13 @@ use nekoton::core::{
     ton_wallet::TonWalletSubscriptionHandler,
 };
 
+use crate::{host::HostPort, PostSerialized};
+
 pub struct TonWalletSubscriptionHandlerImpl {
-    on_message_sent_port: Isolate,
-    on_message_expired_port: Isolate,
-    on_state_changed_port: Isolate,
-    on_transactions_found_port: Isolate,
+    on_message_sent_port: HostPort,
+    on_message_expired_port: HostPort,
+    on_state_changed_port: HostPort,
+    on_transactions_found_port: HostPort,
 }
 
 impl TonWalletSubscriptionHandlerImpl {
@@ -23,10 +24,10 @@ impl TonWalletSubscriptionHandlerImpl {
         on_transactions_found_port: i64,
     ) -> Self {
         Self {
-            on_message_sent_port: Isolate::new(on_message_sent_port),
-            on_message_expired_port: Isolate::new(on_message_expired_port),
-            on_state_changed_port: Isolate::new(on_state_changed_port),
-            on_transactions_found_port: Isolate::new(on_transactions_found_port),
+            on_message_sent_port: HostPort::event(on_message_sent_port),
+            on_message_expired_port: HostPort::event(on_message_expired_port),
+            on_state_changed_port: HostPort::event(on_state_changed_port),
+            on_transactions_found_port: HostPort::event(on_transactions_found_port),
         }
     }
 }
@@ -38,21 +39,17 @@ impl TonWalletSubscriptionHandler for TonWalletSubscriptionHandlerImpl {
         pending_transaction: PendingTransaction,
         transaction: Option<Transaction>,
     ) {
-        let payload = serde_json::to_string(&(pending_transaction, transaction)).unwrap();
-
-        self.on_message_sent_port.post(payload);
+        self.on_message_sent_port
+            .post_serialized(&(pending_transaction, transaction));
     }
 
     fn on_message_expired(&self, pending_transaction: PendingTransaction) {
-        let payload = serde_json::to_string(&pending_transaction).unwrap();
-
-        self.on_message_expired_port.post(payload);
+        self.on_message_expired_port
+            .post_serialized(&pending_transaction);
     }
 
     fn on_state_changed(&self, new_state: ContractState) {
-        let payload = serde_json::to_string(&new_state).unwrap();
-
-        self.on_state_changed_port.post(payload);
+        self.on_state_changed_port.post_serialized(&new_state);
     }
 
     fn on_transactions_found(
@@ -60,8 +57,7 @@ impl TonWalletSubscriptionHandler for TonWalletSubscriptionHandlerImpl {
         transactions: Vec<TransactionWithData<TransactionAdditionalInfo>>,
         batch_info: TransactionsBatchInfo,
     ) {
-        let payload = serde_json::to_string(&(transactions, batch_info)).unwrap();
-
-        self.on_transactions_found_port.post(payload);
+        self.on_transactions_found_port
+            .post_serialized(&(transactions, batch_info));
     }
 }
@@ -4,98 +4,92 @@ pub mod ledger_key;
 mod mnemonic;
 pub mod models;
 use std::{
-    os::raw::{c_char, c_longlong, c_void},
+    os::raw::{c_char, c_longlong, c_ulonglong, c_void},
     sync::Arc,
 };
 
-use allo_isolate::Isolate;
 use ed25519_dalek::Verifier;
 use nekoton::crypto::UnsignedMessage;
 use tokio::sync::RwLock;
 
 use crate::{
-    clock, ffi_box, parse_public_key, runtime, HandleError, MatchResult, PostWithResult,
-    ToPtrAddress, ToStringFromPtr, CLOCK, RUNTIME,
+    clock,
+    errors::{ErrorCode, ExecutionError},
+    execute_async, execute_sync, ffi_handle, parse_public_key, HandleError, ToStringFromPtr, CLOCK,
 };
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_unsigned_message_refresh_timeout(
     result_port: c_longlong,
     unsigned_message: *mut c_void,
-) {
-    let unsigned_message = unsigned_message_from_native_ptr(unsigned_message).clone();
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let unsigned_message = unsigned_message_from_native_ptr(unsigned_message)?;
 
-    runtime!().spawn(async move {
-        fn internal_fn(
-            unsigned_message: &mut Box<dyn UnsignedMessage>,
-        ) -> Result<serde_json::Value, String> {
-            unsigned_message.refresh_timeout(clock!().as_ref());
-
-            Ok(serde_json::Value::Null)
-        }
+        Ok(async move {
+            fn internal_fn(
+                unsigned_message: &mut Box<dyn UnsignedMessage>,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                unsigned_message.refresh_timeout(clock!().as_ref());
 
-        let mut unsigned_message = unsigned_message.write().await;
+                Ok(serde_json::Value::Null)
+            }
 
-        let result = internal_fn(&mut unsigned_message).match_result();
+            let mut unsigned_message = unsigned_message.write().await;
 
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+            internal_fn(&mut unsigned_message)
+        })
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_unsigned_message_expire_at(
     result_port: c_longlong,
     unsigned_message: *mut c_void,
-) {
-    let unsigned_message = unsigned_message_from_native_ptr(unsigned_message).clone();
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let unsigned_message = unsigned_message_from_native_ptr(unsigned_message)?;
 
-    runtime!().spawn(async move {
-        fn internal_fn(
-            unsigned_message: &Box<dyn UnsignedMessage>,
-        ) -> Result<serde_json::Value, String> {
-            let expire_at = unsigned_message.expire_at();
+        Ok(async move {
+            fn internal_fn(
+                unsigned_message: &Box<dyn UnsignedMessage>,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let expire_at = unsigned_message.expire_at();
 
-            serde_json::to_value(expire_at).handle_error()
-        }
+                serde_json::to_value(expire_at).handle_error()
+            }
 
-        let unsigned_message = unsigned_message.read().await;
+            let unsigned_message = unsigned_message.read().await;
 
-        let result = internal_fn(&unsigned_message).match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+            internal_fn(&unsigned_message)
+        })
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_unsigned_message_hash(
     result_port: c_longlong,
     unsigned_message: *mut c_void,
-) {
-    let unsigned_message = unsigned_message_from_native_ptr(unsigned_message);
-
-    runtime!().spawn(async move {
-        fn internal_fn(
-            unsigned_message: &Box<dyn UnsignedMessage>,
-        ) -> Result<serde_json::Value, String> {
-            let hash = unsigned_message.hash();
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let unsigned_message = unsigned_message_from_native_ptr(unsigned_message)?;
 
-            let hash = base64::encode(hash);
+        Ok(async move {
+            fn internal_fn(
+                unsigned_message: &Box<dyn UnsignedMessage>,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let hash = unsigned_message.hash();
 
-            serde_json::to_value(hash).handle_error()
-        }
+                let hash = base64::encode(hash);
 
-        let unsigned_message = unsigned_message.read().await;
+                serde_json::to_value(hash).handle_error()
+            }
 
-        let result = internal_fn(&unsigned_message).match_result();
+            let unsigned_message = unsigned_message.read().await;
 
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+            internal_fn(&unsigned_message)
+        })
+    })
 }
 
 #[no_mangle]
@@ -103,61 +97,61 @@ pub unsafe extern "C" fn nt_unsigned_message_sign(
     result_port: c_longlong,
     unsigned_message: *mut c_void,
     signature: *mut c_char,
-) {
-    let unsigned_message = unsigned_message_from_native_ptr(unsigned_message);
-
-    let signature = signature.to_string_from_ptr();
-
-    runtime!().spawn(async move {
-        fn internal_fn(
-            unsigned_message: &Box<dyn UnsignedMessage>,
-            signature: String,
-        ) -> Result<serde_json::Value, String> {
-            let signature: [u8; ed25519_dalek::SIGNATURE_LENGTH] = base64::decode(signature)
-                .handle_error()?
-                .as_slice()
-                .try_into()
-                .handle_error()?;
-
-            let signed_message = unsigned_message.sign(&signature).handle_error()?;
-
-            serde_json::to_value(signed_message).handle_error()
-        }
-
-        let unsigned_message = unsigned_message.read().await;
-
-        let result = internal_fn(&unsigned_message, signature).match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let unsigned_message = unsigned_message_from_native_ptr(unsigned_message)?;
+
+        let signature = signature.to_string_from_ptr();
+
+        Ok(async move {
+            fn internal_fn(
+                unsigned_message: &Box<dyn UnsignedMessage>,
+                signature: String,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let signature: [u8; ed25519_dalek::SIGNATURE_LENGTH] = base64::decode(signature)
+                    .handle_error()?
+                    .as_slice()
+                    .try_into()
+                    .handle_error()?;
+
+                let signed_message = unsigned_message
+                    .sign(&signature)
+                    .handle_error_as(ErrorCode::SigningFailed)?;
+
+                serde_json::to_value(signed_message).handle_error()
+            }
+
+            let unsigned_message = unsigned_message.read().await;
+
+            internal_fn(&unsigned_message, signature)
+        })
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_unsigned_message_sign_fake(
     result_port: c_longlong,
     unsigned_message: *mut c_void,
-) {
-    let unsigned_message = unsigned_message_from_native_ptr(unsigned_message);
-
-    runtime!().spawn(async move {
-        fn internal_fn(
-            unsigned_message: &Box<dyn UnsignedMessage>,
-        ) -> Result<serde_json::Value, String> {
-            let signed_message = unsigned_message.sign(&[0; 64]).handle_error()?;
-
-            serde_json::to_value(signed_message).handle_error()
-        }
-
-        let unsigned_message = unsigned_message.read().await;
-
-        let result = internal_fn(&unsigned_message).match_result();
-
-        Isolate::new(result_port)
-            .post_with_result(result.to_ptr_address())
-            .unwrap();
-    });
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let unsigned_message = unsigned_message_from_native_ptr(unsigned_message)?;
+
+        Ok(async move {
+            fn internal_fn(
+                unsigned_message: &Box<dyn UnsignedMessage>,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let signed_message = unsigned_message
+                    .sign(&[0; 64])
+                    .handle_error_as(ErrorCode::SigningFailed)?;
+
+                serde_json::to_value(signed_message).handle_error()
+            }
+
+            let unsigned_message = unsigned_message.read().await;
+
+            internal_fn(&unsigned_message)
+        })
+    })
 }
 
 #[no_mangle]
@@ -166,48 +160,58 @@ pub unsafe extern "C" fn nt_verify_signature(
     data_hash: *mut c_char,
     signature: *mut c_char,
 ) -> *mut c_char {
-    let public_key = public_key.to_string_from_ptr();
-    let data_hash = data_hash.to_string_from_ptr();
-    let signature = signature.to_string_from_ptr();
-
-    fn internal_fn(
-        public_key: String,
-        data_hash: String,
-        signature: String,
-    ) -> Result<serde_json::Value, String> {
-        let public_key = parse_public_key(&public_key).handle_error()?;
-
-        let data_hash = match hex::decode(&data_hash) {
-            Ok(data_hash) => data_hash,
-            Err(e) => match base64::decode(&data_hash) {
-                Ok(data_hash) => data_hash,
-                Err(_) => return Err(e).handle_error(),
-            },
-        };
+    execute_sync(|| {
+        let public_key = public_key.to_string_from_ptr();
+        let data_hash = data_hash.to_string_from_ptr();
+        let signature = signature.to_string_from_ptr();
 
-        if data_hash.len() != 32 {
-            return Err("Invalid data hash. Expected 32 bytes").handle_error();
-        }
+        fn internal_fn(
+            public_key: String,
+            data_hash: String,
+            signature: String,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let public_key = parse_public_key(&public_key)?;
 
-        let signature = match base64::decode(&signature) {
-            Ok(signature) => signature,
-            Err(e) => match hex::decode(&signature) {
+            let data_hash = match hex::decode(&data_hash) {
+                Ok(data_hash) => data_hash,
+                Err(e) => match base64::decode(&data_hash) {
+                    Ok(data_hash) => data_hash,
+                    Err(_) => return Err(e).handle_error_as(ErrorCode::InvalidHash),
+                },
+            };
+
+            if data_hash.len() != 32 {
+                return Err(ExecutionError::new(
+                    ErrorCode::InvalidHash,
+                    "Invalid data hash. Expected 32 bytes",
+                ));
+            }
+
+            let signature = match base64::decode(&signature) {
                 Ok(signature) => signature,
-                Err(_) => return Err(e).handle_error(),
-            },
-        };
+                Err(e) => match hex::decode(&signature) {
+                    Ok(signature) => signature,
+                    Err(_) => return Err(e).handle_error_as(ErrorCode::InvalidSignature),
+                },
+            };
 
-        let signature = match ed25519_dalek::Signature::try_from(signature.as_slice()) {
-            Ok(signature) => signature,
-            Err(_) => return Err("Invalid signature. Expected 64 bytes").handle_error(),
-        };
+            let signature = match ed25519_dalek::Signature::try_from(signature.as_slice()) {
+                Ok(signature) => signature,
+                Err(_) => {
+                    return Err(ExecutionError::new(
+                        ErrorCode::InvalidSignature,
+                        "Invalid signature. Expected 64 bytes",
+                    ))
+                },
+            };
 
-        let is_valid = public_key.verify(&data_hash, &signature).is_ok();
+            let is_valid = public_key.verify(&data_hash, &signature).is_ok();
 
-        serde_json::to_value(is_valid).handle_error()
-    }
+            serde_json::to_value(is_valid).handle_error()
+        }
 
-    internal_fn(public_key, data_hash, signature).match_result()
+        internal_fn(public_key, data_hash, signature)
+    })
 }
 
-ffi_box!(unsigned_message, Arc<RwLock<Box<dyn UnsignedMessage>>>);
+ffi_handle!(unsigned_message, Arc<RwLock<Box<dyn UnsignedMessage>>>);
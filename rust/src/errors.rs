@@ -0,0 +1,185 @@
+use std::{any::Any, collections::BTreeMap};
+
+use serde::Serialize;
+
+/// Broad class of a failure, lets the Dart side decide how to present it
+/// without inspecting the concrete code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorCategory {
+    Transport,
+    Abi,
+    Keystore,
+    Validation,
+    Internal,
+}
+
+/// Stable machine-readable error code.
+///
+/// Serialized names are part of the FFI contract, variants must only be added, never renamed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorCode {
+    InvalidInput,
+    InvalidAddress,
+    InvalidPublicKey,
+    InvalidHash,
+    InvalidBoc,
+    InvalidSignature,
+    InvalidPointer,
+    InvalidHandle,
+    UnknownSigner,
+    AccountNotExists,
+    DeployFirst,
+    PreparationFailed,
+
+    RequestFailed,
+    RequestTimeout,
+    RateLimited,
+    SubscriptionFailed,
+    SendFailed,
+
+    InvalidAbi,
+    EncodingFailed,
+    DecodingFailed,
+    ExecutionFailed,
+
+    KeystoreFailed,
+    SigningFailed,
+    EncryptionFailed,
+    DecryptionFailed,
+    LedgerUserRejected,
+    LedgerDeviceLocked,
+    LedgerWrongApp,
+
+    StorageFailed,
+    PortPostFailed,
+    RuntimeFailed,
+    Panic,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn category(self) -> ErrorCategory {
+        match self {
+            Self::InvalidInput
+            | Self::InvalidAddress
+            | Self::InvalidPublicKey
+            | Self::InvalidHash
+            | Self::InvalidBoc
+            | Self::InvalidSignature
+            | Self::InvalidPointer
+            | Self::InvalidHandle
+            | Self::UnknownSigner
+            | Self::AccountNotExists
+            | Self::DeployFirst
+            | Self::PreparationFailed => ErrorCategory::Validation,
+            Self::RequestFailed
+            | Self::RequestTimeout
+            | Self::RateLimited
+            | Self::SubscriptionFailed
+            | Self::SendFailed => ErrorCategory::Transport,
+            Self::InvalidAbi
+            | Self::EncodingFailed
+            | Self::DecodingFailed
+            | Self::ExecutionFailed => ErrorCategory::Abi,
+            Self::KeystoreFailed
+            | Self::SigningFailed
+            | Self::EncryptionFailed
+            | Self::DecryptionFailed
+            | Self::LedgerUserRejected
+            | Self::LedgerDeviceLocked
+            | Self::LedgerWrongApp => ErrorCategory::Keystore,
+            Self::StorageFailed
+            | Self::PortPostFailed
+            | Self::RuntimeFailed
+            | Self::Panic
+            | Self::Internal => ErrorCategory::Internal,
+        }
+    }
+}
+
+/// Error passed to Dart inside `ExecutionResult::Err`.
+#[derive(Clone, Debug, Serialize, thiserror::Error)]
+#[serde(rename_all = "camelCase")]
+#[error("{message}")]
+pub struct ExecutionError {
+    pub code: ErrorCode,
+    pub category: ErrorCategory,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub causes: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub context: BTreeMap<String, String>,
+}
+
+impl ExecutionError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            category: code.category(),
+            message: message.into(),
+            causes: Vec::new(),
+            context: BTreeMap::new(),
+        }
+    }
+
+    /// Keeps an already typed error as is, otherwise wraps `error` with the given code
+    /// and collects its source chain.
+    pub fn from_anyhow(code: ErrorCode, error: anyhow::Error) -> Self {
+        match error.downcast::<Self>() {
+            Ok(error) => error,
+            Err(error) => {
+                let mut chain = error.chain().map(ToString::to_string);
+
+                let message = chain.next().unwrap_or_default();
+                let causes = chain.collect();
+
+                Self {
+                    causes,
+                    ..Self::new(code, message)
+                }
+            },
+        }
+    }
+
+    /// Converts a payload caught by `catch_unwind` into an error.
+    pub fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast::<&'static str>() {
+                Ok(message) => (*message).to_owned(),
+                Err(_) => "Unknown panic".to_owned(),
+            },
+        };
+
+        Self::new(ErrorCode::Panic, message)
+    }
+
+    pub fn with_context(mut self, key: &str, value: impl ToString) -> Self {
+        self.context.insert(key.to_owned(), value.to_string());
+        self
+    }
+}
+
+impl From<anyhow::Error> for ExecutionError {
+    fn from(error: anyhow::Error) -> Self {
+        let code = classify(&error);
+        Self::from_anyhow(code, error)
+    }
+}
+
+/// Errors produced while parsing arguments received from Dart are reported as invalid input,
+/// everything else is internal unless the call site specifies a code.
+fn classify(error: &anyhow::Error) -> ErrorCode {
+    if error.is::<serde_json::Error>()
+        || error.is::<base64::DecodeError>()
+        || error.is::<hex::FromHexError>()
+        || error.is::<std::num::ParseIntError>()
+        || error.is::<std::array::TryFromSliceError>()
+    {
+        ErrorCode::InvalidInput
+    } else {
+        ErrorCode::Internal
+    }
+}
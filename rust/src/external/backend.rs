@@ -0,0 +1,167 @@
+use std::{future::Future, time::Duration};
+
+use anyhow::Result;
+
+#[cfg(feature = "native_connections")]
+use crate::external::native::{NativeClient, NativeConnectionConfig};
+use crate::{
+    channel_err_from_native_ptr_owned, channel_err_new,
+    errors::{ErrorCode, ExecutionError},
+    external::{
+        recording::{ConnectionKind, Recorder, Replayer},
+        request::post_request,
+        retry::{is_idempotent, ConnectionError, ErrorClass, RetryPolicy},
+    },
+    host::HostPort,
+};
+
+/// Source of responses for transport connections.
+pub enum ConnectionBackend {
+    /// Requests are sent to the host, responses are optionally written to a session file.
+    ///
+    /// Failed requests are retried according to the policy, only the final result is recorded.
+    Host {
+        kind: ConnectionKind,
+        port: HostPort,
+        timeout: Option<Duration>,
+        retry_policy: RetryPolicy,
+        recorder: Option<Recorder>,
+    },
+    /// Requests are posted over HTTP right from Rust.
+    #[cfg(feature = "native_connections")]
+    Native {
+        client: NativeClient,
+        retry_policy: RetryPolicy,
+    },
+    /// Responses are served from a previously recorded session file.
+    Replay(Replayer),
+}
+
+impl ConnectionBackend {
+    pub fn host(
+        kind: ConnectionKind,
+        port: i64,
+        timeout: Option<Duration>,
+        retry_policy: RetryPolicy,
+    ) -> Self {
+        Self::Host {
+            kind,
+            port: HostPort::request(port),
+            timeout,
+            retry_policy,
+            recorder: None,
+        }
+    }
+
+    pub fn recording(
+        kind: ConnectionKind,
+        port: i64,
+        timeout: Option<Duration>,
+        retry_policy: RetryPolicy,
+        path: &str,
+    ) -> Result<Self, ExecutionError> {
+        Ok(Self::Host {
+            kind,
+            port: HostPort::request(port),
+            timeout,
+            retry_policy,
+            recorder: Some(Recorder::open(kind, path)?),
+        })
+    }
+
+    #[cfg(feature = "native_connections")]
+    pub fn native(
+        kind: ConnectionKind,
+        config: NativeConnectionConfig,
+    ) -> Result<Self, ExecutionError> {
+        let (client, retry_policy) = NativeClient::new(kind, config)?;
+
+        Ok(Self::Native {
+            client,
+            retry_policy,
+        })
+    }
+
+    pub fn replay(kind: ConnectionKind, path: &str) -> Result<Self, ExecutionError> {
+        Ok(Self::Replay(Replayer::load(kind, path)?))
+    }
+
+    pub async fn post(&self, data: String) -> Result<String> {
+        match self {
+            Self::Host {
+                kind,
+                port,
+                timeout,
+                retry_policy,
+                recorder,
+            } => {
+                let response = with_retries(*kind, retry_policy, &data, || async {
+                    post_request(
+                        port,
+                        *timeout,
+                        channel_err_new,
+                        channel_err_from_native_ptr_owned,
+                        |tx| serde_json::to_string(&(tx, &data)),
+                    )
+                    .await
+                    .map_err(ConnectionError::from_host)
+                })
+                .await;
+
+                if let Some(recorder) = recorder {
+                    recorder.record(&data, &response);
+                }
+
+                response
+            },
+            #[cfg(feature = "native_connections")]
+            Self::Native {
+                client,
+                retry_policy,
+            } => with_retries(client.kind(), retry_policy, &data, || client.post(&data)).await,
+            Self::Replay(replayer) => replayer.replay(&data),
+        }
+    }
+}
+
+async fn with_retries<F, Fut>(
+    kind: ConnectionKind,
+    retry_policy: &RetryPolicy,
+    data: &str,
+    mut send: F,
+) -> Result<String>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let idempotent = is_idempotent(kind, data);
+
+    let mut attempt = 1;
+    loop {
+        let err = match send().await {
+            Ok(response) => return Ok(response),
+            Err(err) => err,
+        };
+
+        let class = ErrorClass::of(&err);
+        match retry_policy.backoff(attempt, class, idempotent) {
+            Some(delay) => {
+                log::debug!(
+                    "Retrying {:?} request after attempt {} in {:?}: {:#}",
+                    kind,
+                    attempt,
+                    delay,
+                    err
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            },
+            None if retry_policy.max_attempts > 1 => {
+                let err = ExecutionError::from_anyhow(ErrorCode::RequestFailed, err)
+                    .with_context("attempts", attempt);
+                return Err(err.into());
+            },
+            None => return Err(err),
+        }
+    }
+}
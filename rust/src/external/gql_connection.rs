@@ -1,30 +1,34 @@
 use std::{
-    os::raw::{c_char, c_longlong, c_uint},
+    os::raw::{c_char, c_longlong, c_uint, c_ulonglong},
     sync::Arc,
+    time::Duration,
 };
 
-use allo_isolate::Isolate;
-use anyhow::{bail, Result};
+use anyhow::Result;
 use async_trait::async_trait;
 use nekoton::external::{GqlConnection, GqlRequest};
-use tokio::sync::oneshot::channel;
 
+#[cfg(feature = "native_connections")]
+use crate::{errors::ErrorCode, external::native::NativeConnectionConfig};
 use crate::{
-    channel_err_new, nt_channel_err_free_ptr, transport::gql_connection_new, HandleError,
-    MatchResult, ToPtrAddress, ToPtrFromAddress, ISOLATE_MESSAGE_POST_ERROR,
+    errors::ExecutionError,
+    execute_sync,
+    external::{
+        backend::ConnectionBackend, recording::ConnectionKind, request::timeout_from_ms,
+        retry::RetryPolicy,
+    },
+    transport::gql_connection_new,
+    HandleError, ToOptionalStringFromPtr, ToPtrAddress, ToStringFromPtr,
 };
 
 pub struct GqlConnectionImpl {
     is_local: bool,
-    port: Isolate,
+    backend: ConnectionBackend,
 }
 
 impl GqlConnectionImpl {
-    pub fn new(is_local: bool, port: i64) -> Self {
-        Self {
-            is_local,
-            port: Isolate::new(port),
-        }
+    pub fn new(is_local: bool, backend: ConnectionBackend) -> Self {
+        Self { is_local, backend }
     }
 }
 
@@ -35,40 +39,132 @@ impl GqlConnection for GqlConnectionImpl {
     }
 
     async fn post(&self, req: GqlRequest) -> Result<String> {
-        let (tx, rx) = channel::<Result<String>>();
+        self.backend.post(req.data).await
+    }
+}
 
-        let tx = channel_err_new(tx).to_ptr_address();
-        let data = req.data;
+#[no_mangle]
+pub unsafe extern "C" fn nt_gql_connection_create(
+    is_local: c_uint,
+    port: c_longlong,
+    timeout_ms: c_ulonglong,
+    retry_policy: *mut c_char,
+) -> *mut c_char {
+    execute_sync(|| {
+        let is_local = is_local != 0;
+        let timeout = timeout_from_ms(timeout_ms);
+        let retry_policy = retry_policy.to_optional_string_from_ptr();
 
-        let request = serde_json::to_string(&(tx.clone(), data))?;
+        fn internal_fn(
+            is_local: bool,
+            port: i64,
+            timeout: Option<Duration>,
+            retry_policy: Option<String>,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let retry_policy = RetryPolicy::from_json(retry_policy)?;
+            let gql_connection = GqlConnectionImpl::new(
+                is_local,
+                ConnectionBackend::host(ConnectionKind::Gql, port, timeout, retry_policy),
+            );
 
-        match self.port.post(request) {
-            true => rx.await.unwrap(),
-            false => {
-                unsafe {
-                    nt_channel_err_free_ptr(tx.to_ptr_from_address());
-                }
+            let ptr = gql_connection_new(Arc::new(gql_connection));
 
-                bail!(ISOLATE_MESSAGE_POST_ERROR)
-            },
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
         }
-    }
+
+        internal_fn(is_local, port, timeout, retry_policy)
+    })
 }
 
+/// Same as `nt_gql_connection_create`, every request is also appended to the session file.
 #[no_mangle]
-pub unsafe extern "C" fn nt_gql_connection_create(
+pub unsafe extern "C" fn nt_gql_connection_create_recording(
     is_local: c_uint,
     port: c_longlong,
+    timeout_ms: c_ulonglong,
+    retry_policy: *mut c_char,
+    path: *mut c_char,
 ) -> *mut c_char {
-    let is_local = is_local != 0;
+    execute_sync(|| {
+        let is_local = is_local != 0;
+        let timeout = timeout_from_ms(timeout_ms);
+        let retry_policy = retry_policy.to_optional_string_from_ptr();
+        let path = path.to_string_from_ptr();
 
-    fn internal_fn(is_local: bool, port: i64) -> Result<serde_json::Value, String> {
-        let gql_connection = GqlConnectionImpl::new(is_local, port);
+        fn internal_fn(
+            is_local: bool,
+            port: i64,
+            timeout: Option<Duration>,
+            retry_policy: Option<String>,
+            path: String,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let retry_policy = RetryPolicy::from_json(retry_policy)?;
+            let backend = ConnectionBackend::recording(
+                ConnectionKind::Gql,
+                port,
+                timeout,
+                retry_policy,
+                &path,
+            )?;
+            let gql_connection = GqlConnectionImpl::new(is_local, backend);
 
-        let ptr = gql_connection_new(Arc::new(gql_connection));
+            let ptr = gql_connection_new(Arc::new(gql_connection));
 
-        serde_json::to_value(ptr.to_ptr_address()).handle_error()
-    }
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
+        }
+
+        internal_fn(is_local, port, timeout, retry_policy, path)
+    })
+}
+
+/// Connection which answers requests from a session file instead of the network.
+#[no_mangle]
+pub unsafe extern "C" fn nt_gql_connection_create_replay(
+    is_local: c_uint,
+    path: *mut c_char,
+) -> *mut c_char {
+    execute_sync(|| {
+        let is_local = is_local != 0;
+        let path = path.to_string_from_ptr();
+
+        fn internal_fn(is_local: bool, path: String) -> Result<serde_json::Value, ExecutionError> {
+            let backend = ConnectionBackend::replay(ConnectionKind::Gql, &path)?;
+            let gql_connection = GqlConnectionImpl::new(is_local, backend);
+
+            let ptr = gql_connection_new(Arc::new(gql_connection));
+
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
+        }
+
+        internal_fn(is_local, path)
+    })
+}
+
+/// Connection which posts requests over HTTP from Rust, `config` is a `NativeConnectionConfig`.
+#[cfg(feature = "native_connections")]
+#[no_mangle]
+pub unsafe extern "C" fn nt_gql_connection_create_native(
+    is_local: c_uint,
+    config: *mut c_char,
+) -> *mut c_char {
+    execute_sync(|| {
+        let is_local = is_local != 0;
+        let config = config.to_string_from_ptr();
+
+        fn internal_fn(
+            is_local: bool,
+            config: String,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let config = serde_json::from_str::<NativeConnectionConfig>(&config)
+                .handle_error_as(ErrorCode::InvalidInput)?;
+            let backend = ConnectionBackend::native(ConnectionKind::Gql, config)?;
+            let gql_connection = GqlConnectionImpl::new(is_local, backend);
+
+            let ptr = gql_connection_new(Arc::new(gql_connection));
+
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
+        }
 
-    internal_fn(is_local, port).match_result()
+        internal_fn(is_local, config)
+    })
 }
@@ -1,64 +1,147 @@
 use std::{
-    os::raw::{c_char, c_longlong},
+    os::raw::{c_char, c_longlong, c_ulonglong},
     sync::Arc,
+    time::Duration,
 };
 
-use allo_isolate::Isolate;
-use anyhow::{bail, Result};
+use anyhow::Result;
 use async_trait::async_trait;
 use nekoton::external::{JrpcConnection, JrpcRequest};
-use tokio::sync::oneshot::channel;
 
+#[cfg(feature = "native_connections")]
+use crate::{errors::ErrorCode, external::native::NativeConnectionConfig};
 use crate::{
-    channel_err_new, ffi_box, nt_channel_err_free_ptr, HandleError, MatchResult, ToPtrAddress,
-    ToPtrFromAddress, ISOLATE_MESSAGE_POST_ERROR,
+    errors::ExecutionError,
+    execute_sync,
+    external::{
+        backend::ConnectionBackend, recording::ConnectionKind, request::timeout_from_ms,
+        retry::RetryPolicy,
+    },
+    ffi_handle, HandleError, ToOptionalStringFromPtr, ToPtrAddress, ToStringFromPtr,
 };
 
 pub struct JrpcConnectionImpl {
-    port: Isolate,
+    backend: ConnectionBackend,
 }
 
 impl JrpcConnectionImpl {
-    pub fn new(port: i64) -> Self {
-        Self {
-            port: Isolate::new(port),
-        }
+    pub fn new(backend: ConnectionBackend) -> Self {
+        Self { backend }
     }
 }
 
 #[async_trait]
 impl JrpcConnection for JrpcConnectionImpl {
     async fn post(&self, req: JrpcRequest) -> Result<String> {
-        let (tx, rx) = channel::<Result<String>>();
+        self.backend.post(req.data).await
+    }
+}
 
-        let tx = channel_err_new(tx).to_ptr_address();
-        let data = req.data;
+#[no_mangle]
+pub unsafe extern "C" fn nt_jrpc_connection_create(
+    port: c_longlong,
+    timeout_ms: c_ulonglong,
+    retry_policy: *mut c_char,
+) -> *mut c_char {
+    execute_sync(|| {
+        let timeout = timeout_from_ms(timeout_ms);
+        let retry_policy = retry_policy.to_optional_string_from_ptr();
 
-        let request = serde_json::to_string(&(tx.clone(), data))?;
+        fn internal_fn(
+            port: i64,
+            timeout: Option<Duration>,
+            retry_policy: Option<String>,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let retry_policy = RetryPolicy::from_json(retry_policy)?;
+            let jrpc_connection = JrpcConnectionImpl::new(ConnectionBackend::host(
+                ConnectionKind::Jrpc,
+                port,
+                timeout,
+                retry_policy,
+            ));
 
-        match self.port.post(request) {
-            true => rx.await.unwrap(),
-            false => {
-                unsafe {
-                    nt_channel_err_free_ptr(tx.to_ptr_from_address());
-                }
+            let ptr = jrpc_connection_new(Arc::new(jrpc_connection));
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
+        }
 
-                bail!(ISOLATE_MESSAGE_POST_ERROR)
-            },
+        internal_fn(port, timeout, retry_policy)
+    })
+}
+
+/// Same as `nt_jrpc_connection_create`, every request is also appended to the session file.
+#[no_mangle]
+pub unsafe extern "C" fn nt_jrpc_connection_create_recording(
+    port: c_longlong,
+    timeout_ms: c_ulonglong,
+    retry_policy: *mut c_char,
+    path: *mut c_char,
+) -> *mut c_char {
+    execute_sync(|| {
+        let timeout = timeout_from_ms(timeout_ms);
+        let retry_policy = retry_policy.to_optional_string_from_ptr();
+        let path = path.to_string_from_ptr();
+
+        fn internal_fn(
+            port: i64,
+            timeout: Option<Duration>,
+            retry_policy: Option<String>,
+            path: String,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let retry_policy = RetryPolicy::from_json(retry_policy)?;
+            let backend = ConnectionBackend::recording(
+                ConnectionKind::Jrpc,
+                port,
+                timeout,
+                retry_policy,
+                &path,
+            )?;
+            let jrpc_connection = JrpcConnectionImpl::new(backend);
+
+            let ptr = jrpc_connection_new(Arc::new(jrpc_connection));
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
         }
-    }
+
+        internal_fn(port, timeout, retry_policy, path)
+    })
 }
 
+/// Connection which answers requests from a session file instead of the network.
 #[no_mangle]
-pub unsafe extern "C" fn nt_jrpc_connection_create(port: c_longlong) -> *mut c_char {
-    fn internal_fn(port: i64) -> Result<serde_json::Value, String> {
-        let jrpc_connection = JrpcConnectionImpl::new(port);
+pub unsafe extern "C" fn nt_jrpc_connection_create_replay(path: *mut c_char) -> *mut c_char {
+    execute_sync(|| {
+        let path = path.to_string_from_ptr();
 
-        let ptr = jrpc_connection_new(Arc::new(jrpc_connection));
-        serde_json::to_value(ptr.to_ptr_address()).handle_error()
-    }
+        fn internal_fn(path: String) -> Result<serde_json::Value, ExecutionError> {
+            let backend = ConnectionBackend::replay(ConnectionKind::Jrpc, &path)?;
+            let jrpc_connection = JrpcConnectionImpl::new(backend);
+
+            let ptr = jrpc_connection_new(Arc::new(jrpc_connection));
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
+        }
+
+        internal_fn(path)
+    })
+}
+
+/// Connection which posts requests over HTTP from Rust, `config` is a `NativeConnectionConfig`.
+#[cfg(feature = "native_connections")]
+#[no_mangle]
+pub unsafe extern "C" fn nt_jrpc_connection_create_native(config: *mut c_char) -> *mut c_char {
+    execute_sync(|| {
+        let config = config.to_string_from_ptr();
+
+        fn internal_fn(config: String) -> Result<serde_json::Value, ExecutionError> {
+            let config = serde_json::from_str::<NativeConnectionConfig>(&config)
+                .handle_error_as(ErrorCode::InvalidInput)?;
+            let backend = ConnectionBackend::native(ConnectionKind::Jrpc, config)?;
+            let jrpc_connection = JrpcConnectionImpl::new(backend);
+
+            let ptr = jrpc_connection_new(Arc::new(jrpc_connection));
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
+        }
 
-    internal_fn(port).match_result()
+        internal_fn(config)
+    })
 }
 
-ffi_box!(jrpc_connection, Arc<JrpcConnectionImpl>);
+ffi_handle!(jrpc_connection, Arc<JrpcConnectionImpl>);
@@ -1,31 +1,153 @@
 use std::{
-    os::raw::{c_char, c_longlong},
+    os::raw::{c_char, c_longlong, c_ulonglong},
     sync::Arc,
+    time::Duration,
 };
 
-use allo_isolate::Isolate;
-use anyhow::{bail, Result};
+use anyhow::{Context, Result};
 use async_trait::async_trait;
 use nekoton::external::{LedgerConnection, LedgerSignatureContext};
-use tokio::sync::oneshot::channel;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    channel_err_new, ffi_box, nt_channel_err_free_ptr, HandleError, MatchResult, ToPtrAddress,
-    ToPtrFromAddress, ISOLATE_MESSAGE_POST_ERROR,
+    channel_err_from_native_ptr_owned, channel_err_new,
+    errors::{ErrorCode, ExecutionError},
+    execute_sync,
+    external::request::{post_request, timeout_from_ms},
+    ffi_handle,
+    host::HostPort,
+    HandleError, ToPtrAddress,
 };
 
 pub struct LedgerConnectionImpl {
-    get_public_key_port: Isolate,
-    _sign_port: Isolate,
+    get_public_key_port: HostPort,
+    sign_port: HostPort,
+    timeout: Option<Duration>,
+}
+
+/// Sent to the sign port along with the address of the request.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LedgerSignRequest<'a> {
+    account: u16,
+    /// Only set for transactions.
+    wallet: Option<u16>,
+    signature_id: Option<i32>,
+    message: &'a [u8],
+    /// Transfer details which the device displays.
+    context: Option<LedgerSignatureContextHelper>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LedgerSignatureContextHelper {
+    decimals: u8,
+    asset: String,
+    amount: String,
+    address: String,
+}
+
+impl From<&LedgerSignatureContext> for LedgerSignatureContextHelper {
+    fn from(context: &LedgerSignatureContext) -> Self {
+        Self {
+            decimals: context.decimals,
+            asset: context.asset.clone(),
+            amount: context.amount.to_string(),
+            address: context.address.to_string(),
+        }
+    }
+}
+
+/// Error which the host resolves a sign request with when the device refused to sign.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LedgerErrorReply {
+    kind: Option<LedgerErrorKind>,
+    /// Status word returned by the device, used when `kind` is not set.
+    status_code: Option<u16>,
+    message: String,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum LedgerErrorKind {
+    UserRejected,
+    DeviceLocked,
+    WrongApp,
+}
+
+impl LedgerErrorKind {
+    fn from_status_code(status_code: u16) -> Option<Self> {
+        match status_code {
+            0x6985 => Some(Self::UserRejected),
+            0x5515 => Some(Self::DeviceLocked),
+            0x6511 | 0x6d00 | 0x6e00 => Some(Self::WrongApp),
+            _ => None,
+        }
+    }
+
+    fn code(self) -> ErrorCode {
+        match self {
+            Self::UserRejected => ErrorCode::LedgerUserRejected,
+            Self::DeviceLocked => ErrorCode::LedgerDeviceLocked,
+            Self::WrongApp => ErrorCode::LedgerWrongApp,
+        }
+    }
 }
 
 impl LedgerConnectionImpl {
-    pub fn new(get_public_key_port: i64, sign_port: i64) -> Self {
+    pub fn new(get_public_key_port: i64, sign_port: i64, timeout: Option<Duration>) -> Self {
         Self {
-            get_public_key_port: Isolate::new(get_public_key_port),
-            _sign_port: Isolate::new(sign_port),
+            get_public_key_port: HostPort::request(get_public_key_port),
+            sign_port: HostPort::request(sign_port),
+            timeout,
         }
     }
+
+    async fn request_signature(
+        &self,
+        request: LedgerSignRequest<'_>,
+    ) -> Result<[u8; ed25519_dalek::SIGNATURE_LENGTH]> {
+        let signature = post_request(
+            &self.sign_port,
+            self.timeout,
+            channel_err_new,
+            channel_err_from_native_ptr_owned,
+            |tx| serde_json::to_string(&(tx, request)),
+        )
+        .await
+        .map_err(device_error)?;
+
+        hex::decode(signature)?
+            .as_slice()
+            .try_into()
+            .context("Invalid signature length")
+    }
+}
+
+/// Replaces errors reported by the device with typed ones, other errors are kept as is.
+fn device_error(error: anyhow::Error) -> anyhow::Error {
+    let reply = match serde_json::from_str::<LedgerErrorReply>(&error.to_string()) {
+        Ok(reply) => reply,
+        Err(_) => return error,
+    };
+
+    let code = reply
+        .kind
+        .or_else(|| {
+            reply
+                .status_code
+                .and_then(LedgerErrorKind::from_status_code)
+        })
+        .map(LedgerErrorKind::code)
+        .unwrap_or(ErrorCode::SigningFailed);
+
+    let mut error = ExecutionError::new(code, reply.message);
+    if let Some(status_code) = reply.status_code {
+        error = error.with_context("statusCode", format!("0x{:04x}", status_code));
+    }
+
+    error.into()
 }
 
 #[async_trait]
@@ -34,45 +156,53 @@ impl LedgerConnection for LedgerConnectionImpl {
         &self,
         account_id: u16,
     ) -> Result<[u8; ed25519_dalek::PUBLIC_KEY_LENGTH]> {
-        let (tx, rx) = channel::<Result<String>>();
-
-        let tx = channel_err_new(tx).to_ptr_address();
-
-        let request = serde_json::to_string(&(tx.clone(), account_id))?;
-
-        match self.get_public_key_port.post(request) {
-            true => rx
-                .await
-                .unwrap()
-                .map(|e| -> [u8; ed25519_dalek::PUBLIC_KEY_LENGTH] {
-                    hex::decode(e).unwrap().as_slice().try_into().unwrap()
-                }),
-            false => {
-                unsafe { nt_channel_err_free_ptr(tx.to_ptr_from_address()) }
+        let public_key = post_request(
+            &self.get_public_key_port,
+            self.timeout,
+            channel_err_new,
+            channel_err_from_native_ptr_owned,
+            |tx| serde_json::to_string(&(tx, account_id)),
+        )
+        .await?;
 
-                bail!(ISOLATE_MESSAGE_POST_ERROR)
-            },
-        }
+        hex::decode(public_key)?
+            .as_slice()
+            .try_into()
+            .context("Invalid public key length")
     }
 
     async fn sign(
         &self,
-        _account: u16,
-        _signature_id: Option<i32>,
-        _message: &[u8],
+        account: u16,
+        signature_id: Option<i32>,
+        message: &[u8],
     ) -> Result<[u8; ed25519_dalek::SIGNATURE_LENGTH]> {
-        todo!()
+        self.request_signature(LedgerSignRequest {
+            account,
+            wallet: None,
+            signature_id,
+            message,
+            context: None,
+        })
+        .await
     }
 
     async fn sign_transaction(
         &self,
-        _account: u16,
-        _wallet: u16,
-        _signature_id: Option<i32>,
-        _message: &[u8],
-        _context: &LedgerSignatureContext,
+        account: u16,
+        wallet: u16,
+        signature_id: Option<i32>,
+        message: &[u8],
+        context: &LedgerSignatureContext,
     ) -> Result<[u8; ed25519_dalek::SIGNATURE_LENGTH]> {
-        todo!()
+        self.request_signature(LedgerSignRequest {
+            account,
+            wallet: Some(wallet),
+            signature_id,
+            message,
+            context: Some(context.into()),
+        })
+        .await
     }
 }
 
@@ -80,15 +210,25 @@ impl LedgerConnection for LedgerConnectionImpl {
 pub unsafe extern "C" fn nt_ledger_connection_create(
     get_public_key_port: c_longlong,
     sign_port: c_longlong,
+    timeout_ms: c_ulonglong,
 ) -> *mut c_char {
-    fn internal_fn(get_public_key_port: i64, sign_port: i64) -> Result<serde_json::Value, String> {
-        let ledger_connection = LedgerConnectionImpl::new(get_public_key_port, sign_port);
+    execute_sync(|| {
+        let timeout = timeout_from_ms(timeout_ms);
 
-        let ptr = ledger_connection_new(Arc::new(ledger_connection));
-        serde_json::to_value(ptr.to_ptr_address()).handle_error()
-    }
+        fn internal_fn(
+            get_public_key_port: i64,
+            sign_port: i64,
+            timeout: Option<Duration>,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let ledger_connection =
+                LedgerConnectionImpl::new(get_public_key_port, sign_port, timeout);
+
+            let ptr = ledger_connection_new(Arc::new(ledger_connection));
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
+        }
 
-    internal_fn(get_public_key_port, sign_port).match_result()
+        internal_fn(get_public_key_port, sign_port, timeout)
+    })
 }
 
-ffi_box!(ledger_connection, Arc<LedgerConnectionImpl>);
+ffi_handle!(ledger_connection, Arc<LedgerConnectionImpl>);
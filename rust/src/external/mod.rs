@@ -1,7 +1,13 @@
+pub mod backend;
 pub mod gql_connection;
 pub mod jrpc_connection;
 pub mod ledger_connection;
+#[cfg(feature = "native_connections")]
+pub mod native;
 pub mod proto_connection;
+pub mod recording;
+pub mod request;
+pub mod retry;
 pub mod storage;
 
 use std::os::raw::{c_char, c_void};
@@ -9,7 +15,7 @@ use std::os::raw::{c_char, c_void};
 use anyhow::anyhow;
 
 use crate::{
-    channel_err_from_native_ptr_owned, channel_result_option_from_native_ptr_owned,
+    catch_panic, channel_err_from_native_ptr_owned, channel_result_option_from_native_ptr_owned,
     channel_result_unit_from_native_ptr_owned, ToOptionalStringFromPtr,
 };
 
@@ -19,22 +25,30 @@ pub unsafe extern "C" fn nt_external_resolve_request_with_string(
     ok: *mut c_char,
     err: *mut c_char,
 ) {
-    let tx = channel_err_from_native_ptr_owned(tx);
+    catch_panic("nt_external_resolve_request_with_string", || {
+        let tx = match channel_err_from_native_ptr_owned(tx) {
+            Ok(tx) => tx,
+            Err(err) => {
+                log::error!("{}", err);
+                return;
+            },
+        };
 
-    let ok = ok.to_optional_string_from_ptr();
-    let err = err.to_optional_string_from_ptr();
+        let ok = ok.to_optional_string_from_ptr();
+        let err = err.to_optional_string_from_ptr();
 
-    let result = match ok {
-        Some(ok) => Ok(ok),
-        None => match err {
-            Some(err) => Err(anyhow!(err)),
-            None => panic!(),
-        },
-    };
+        let result = match ok {
+            Some(ok) => Ok(ok),
+            None => match err {
+                Some(err) => Err(anyhow!(err)),
+                None => Err(anyhow!("Request was resolved without a value")),
+            },
+        };
 
-    if let Err(e) = tx.send(result) {
-        log::error!("channel closed: {:?}", e);
-    }
+        if let Err(e) = tx.send(result) {
+            log::error!("channel closed: {:?}", e);
+        }
+    });
 }
 
 #[no_mangle]
@@ -43,36 +57,52 @@ pub unsafe extern "C" fn nt_external_resolve_request_with_optional_string(
     ok: *mut c_char,
     err: *mut c_char,
 ) {
-    let tx = channel_result_option_from_native_ptr_owned(tx);
+    catch_panic("nt_external_resolve_request_with_optional_string", || {
+        let tx = match channel_result_option_from_native_ptr_owned(tx) {
+            Ok(tx) => tx,
+            Err(err) => {
+                log::error!("{}", err);
+                return;
+            },
+        };
 
-    let ok = ok.to_optional_string_from_ptr();
-    let err = err.to_optional_string_from_ptr();
+        let ok = ok.to_optional_string_from_ptr();
+        let err = err.to_optional_string_from_ptr();
 
-    let result = match ok {
-        Some(ok) => Ok(Some(ok)),
-        None => match err {
-            Some(err) => Err(anyhow!(err)),
-            None => Ok(None),
-        },
-    };
+        let result = match ok {
+            Some(ok) => Ok(Some(ok)),
+            None => match err {
+                Some(err) => Err(anyhow!(err)),
+                None => Ok(None),
+            },
+        };
 
-    if let Err(e) = tx.send(result) {
-        log::error!("channel closed: {:?}", e);
-    }
+        if let Err(e) = tx.send(result) {
+            log::error!("channel closed: {:?}", e);
+        }
+    });
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_external_resolve_request_with_unit(tx: *mut c_void, err: *mut c_char) {
-    let tx = channel_result_unit_from_native_ptr_owned(tx);
+    catch_panic("nt_external_resolve_request_with_unit", || {
+        let tx = match channel_result_unit_from_native_ptr_owned(tx) {
+            Ok(tx) => tx,
+            Err(err) => {
+                log::error!("{}", err);
+                return;
+            },
+        };
 
-    let err = err.to_optional_string_from_ptr();
+        let err = err.to_optional_string_from_ptr();
 
-    let result = match err {
-        Some(err) => Err(anyhow!(err)),
-        None => Ok(()),
-    };
+        let result = match err {
+            Some(err) => Err(anyhow!(err)),
+            None => Ok(()),
+        };
 
-    if let Err(e) = tx.send(result) {
-        log::error!("channel closed: {:?}", e);
-    }
+        if let Err(e) = tx.send(result) {
+            log::error!("channel closed: {:?}", e);
+        }
+    });
 }
@@ -0,0 +1,171 @@
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::Result;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE},
+    Certificate, Client, Url,
+};
+use serde::Deserialize;
+
+use crate::{
+    errors::{ErrorCode, ExecutionError},
+    external::{
+        recording::ConnectionKind,
+        retry::{ConnectionError, ErrorClass, RetryPolicy},
+    },
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeConnectionConfig {
+    /// Url which every request is posted to.
+    pub endpoint: String,
+    /// Sent with every request, e.g. API keys.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Requests never time out if not set.
+    pub timeout_ms: Option<u64>,
+    pub connect_timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub tls: TlsConfig,
+    pub retry_policy: Option<RetryPolicy>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsConfig {
+    /// PEM certificates which are trusted in addition to the bundled Mozilla roots.
+    /// The certificate store of the OS is not used, so corporate CAs must be added here.
+    #[serde(default)]
+    pub root_certificates: Vec<String>,
+    /// Only for local development nodes with self-signed certificates.
+    #[serde(default)]
+    pub accept_invalid_certificates: bool,
+}
+
+/// HTTP client which posts requests from Rust directly instead of going through the host.
+pub struct NativeClient {
+    kind: ConnectionKind,
+    client: Client,
+    endpoint: Url,
+    timeout: Option<Duration>,
+}
+
+impl NativeClient {
+    pub fn new(
+        kind: ConnectionKind,
+        config: NativeConnectionConfig,
+    ) -> Result<(Self, RetryPolicy), ExecutionError> {
+        let invalid_config = |message: String| {
+            ExecutionError::new(
+                ErrorCode::InvalidInput,
+                format!("Invalid connection config: {}", message),
+            )
+        };
+
+        let endpoint = Url::parse(&config.endpoint).map_err(|e| {
+            invalid_config(e.to_string()).with_context("endpoint", &config.endpoint)
+        })?;
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &config.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| invalid_config(e.to_string()).with_context("header", name))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| invalid_config(e.to_string()).with_context("header", name.as_str()))?;
+            headers.insert(name, value);
+        }
+
+        let content_type = match kind {
+            ConnectionKind::Jrpc | ConnectionKind::Gql => "application/json",
+            ConnectionKind::Proto => "application/x-protobuf",
+        };
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+
+        let mut builder = Client::builder()
+            .default_headers(headers)
+            .danger_accept_invalid_certs(config.tls.accept_invalid_certificates);
+
+        for certificate in &config.tls.root_certificates {
+            let certificate = Certificate::from_pem(certificate.as_bytes())
+                .map_err(|e| invalid_config(e.to_string()))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+
+        let timeout = config.timeout_ms.map(Duration::from_millis);
+        if let Some(connect_timeout_ms) = config.connect_timeout_ms {
+            builder = builder.connect_timeout(Duration::from_millis(connect_timeout_ms));
+        }
+
+        let client = builder.build().map_err(|e| invalid_config(e.to_string()))?;
+
+        let retry_policy = match config.retry_policy {
+            Some(retry_policy) => retry_policy.validate()?,
+            None => RetryPolicy::default(),
+        };
+
+        let client = Self {
+            kind,
+            client,
+            endpoint,
+            timeout,
+        };
+
+        Ok((client, retry_policy))
+    }
+
+    pub fn kind(&self) -> ConnectionKind {
+        self.kind
+    }
+
+    /// Proto requests and responses are base64 encoded, the same as for the host.
+    pub async fn post(&self, data: &str) -> Result<String> {
+        let body = match self.kind {
+            ConnectionKind::Jrpc | ConnectionKind::Gql => data.as_bytes().to_vec(),
+            ConnectionKind::Proto => base64::decode(data)?,
+        };
+
+        let mut request = self.client.post(self.endpoint.clone()).body(body);
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) if e.is_timeout() => {
+                return Err(
+                    ExecutionError::new(ErrorCode::RequestTimeout, "Request timed out")
+                        .with_context("timeoutMs", self.timeout.unwrap_or_default().as_millis())
+                        .into(),
+                )
+            },
+            Err(e) => return Err(network_error(e).into()),
+        };
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(ConnectionError::status(
+                status.as_u16(),
+                format!("Endpoint responded with {}", status),
+            )
+            .into());
+        }
+
+        let response = response.bytes().await.map_err(network_error)?;
+
+        Ok(match self.kind {
+            ConnectionKind::Jrpc | ConnectionKind::Gql => String::from_utf8(response.to_vec())?,
+            ConnectionKind::Proto => base64::encode(&response),
+        })
+    }
+}
+
+/// Only failed connects are known to leave the request unsent.
+fn network_error(error: reqwest::Error) -> ConnectionError {
+    let kind = match error.is_connect() {
+        true => ErrorClass::Connect,
+        false => ErrorClass::Network,
+    };
+
+    ConnectionError::new(kind, error.to_string())
+}
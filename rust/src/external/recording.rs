@@ -0,0 +1,166 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    sync::Mutex,
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{ErrorCode, ExecutionError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConnectionKind {
+    Gql,
+    Jrpc,
+    Proto,
+}
+
+/// Line of a session file. Proto requests and responses are base64 encoded.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordedExchange {
+    connection: ConnectionKind,
+    request: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    response: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Appends every request with its response to a JSON lines session file.
+///
+/// Lines are written as soon as a response arrives, so the session survives an app crash.
+/// Several connections may record into the same file.
+pub struct Recorder {
+    kind: ConnectionKind,
+    file: Mutex<File>,
+}
+
+impl Recorder {
+    pub fn open(kind: ConnectionKind, path: &str) -> Result<Self, ExecutionError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| {
+                ExecutionError::new(ErrorCode::InvalidInput, e.to_string())
+                    .with_context("path", path)
+            })?;
+
+        Ok(Self {
+            kind,
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn record(&self, request: &str, response: &Result<String>) {
+        let (response, error) = match response {
+            Ok(response) => (Some(response.clone()), None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+
+        let exchange = RecordedExchange {
+            connection: self.kind,
+            request: request.to_owned(),
+            response,
+            error,
+        };
+
+        let mut line = match serde_json::to_string(&exchange) {
+            Ok(line) => line,
+            Err(err) => {
+                log::error!("Failed to serialize recorded request: {}", err);
+                return;
+            },
+        };
+        line.push('\n');
+
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(err) = file.write_all(line.as_bytes()) {
+            log::error!("Failed to record request: {}", err);
+        }
+    }
+}
+
+/// Serves responses from a session file written by [`Recorder`].
+///
+/// Identical requests get their recorded responses in the original order,
+/// the last one is repeated once they run out, e.g. for polling.
+pub struct Replayer {
+    kind: ConnectionKind,
+    responses: Mutex<HashMap<String, VecDeque<Result<String, String>>>>,
+}
+
+impl Replayer {
+    pub fn load(kind: ConnectionKind, path: &str) -> Result<Self, ExecutionError> {
+        let invalid_input = |message: String| {
+            ExecutionError::new(ErrorCode::InvalidInput, message).with_context("path", path)
+        };
+
+        let file = File::open(path).map_err(|e| invalid_input(e.to_string()))?;
+
+        let mut responses = HashMap::<String, VecDeque<Result<String, String>>>::new();
+
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| invalid_input(e.to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let exchange = serde_json::from_str::<RecordedExchange>(&line)
+                .map_err(|e| invalid_input(format!("Invalid line {}: {}", index + 1, e)))?;
+
+            if exchange.connection != kind {
+                continue;
+            }
+
+            let response = match (exchange.response, exchange.error) {
+                (Some(response), _) => Ok(response),
+                (None, Some(error)) => Err(error),
+                (None, None) => {
+                    return Err(invalid_input(format!(
+                        "Line {} has neither response nor error",
+                        index + 1
+                    )))
+                },
+            };
+
+            responses
+                .entry(exchange.request)
+                .or_default()
+                .push_back(response);
+        }
+
+        Ok(Self {
+            kind,
+            responses: Mutex::new(responses),
+        })
+    }
+
+    pub fn replay(&self, request: &str) -> Result<String> {
+        let mut responses = self.responses.lock().unwrap_or_else(|e| e.into_inner());
+
+        let queue = responses.get_mut(request).ok_or_else(|| {
+            anyhow!(
+                "No recorded {:?} response for request: {}",
+                self.kind,
+                request
+            )
+        })?;
+
+        let response = if queue.len() > 1 {
+            queue.pop_front()
+        } else {
+            queue.front().cloned()
+        };
+
+        match response {
+            Some(Ok(response)) => Ok(response),
+            Some(Err(error)) => Err(anyhow!(error)),
+            None => Err(anyhow!("Recorded responses are exhausted")),
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::{os::raw::c_void, time::Duration};
+
+use anyhow::{Context, Result};
+use tokio::sync::oneshot::{channel, Sender};
+
+use crate::{
+    errors::{ErrorCode, ExecutionError},
+    handles::{handle_to_ptr, ptr_to_handle},
+    host::HostPort,
+    ToPtrAddress, ISOLATE_MESSAGE_POST_ERROR,
+};
+
+type RegisterFn<T> = fn(Sender<Result<T>>) -> *mut c_void;
+
+type ReclaimFn<T> = unsafe fn(*mut c_void) -> Result<Sender<Result<T>>, ExecutionError>;
+
+/// Zero means that requests never time out.
+pub fn timeout_from_ms(timeout_ms: u64) -> Option<Duration> {
+    (timeout_ms != 0).then(|| Duration::from_millis(timeout_ms))
+}
+
+/// Sends a request to the host and waits until it is resolved with `nt_external_resolve_request_*`.
+///
+/// `request` builds the message from the address of the channel sender. The sender is kept in
+/// the handle registry while the request is outstanding and is reclaimed if the request fails
+/// to post, times out or the future is dropped.
+pub async fn post_request<T, F>(
+    port: &HostPort,
+    timeout: Option<Duration>,
+    register: RegisterFn<T>,
+    reclaim: ReclaimFn<T>,
+    request: F,
+) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(String) -> serde_json::Result<String>,
+{
+    let (tx, rx) = channel::<Result<T>>();
+
+    let tx = register(tx);
+    let mut pending = PendingRequest {
+        handle: ptr_to_handle(tx),
+        reclaim,
+    };
+
+    let request = request(tx.to_ptr_address())?;
+
+    if !port.post(request) {
+        return Err(
+            ExecutionError::new(ErrorCode::PortPostFailed, ISOLATE_MESSAGE_POST_ERROR).into(),
+        );
+    }
+
+    let result = match timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, rx).await {
+            Ok(result) => result,
+            Err(_) => {
+                return Err(
+                    ExecutionError::new(ErrorCode::RequestTimeout, "Request timed out")
+                        .with_context("timeoutMs", timeout.as_millis())
+                        .into(),
+                )
+            },
+        },
+        None => rx.await,
+    };
+
+    // The sender was already taken out of the registry by the resolver or by a shutdown
+    pending.disarm();
+
+    result.context("Request was dropped without a response")?
+}
+
+struct PendingRequest<T> {
+    handle: usize,
+    reclaim: ReclaimFn<T>,
+}
+
+impl<T> PendingRequest<T> {
+    fn disarm(&mut self) {
+        self.handle = 0;
+    }
+}
+
+impl<T> Drop for PendingRequest<T> {
+    fn drop(&mut self) {
+        if self.handle == 0 {
+            return;
+        }
+
+        // Fails if Dart has resolved the request concurrently, the sender is dropped anyway
+        let _ = unsafe { (self.reclaim)(handle_to_ptr(self.handle)) };
+    }
+}
@@ -0,0 +1,318 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    errors::{ErrorCode, ExecutionError},
+    external::recording::ConnectionKind,
+};
+
+const DEFAULT_INITIAL_BACKOFF_MS: u64 = 200;
+
+const DEFAULT_MAX_BACKOFF_MS: u64 = 5_000;
+
+const DEFAULT_MULTIPLIER: f64 = 2.0;
+
+const DEFAULT_JITTER: f64 = 0.2;
+
+/// Kind of a failed request, derived from the error returned by the host.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorClass {
+    /// The request timed out, it may have been processed.
+    Timeout,
+    /// The endpoint couldn't be reached, so the request wasn't sent.
+    Connect,
+    /// The connection broke, the request may have been processed.
+    Network,
+    /// The endpoint answered with a 5xx status.
+    Server,
+    /// The endpoint answered with 429.
+    RateLimited,
+    Other,
+}
+
+impl ErrorClass {
+    /// Only typed errors are classified, an error message alone is never enough to retry.
+    pub fn of(error: &anyhow::Error) -> Self {
+        if let Some(error) = error.downcast_ref::<ConnectionError>() {
+            return error.class();
+        }
+
+        match error.downcast_ref::<ExecutionError>() {
+            Some(error) if error.code == ErrorCode::RequestTimeout => Self::Timeout,
+            _ => Self::Other,
+        }
+    }
+
+    fn from_status_code(status_code: u16) -> Self {
+        match status_code {
+            429 => Self::RateLimited,
+            500..=599 => Self::Server,
+            _ => Self::Other,
+        }
+    }
+
+    /// Whether the request surely didn't reach the endpoint.
+    fn is_undelivered(self) -> bool {
+        matches!(self, Self::Connect | Self::RateLimited)
+    }
+}
+
+/// Failed request as reported by the host or the native client.
+///
+/// The host reports it as a JSON error `{ kind?, statusCode?, message }`,
+/// the status code takes precedence over the kind.
+#[derive(Debug, Deserialize, thiserror::Error)]
+#[serde(rename_all = "camelCase")]
+#[error("{message}")]
+pub struct ConnectionError {
+    pub kind: Option<ErrorClass>,
+    pub status_code: Option<u16>,
+    pub message: String,
+}
+
+impl ConnectionError {
+    pub fn new(kind: ErrorClass, message: impl Into<String>) -> Self {
+        Self {
+            kind: Some(kind),
+            status_code: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn status(status_code: u16, message: impl Into<String>) -> Self {
+        Self {
+            kind: None,
+            status_code: Some(status_code),
+            message: message.into(),
+        }
+    }
+
+    /// Replaces errors reported by the host with typed ones, other errors are kept as is.
+    pub fn from_host(error: anyhow::Error) -> anyhow::Error {
+        if error.is::<ExecutionError>() {
+            return error;
+        }
+
+        match serde_json::from_str::<Self>(&error.to_string()) {
+            Ok(error) => error.into(),
+            Err(_) => error,
+        }
+    }
+
+    pub fn class(&self) -> ErrorClass {
+        match (self.status_code, self.kind) {
+            (Some(status_code), _) => ErrorClass::from_status_code(status_code),
+            (None, Some(kind)) => kind,
+            (None, None) => ErrorClass::Other,
+        }
+    }
+}
+
+/// Requests which change the blockchain state, i.e. sent messages.
+///
+/// Proto requests can't be told apart without decoding them, so they are all treated as
+/// non-idempotent. GQL requests are idempotent only if every operation of the document
+/// is a query, documents which can't be parsed are not.
+pub fn is_idempotent(kind: ConnectionKind, data: &str) -> bool {
+    match kind {
+        ConnectionKind::Jrpc => serde_json::from_str::<serde_json::Value>(data)
+            .map(|request| request["method"] != "sendMessage")
+            .unwrap_or_default(),
+        ConnectionKind::Gql => match serde_json::from_str::<serde_json::Value>(data) {
+            Ok(request) => request["query"].as_str().map_or(false, is_gql_query),
+            Err(_) => is_gql_query(data),
+        },
+        ConnectionKind::Proto => false,
+    }
+}
+
+fn is_gql_query(document: &str) -> bool {
+    match gql_operation_types(document) {
+        Some(types) => {
+            types.iter().any(|ty| *ty == "query")
+                && types.iter().all(|ty| matches!(*ty, "query" | "fragment"))
+        },
+        None => false,
+    }
+}
+
+/// Keywords of the top-level definitions of a GraphQL document, `query` for the `{ .. }`
+/// shorthand. Only the structure is checked, `None` if braces or strings are unbalanced.
+fn gql_operation_types(document: &str) -> Option<Vec<&str>> {
+    let bytes = document.as_bytes();
+
+    let mut types = Vec::new();
+    let mut braces = 0usize;
+    let mut parens = 0usize;
+    let mut is_definition_start = true;
+
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            },
+            b'"' if document[i..].starts_with("\"\"\"") => {
+                i += 3 + document[i + 3..].find("\"\"\"")? + 2;
+            },
+            b'"' => {
+                i += 1;
+                while *bytes.get(i)? != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+            },
+            b'(' => parens += 1,
+            b')' => parens = parens.checked_sub(1)?,
+            b'{' => {
+                if is_definition_start {
+                    types.push("query");
+                    is_definition_start = false;
+                }
+                braces += 1;
+            },
+            b'}' => {
+                braces = braces.checked_sub(1)?;
+                is_definition_start = braces == 0 && parens == 0;
+            },
+            c if c == b'_' || c.is_ascii_alphabetic() => {
+                let start = i;
+                while i + 1 < bytes.len()
+                    && (bytes[i + 1] == b'_' || bytes[i + 1].is_ascii_alphanumeric())
+                {
+                    i += 1;
+                }
+                if is_definition_start {
+                    types.push(&document[start..=i]);
+                    is_definition_start = false;
+                }
+            },
+            _ => {},
+        }
+        i += 1;
+    }
+
+    (braces == 0 && parens == 0).then(|| types)
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryPolicy {
+    /// Total number of attempts including the first one.
+    pub max_attempts: u32,
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f64,
+    /// Fraction of the delay which is randomized, from 0 to 1.
+    #[serde(default = "default_jitter")]
+    pub jitter: f64,
+    /// Error classes which are retried, all of them except `other` by default.
+    #[serde(default = "default_retry_on")]
+    pub retry_on: Vec<ErrorClass>,
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    DEFAULT_INITIAL_BACKOFF_MS
+}
+
+fn default_max_backoff_ms() -> u64 {
+    DEFAULT_MAX_BACKOFF_MS
+}
+
+fn default_multiplier() -> f64 {
+    DEFAULT_MULTIPLIER
+}
+
+fn default_jitter() -> f64 {
+    DEFAULT_JITTER
+}
+
+fn default_retry_on() -> Vec<ErrorClass> {
+    vec![
+        ErrorClass::Timeout,
+        ErrorClass::Connect,
+        ErrorClass::Network,
+        ErrorClass::Server,
+        ErrorClass::RateLimited,
+    ]
+}
+
+impl Default for RetryPolicy {
+    /// Every request is made once.
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff_ms: DEFAULT_INITIAL_BACKOFF_MS,
+            max_backoff_ms: DEFAULT_MAX_BACKOFF_MS,
+            multiplier: DEFAULT_MULTIPLIER,
+            jitter: DEFAULT_JITTER,
+            retry_on: default_retry_on(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_json(policy: Option<String>) -> Result<Self, ExecutionError> {
+        match policy {
+            Some(policy) => serde_json::from_str::<Self>(&policy)
+                .map_err(|e| {
+                    ExecutionError::new(
+                        ErrorCode::InvalidInput,
+                        format!("Invalid retry policy: {}", e),
+                    )
+                })?
+                .validate(),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn validate(self) -> Result<Self, ExecutionError> {
+        if self.max_attempts == 0 {
+            return Err(ExecutionError::new(
+                ErrorCode::InvalidInput,
+                "Retry policy must allow at least one attempt",
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&self.jitter) || self.multiplier < 1.0 {
+            return Err(ExecutionError::new(
+                ErrorCode::InvalidInput,
+                "Retry policy jitter must be within [0, 1] and multiplier at least 1",
+            ));
+        }
+
+        Ok(self)
+    }
+
+    /// Delay before the next attempt, `None` if the request must not be retried.
+    ///
+    /// Non-idempotent requests are only retried when they surely weren't delivered.
+    pub fn backoff(&self, attempt: u32, class: ErrorClass, idempotent: bool) -> Option<Duration> {
+        if attempt >= self.max_attempts || !self.retry_on.contains(&class) {
+            return None;
+        }
+
+        if !idempotent && !class.is_undelivered() {
+            return None;
+        }
+
+        let delay = (self.initial_backoff_ms as f64 * self.multiplier.powi(attempt as i32 - 1))
+            .min(self.max_backoff_ms as f64);
+
+        // Uniformly spread within [delay * (1 - jitter), delay]
+        let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        let delay = delay * (1.0 - self.jitter * random);
+
+        Some(Duration::from_secs_f64(delay / 1000.0))
+    }
+}
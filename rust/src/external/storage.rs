@@ -1,26 +1,31 @@
 use std::{
-    os::raw::{c_char, c_longlong},
+    os::raw::{c_char, c_longlong, c_ulonglong},
     sync::Arc,
+    time::Duration,
 };
 
-use allo_isolate::Isolate;
-use anyhow::{bail, Result};
+use anyhow::Result;
 use async_trait::async_trait;
 use nekoton::external::Storage;
-use tokio::sync::oneshot::channel;
 
 use crate::{
-    channel_result_option_new, channel_result_unit_new, ffi_box, nt_channel_result_option_free_ptr,
-    nt_channel_result_unit_free_ptr, HandleError, MatchResult, ToPtrAddress, ToPtrFromAddress,
-    ISOLATE_MESSAGE_POST_ERROR,
+    channel_result_option_from_native_ptr_owned, channel_result_option_new,
+    channel_result_unit_from_native_ptr_owned, channel_result_unit_new,
+    errors::ExecutionError,
+    execute_sync,
+    external::request::{post_request, timeout_from_ms},
+    ffi_handle,
+    host::HostPort,
+    HandleError, PostSerialized, ToPtrAddress,
 };
 
 pub struct StorageImpl {
-    get_port: Isolate,
-    set_port: Isolate,
-    set_unchecked_port: Isolate,
-    remove_port: Isolate,
-    remove_unchecked_port: Isolate,
+    get_port: HostPort,
+    set_port: HostPort,
+    set_unchecked_port: HostPort,
+    remove_port: HostPort,
+    remove_unchecked_port: HostPort,
+    timeout: Option<Duration>,
 }
 
 impl StorageImpl {
@@ -30,13 +35,15 @@ impl StorageImpl {
         set_unchecked_port: i64,
         remove_port: i64,
         remove_unchecked_port: i64,
+        timeout: Option<Duration>,
     ) -> Self {
         Self {
-            get_port: Isolate::new(get_port),
-            set_port: Isolate::new(set_port),
-            set_unchecked_port: Isolate::new(set_unchecked_port),
-            remove_port: Isolate::new(remove_port),
-            remove_unchecked_port: Isolate::new(remove_unchecked_port),
+            get_port: HostPort::request(get_port),
+            set_port: HostPort::request(set_port),
+            set_unchecked_port: HostPort::request(set_unchecked_port),
+            remove_port: HostPort::request(remove_port),
+            remove_unchecked_port: HostPort::request(remove_unchecked_port),
+            timeout,
         }
     }
 }
@@ -44,81 +51,44 @@ impl StorageImpl {
 #[async_trait]
 impl Storage for StorageImpl {
     async fn get(&self, key: &str) -> Result<Option<String>> {
-        let (tx, rx) = channel::<Result<Option<String>>>();
-
-        let tx = channel_result_option_new(tx).to_ptr_address();
-        let key = key.to_owned();
-
-        let request = serde_json::to_string(&(tx.clone(), key))?;
-
-        match self.get_port.post(request) {
-            true => rx.await.unwrap(),
-            false => {
-                unsafe {
-                    nt_channel_result_option_free_ptr(tx.to_ptr_from_address());
-                }
-
-                bail!(ISOLATE_MESSAGE_POST_ERROR)
-            },
-        }
+        post_request(
+            &self.get_port,
+            self.timeout,
+            channel_result_option_new,
+            channel_result_option_from_native_ptr_owned,
+            |tx| serde_json::to_string(&(tx, key)),
+        )
+        .await
     }
 
     async fn set(&self, key: &str, value: &str) -> Result<()> {
-        let (tx, rx) = channel::<Result<()>>();
-
-        let tx = channel_result_unit_new(tx).to_ptr_address();
-        let key = key.to_owned();
-        let value = value.to_owned();
-
-        let request = serde_json::to_string(&(tx.clone(), key, value))?;
-
-        match self.set_port.post(request) {
-            true => rx.await.unwrap(),
-            false => {
-                unsafe {
-                    nt_channel_result_unit_free_ptr(tx.to_ptr_from_address());
-                }
-
-                bail!(ISOLATE_MESSAGE_POST_ERROR)
-            },
-        }
+        post_request(
+            &self.set_port,
+            self.timeout,
+            channel_result_unit_new,
+            channel_result_unit_from_native_ptr_owned,
+            |tx| serde_json::to_string(&(tx, key, value)),
+        )
+        .await
     }
 
     fn set_unchecked(&self, key: &str, value: &str) {
-        let key = key.to_owned();
-        let value = value.to_owned();
-
-        let request = serde_json::to_string(&(key, value)).unwrap();
-
-        self.set_unchecked_port.post(request);
+        self.set_unchecked_port.post_serialized(&(key, value));
     }
 
     async fn remove(&self, key: &str) -> Result<()> {
-        let (tx, rx) = channel::<Result<()>>();
-
-        let tx = channel_result_unit_new(tx).to_ptr_address();
-        let key = key.to_owned();
-
-        let request = serde_json::to_string(&(tx.clone(), key))?;
-
-        match self.remove_port.post(request) {
-            true => rx.await.unwrap(),
-            false => {
-                unsafe {
-                    nt_channel_result_unit_free_ptr(tx.to_ptr_from_address());
-                }
-
-                bail!(ISOLATE_MESSAGE_POST_ERROR)
-            },
-        }
+        post_request(
+            &self.remove_port,
+            self.timeout,
+            channel_result_unit_new,
+            channel_result_unit_from_native_ptr_owned,
+            |tx| serde_json::to_string(&(tx, key)),
+        )
+        .await
     }
 
     fn remove_unchecked(&self, key: &str) {
-        let key = key.to_owned();
-
-        let request = serde_json::to_string(&key).unwrap();
-
-        self.remove_unchecked_port.post(request);
+        self.remove_unchecked_port.post_serialized(&key);
     }
 }
 
@@ -129,34 +99,41 @@ pub unsafe extern "C" fn nt_storage_create(
     set_unchecked_port: c_longlong,
     remove_port: c_longlong,
     remove_unchecked_port: c_longlong,
+    timeout_ms: c_ulonglong,
 ) -> *mut c_char {
-    fn internal_fn(
-        get_port: i64,
-        set_port: i64,
-        set_unchecked_port: i64,
-        remove_port: i64,
-        remove_unchecked_port: i64,
-    ) -> Result<serde_json::Value, String> {
-        let storage = StorageImpl::new(
+    execute_sync(|| {
+        let timeout = timeout_from_ms(timeout_ms);
+
+        fn internal_fn(
+            get_port: i64,
+            set_port: i64,
+            set_unchecked_port: i64,
+            remove_port: i64,
+            remove_unchecked_port: i64,
+            timeout: Option<Duration>,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let storage = StorageImpl::new(
+                get_port,
+                set_port,
+                set_unchecked_port,
+                remove_port,
+                remove_unchecked_port,
+                timeout,
+            );
+
+            let ptr = storage_new(Arc::new(storage));
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
+        }
+
+        internal_fn(
             get_port,
             set_port,
             set_unchecked_port,
             remove_port,
             remove_unchecked_port,
-        );
-
-        let ptr = storage_new(Arc::new(storage));
-        serde_json::to_value(ptr.to_ptr_address()).handle_error()
-    }
-
-    internal_fn(
-        get_port,
-        set_port,
-        set_unchecked_port,
-        remove_port,
-        remove_unchecked_port,
-    )
-    .match_result()
+            timeout,
+        )
+    })
 }
 
-ffi_box!(storage, Arc<StorageImpl>);
+ffi_handle!(storage, Arc<StorageImpl>);
@@ -0,0 +1,281 @@
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    os::raw::c_void,
+    sync::{Mutex, MutexGuard},
+};
+
+use lazy_static::lazy_static;
+
+use crate::errors::{ErrorCode, ExecutionError};
+
+/// Low half of a handle is a slot index, high half is a slot generation.
+/// Generation starts from 1, so a valid handle is never null.
+const INDEX_BITS: u32 = usize::BITS / 2;
+const INDEX_MASK: usize = (1 << INDEX_BITS) - 1;
+const MAX_GENERATION: usize = usize::MAX >> INDEX_BITS;
+
+lazy_static! {
+    static ref HANDLES: Mutex<HandleRegistry> = Mutex::new(HandleRegistry::default());
+}
+
+pub fn handles() -> MutexGuard<'static, HandleRegistry> {
+    HANDLES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Table of objects passed to Dart.
+///
+/// Dart only sees opaque generation-checked handles, so stale, already freed or
+/// wrong-type handles are reported as errors instead of being dereferenced.
+#[derive(Default)]
+pub struct HandleRegistry {
+    slots: Vec<Slot>,
+    free: Vec<usize>,
+}
+
+struct Slot {
+    generation: usize,
+    entry: Option<Entry>,
+}
+
+struct Entry {
+    kind: &'static str,
+    value: Box<dyn Any + Send + Sync>,
+}
+
+impl HandleRegistry {
+    #[allow(clippy::disallowed_methods)]
+    pub fn insert<T>(&mut self, kind: &'static str, value: T) -> usize
+    where
+        T: Any + Send + Sync,
+    {
+        let entry = Entry {
+            kind,
+            value: Box::new(value),
+        };
+
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.slots.push(Slot {
+                    generation: 1,
+                    entry: None,
+                });
+                self.slots.len() - 1
+            },
+        };
+
+        let slot = &mut self.slots[index];
+        slot.entry = Some(entry);
+
+        (slot.generation << INDEX_BITS) | index
+    }
+
+    pub fn get<T>(&self, kind: &'static str, handle: usize) -> Result<&T, ExecutionError>
+    where
+        T: Any,
+    {
+        let entry = self.entry(kind, handle)?;
+
+        entry
+            .value
+            .downcast_ref::<T>()
+            .ok_or_else(|| wrong_kind(kind, entry.kind, handle))
+    }
+
+    pub fn remove<T>(&mut self, kind: &'static str, handle: usize) -> Result<T, ExecutionError>
+    where
+        T: Any,
+    {
+        let entry = self.entry(kind, handle)?;
+        if !entry.value.is::<T>() {
+            return Err(wrong_kind(kind, entry.kind, handle));
+        }
+
+        let index = handle & INDEX_MASK;
+        let slot = &mut self.slots[index];
+
+        let entry = slot.entry.take().expect("Checked above");
+
+        slot.generation = match slot.generation {
+            MAX_GENERATION => 1,
+            generation => generation + 1,
+        };
+        self.free.push(index);
+
+        Ok(*entry.value.downcast::<T>().expect("Checked above"))
+    }
+
+    /// Number of live handles of each kind.
+    pub fn live(&self) -> BTreeMap<&'static str, usize> {
+        let mut live = BTreeMap::new();
+
+        for entry in self.slots.iter().filter_map(|slot| slot.entry.as_ref()) {
+            *live.entry(entry.kind).or_default() += 1;
+        }
+
+        live
+    }
+
+    /// Invalidates all handles. Returned objects should be dropped outside of the registry lock.
+    pub fn clear(&mut self) -> Vec<Box<dyn Any + Send + Sync>> {
+        let mut values = Vec::new();
+
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if let Some(entry) = slot.entry.take() {
+                slot.generation = match slot.generation {
+                    MAX_GENERATION => 1,
+                    generation => generation + 1,
+                };
+                self.free.push(index);
+                values.push(entry.value);
+            }
+        }
+
+        values
+    }
+
+    /// Kind of a live handle, lets a handle of one of several kinds be resolved
+    /// without a separate type tag.
+    pub fn kind(&self, handle: usize) -> Result<&'static str, ExecutionError> {
+        self.live_entry("unknown", handle).map(|entry| entry.kind)
+    }
+
+    fn entry(&self, kind: &'static str, handle: usize) -> Result<&Entry, ExecutionError> {
+        let entry = self.live_entry(kind, handle)?;
+
+        match entry.kind == kind {
+            true => Ok(entry),
+            false => Err(wrong_kind(kind, entry.kind, handle)),
+        }
+    }
+
+    fn live_entry(&self, kind: &'static str, handle: usize) -> Result<&Entry, ExecutionError> {
+        let index = handle & INDEX_MASK;
+        let generation = handle >> INDEX_BITS;
+
+        let slot = self
+            .slots
+            .get(index)
+            .filter(|slot| generation != 0 && slot.generation == generation)
+            .ok_or_else(|| invalid_handle("Stale or unknown handle", kind, handle))?;
+
+        slot.entry
+            .as_ref()
+            .ok_or_else(|| invalid_handle("Handle was already freed", kind, handle))
+    }
+}
+
+pub fn handle_to_ptr(handle: usize) -> *mut c_void {
+    handle as *mut c_void
+}
+
+pub fn ptr_to_handle(ptr: *mut c_void) -> usize {
+    ptr as usize
+}
+
+fn invalid_handle(message: &str, kind: &str, handle: usize) -> ExecutionError {
+    ExecutionError::new(ErrorCode::InvalidHandle, message)
+        .with_context("kind", kind)
+        .with_context("handle", handle)
+}
+
+fn wrong_kind(expected: &str, actual: &str, handle: usize) -> ExecutionError {
+    invalid_handle("Handle has a different type", expected, handle).with_context("actual", actual)
+}
+
+/// Registers a handle kind for the given type.
+///
+/// Generates `$name_new` to register an object, `nt_$name_free_ptr` to release it from Dart,
+/// `$name_from_native_ptr` and `$name_from_native_ptr_opt` to get a clone of the object and
+/// `$name_from_native_ptr_owned` to take it out of the registry.
+///
+/// Types that can't be cloned (e.g. channel senders) use the `@owned` form without getters.
+#[macro_export]
+macro_rules! ffi_handle {
+    (@owned $name:ident, $type:ty) => {
+        ::paste::paste! {
+            #[allow(dead_code)]
+            pub fn [< $name _new >](data: $type) -> *mut std::ffi::c_void {
+                let handle = $crate::handles::handles().insert::<$type>(stringify!($name), data);
+                $crate::handles::handle_to_ptr(handle)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn [< nt_ $name _free_ptr >](ptr: *mut std::ffi::c_void) {
+                let handle = $crate::handles::ptr_to_handle(ptr);
+
+                let result = $crate::handles::handles().remove::<$type>(stringify!($name), handle);
+
+                // Dropped outside of the registry lock
+                if let Err(err) = result {
+                    log::error!(concat!("nt_", stringify!($name), "_free_ptr: {}"), err);
+                }
+            }
+
+            #[allow(dead_code)]
+            pub unsafe fn [< $name _from_native_ptr_owned >](
+                ptr: *mut std::ffi::c_void,
+            ) -> Result<$type, $crate::errors::ExecutionError> {
+                let handle = $crate::handles::ptr_to_handle(ptr);
+
+                $crate::handles::handles().remove::<$type>(stringify!($name), handle)
+            }
+        }
+    };
+    ($name:ident, $type:ty) => {
+        $crate::ffi_handle!(@owned $name, $type);
+
+        ::paste::paste! {
+            #[allow(dead_code)]
+            pub unsafe fn [< $name _from_native_ptr >](
+                ptr: *mut std::ffi::c_void,
+            ) -> Result<$type, $crate::errors::ExecutionError> {
+                let handle = $crate::handles::ptr_to_handle(ptr);
+
+                $crate::handles::handles()
+                    .get::<$type>(stringify!($name), handle)
+                    .map(Clone::clone)
+            }
+
+            #[allow(dead_code)]
+            pub unsafe fn [< $name _from_native_ptr_opt >](
+                ptr: *mut std::ffi::c_void,
+            ) -> Result<Option<$type>, $crate::errors::ExecutionError> {
+                match ptr.is_null() {
+                    true => Ok(None),
+                    false => [< $name _from_native_ptr >](ptr).map(Some),
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stale_and_wrong_kind_handles() {
+        let mut registry = HandleRegistry::default();
+
+        let first = registry.insert("string", "test".to_owned());
+        assert_ne!(first, 0);
+        assert_eq!(registry.get::<String>("string", first).unwrap(), "test");
+        assert_eq!(registry.kind(first).unwrap(), "string");
+        assert!(registry.get::<String>("other", first).is_err());
+        assert!(registry.get::<u32>("string", first).is_err());
+
+        assert_eq!(registry.remove::<String>("string", first).unwrap(), "test");
+        assert!(registry.get::<String>("string", first).is_err());
+        assert!(registry.remove::<String>("string", first).is_err());
+        assert!(registry.kind(first).is_err());
+
+        let second = registry.insert("string", "reused".to_owned());
+        assert_eq!(second & INDEX_MASK, first & INDEX_MASK);
+        assert_ne!(second, first);
+        assert!(registry.get::<String>("string", first).is_err());
+
+        assert_eq!(registry.live().get("string"), Some(&1));
+    }
+}
@@ -1,4 +1,4 @@
-mod models;
+pub mod models;
 
 use std::{
     borrow::Cow,
@@ -25,6 +25,8 @@ use ton_types::SliceData;
 use crate::{
     clock,
     crypto::unsigned_message_new,
+    errors::{ErrorCode, ExecutionError},
+    execute_sync,
     helpers::{
         abi::models::{
             AbiParam, DecodedEvent, DecodedInput, DecodedOutput, DecodedTransaction,
@@ -32,21 +34,23 @@ use crate::{
         },
         parse_account_stuff,
     },
-    parse_address, parse_public_key, HandleError, MatchResult, ToOptionalStringFromPtr,
-    ToPtrAddress, ToStringFromPtr, CLOCK,
+    parse_address, parse_public_key, HandleError, ToOptionalStringFromPtr, ToPtrAddress,
+    ToStringFromPtr, CLOCK,
 };
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_check_public_key(public_key: *mut c_char) -> *mut c_char {
-    let public_key = public_key.to_string_from_ptr();
+    execute_sync(|| {
+        let public_key = public_key.to_string_from_ptr();
 
-    fn internal_fn(public_key: String) -> Result<serde_json::Value, String> {
-        parse_public_key(&public_key).handle_error()?;
+        fn internal_fn(public_key: String) -> Result<serde_json::Value, ExecutionError> {
+            parse_public_key(&public_key)?;
 
-        Ok(serde_json::Value::Null)
-    }
+            Ok(serde_json::Value::Null)
+        }
 
-    internal_fn(public_key).match_result()
+        internal_fn(public_key)
+    })
 }
 
 #[no_mangle]
@@ -57,50 +61,55 @@ pub unsafe extern "C" fn nt_run_local(
     input: *mut c_char,
     responsible: c_uint,
 ) -> *mut c_char {
-    let account_stuff_boc = account_stuff_boc.to_string_from_ptr();
-    let contract_abi = contract_abi.to_string_from_ptr();
-    let method = method.to_string_from_ptr();
-    let input = input.to_string_from_ptr();
-    let responsible = responsible != 0;
-
-    fn internal_fn(
-        account_stuff_boc: String,
-        contract_abi: String,
-        method: String,
-        input: String,
-        responsible: bool,
-    ) -> Result<serde_json::Value, String> {
-        let account_stuff = parse_account_stuff(&account_stuff_boc)?;
-        let contract_abi = parse_contract_abi(&contract_abi)?;
-        let method = contract_abi.function(&method).handle_error()?;
-
-        let input = serde_json::from_str::<serde_json::Value>(&input).handle_error()?;
-        let input = nekoton_abi::parse_abi_tokens(&method.inputs, input).handle_error()?;
-
-        let output = if responsible {
-            method
-                .run_local_responsible(clock!().as_ref(), account_stuff, &input)
-                .handle_error()?
-        } else {
-            method
-                .run_local(clock!().as_ref(), account_stuff, &input)
-                .handle_error()?
-        };
+    execute_sync(|| {
+        let account_stuff_boc = account_stuff_boc.to_string_from_ptr();
+        let contract_abi = contract_abi.to_string_from_ptr();
+        let method = method.to_string_from_ptr();
+        let input = input.to_string_from_ptr();
+        let responsible = responsible != 0;
+
+        fn internal_fn(
+            account_stuff_boc: String,
+            contract_abi: String,
+            method: String,
+            input: String,
+            responsible: bool,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let account_stuff = parse_account_stuff(&account_stuff_boc)?;
+            let contract_abi = parse_contract_abi(&contract_abi)?;
+            let method = parse_function(&contract_abi, &method)?;
+
+            let input = serde_json::from_str::<serde_json::Value>(&input).handle_error()?;
+            let input = nekoton_abi::parse_abi_tokens(&method.inputs, input)
+                .handle_error_as(ErrorCode::EncodingFailed)?;
+
+            let output = if responsible {
+                method
+                    .run_local_responsible(clock!().as_ref(), account_stuff, &input)
+                    .handle_error_as(ErrorCode::ExecutionFailed)?
+            } else {
+                method
+                    .run_local(clock!().as_ref(), account_stuff, &input)
+                    .handle_error_as(ErrorCode::ExecutionFailed)?
+            };
+
+            let tokens = output
+                .tokens
+                .map(|e| {
+                    nekoton_abi::make_abi_tokens(&e).handle_error_as(ErrorCode::DecodingFailed)
+                })
+                .transpose()?;
 
-        let tokens = output
-            .tokens
-            .map(|e| nekoton_abi::make_abi_tokens(&e).handle_error())
-            .transpose()?;
+            let execution_output = ExecutionOutput {
+                output: tokens,
+                code: output.result_code,
+            };
 
-        let execution_output = ExecutionOutput {
-            output: tokens,
-            code: output.result_code,
-        };
-
-        serde_json::to_value(execution_output).handle_error()
-    }
+            serde_json::to_value(execution_output).handle_error()
+        }
 
-    internal_fn(account_stuff_boc, contract_abi, method, input, responsible).match_result()
+        internal_fn(account_stuff_boc, contract_abi, method, input, responsible)
+    })
 }
 
 #[no_mangle]
@@ -111,63 +120,63 @@ pub unsafe extern "C" fn nt_get_expected_address(
     public_key: *mut c_char,
     init_data: *mut c_char,
 ) -> *mut c_char {
-    let tvc = tvc.to_string_from_ptr();
-    let contract_abi = contract_abi.to_string_from_ptr();
-    let public_key = public_key.to_optional_string_from_ptr();
-    let init_data = init_data.to_string_from_ptr();
-
-    fn internal_fn(
-        tvc: String,
-        contract_abi: String,
-        workchain_id: i8,
-        public_key: Option<String>,
-        init_data: String,
-    ) -> Result<serde_json::Value, String> {
-        let mut state_init = ton_block::StateInit::construct_from_base64(&tvc).handle_error()?;
-        let contract_abi = parse_contract_abi(&contract_abi)?;
-        let public_key = public_key
-            .as_deref()
-            .map(parse_public_key)
-            .transpose()
-            .handle_error()?;
-
-        let params = contract_abi
-            .data
-            .values()
-            .cloned()
-            .map(|v| v.value)
-            .collect::<Vec<_>>();
-
-        let init_data = serde_json::from_str::<serde_json::Value>(&init_data).handle_error()?;
-        let init_data = nekoton_abi::parse_abi_tokens(&params, init_data).handle_error()?;
-
-        state_init.data = if let Some(data) = state_init.data.take() {
-            Some(
-                insert_state_init_data(
-                    &contract_abi,
-                    SliceData::load_cell(data).unwrap(),
-                    &public_key,
-                    init_data,
+    execute_sync(|| {
+        let tvc = tvc.to_string_from_ptr();
+        let contract_abi = contract_abi.to_string_from_ptr();
+        let public_key = public_key.to_optional_string_from_ptr();
+        let init_data = init_data.to_string_from_ptr();
+
+        fn internal_fn(
+            tvc: String,
+            contract_abi: String,
+            workchain_id: i8,
+            public_key: Option<String>,
+            init_data: String,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let mut state_init = ton_block::StateInit::construct_from_base64(&tvc)
+                .handle_error_as(ErrorCode::InvalidBoc)?;
+            let contract_abi = parse_contract_abi(&contract_abi)?;
+            let public_key = public_key.as_deref().map(parse_public_key).transpose()?;
+
+            let params = contract_abi
+                .data
+                .values()
+                .cloned()
+                .map(|v| v.value)
+                .collect::<Vec<_>>();
+
+            let init_data = serde_json::from_str::<serde_json::Value>(&init_data).handle_error()?;
+            let init_data = nekoton_abi::parse_abi_tokens(&params, init_data)
+                .handle_error_as(ErrorCode::EncodingFailed)?;
+
+            state_init.data = if let Some(data) = state_init.data.take() {
+                Some(
+                    insert_state_init_data(
+                        &contract_abi,
+                        SliceData::load_cell(data).handle_error_as(ErrorCode::InvalidBoc)?,
+                        &public_key,
+                        init_data,
+                    )
+                    .handle_error_as(ErrorCode::EncodingFailed)?
+                    .into_cell(),
                 )
-                .handle_error()?
-                .into_cell(),
-            )
-        } else {
-            None
-        };
+            } else {
+                None
+            };
 
-        let cell = state_init.serialize().handle_error()?;
-        let repr_hash = cell.repr_hash().to_hex_string();
+            let cell = state_init.serialize().handle_error()?;
+            let repr_hash = cell.repr_hash().to_hex_string();
 
-        let address = format!("{workchain_id}:{repr_hash}");
-        let state_init = ton_types::serialize_toc(&cell)
-            .map(base64::encode)
-            .handle_error()?;
+            let address = format!("{workchain_id}:{repr_hash}");
+            let state_init = ton_types::serialize_toc(&cell)
+                .map(base64::encode)
+                .handle_error()?;
 
-        serde_json::to_value((address, state_init)).handle_error()
-    }
+            serde_json::to_value((address, state_init)).handle_error()
+        }
 
-    internal_fn(tvc, contract_abi, workchain_id, public_key, init_data).match_result()
+        internal_fn(tvc, contract_abi, workchain_id, public_key, init_data)
+    })
 }
 
 #[no_mangle]
@@ -176,35 +185,38 @@ pub unsafe extern "C" fn nt_encode_internal_input(
     method: *mut c_char,
     input: *mut c_char,
 ) -> *mut c_char {
-    let contract_abi = contract_abi.to_string_from_ptr();
-    let method = method.to_string_from_ptr();
-    let input = input.to_string_from_ptr();
+    execute_sync(|| {
+        let contract_abi = contract_abi.to_string_from_ptr();
+        let method = method.to_string_from_ptr();
+        let input = input.to_string_from_ptr();
 
-    fn internal_fn(
-        contract_abi: String,
-        method: String,
-        input: String,
-    ) -> Result<serde_json::Value, String> {
-        let contract_abi = parse_contract_abi(&contract_abi)?;
+        fn internal_fn(
+            contract_abi: String,
+            method: String,
+            input: String,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let contract_abi = parse_contract_abi(&contract_abi)?;
 
-        let method = contract_abi.function(&method).handle_error()?;
+            let method = parse_function(&contract_abi, &method)?;
 
-        let input = serde_json::from_str::<serde_json::Value>(&input).handle_error()?;
-        let input = nekoton_abi::parse_abi_tokens(&method.inputs, input).handle_error()?;
+            let input = serde_json::from_str::<serde_json::Value>(&input).handle_error()?;
+            let input = nekoton_abi::parse_abi_tokens(&method.inputs, input)
+                .handle_error_as(ErrorCode::EncodingFailed)?;
 
-        let body = method
-            .encode_internal_input(&input)
-            .and_then(|e| e.into_cell())
-            .handle_error()?;
+            let body = method
+                .encode_internal_input(&input)
+                .and_then(|e| e.into_cell())
+                .handle_error_as(ErrorCode::EncodingFailed)?;
 
-        let body = ton_types::serialize_toc(&body).handle_error()?;
+            let body = ton_types::serialize_toc(&body).handle_error()?;
 
-        let body = base64::encode(body);
+            let body = base64::encode(body);
 
-        serde_json::to_value(body).handle_error()
-    }
+            serde_json::to_value(body).handle_error()
+        }
 
-    internal_fn(contract_abi, method, input).match_result()
+        internal_fn(contract_abi, method, input)
+    })
 }
 
 #[no_mangle]
@@ -216,74 +228,79 @@ pub unsafe extern "C" fn nt_create_external_message_without_signature(
     input: *mut c_char,
     timeout: c_uint,
 ) -> *mut c_char {
-    let dst = dst.to_string_from_ptr();
-    let contract_abi = contract_abi.to_string_from_ptr();
-    let method = method.to_string_from_ptr();
-    let state_init = state_init.to_optional_string_from_ptr();
-    let input = input.to_string_from_ptr();
-
-    fn internal_fn(
-        dst: String,
-        contract_abi: String,
-        method: String,
-        state_init: Option<String>,
-        input: String,
-        timeout: u32,
-    ) -> Result<serde_json::Value, String> {
-        let dst = parse_address(&dst)?;
-        let contract_abi = parse_contract_abi(&contract_abi)?;
-        let method = contract_abi.function(&method).handle_error()?;
-
-        let state_init = state_init
-            .as_deref()
-            .map(ton_block::StateInit::construct_from_base64)
-            .transpose()
-            .handle_error()?;
-
-        let input = serde_json::from_str::<serde_json::Value>(&input).handle_error()?;
-        let input = nekoton_abi::parse_abi_tokens(&method.inputs, input).handle_error()?;
-
-        let time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map_err(|e| format!("{e}"))?
-            .as_millis() as u64;
-
-        let expire_at = ExpireAt::new_from_millis(Expiration::Timeout(timeout), time);
-
-        let mut header = HashMap::with_capacity(3);
-
-        header.insert("time".to_string(), ton_abi::TokenValue::Time(time));
-        header.insert(
-            "expire".to_string(),
-            ton_abi::TokenValue::Expire(expire_at.timestamp),
-        );
-        header.insert("pubkey".to_string(), ton_abi::TokenValue::PublicKey(None));
-
-        let body = method
-            .encode_input(&header, &input, false, None, Some(dst.clone()))
-            .handle_error()?;
-
-        let mut message =
-            ton_block::Message::with_ext_in_header(ton_block::ExternalInboundMessageHeader {
-                dst,
-                ..Default::default()
-            });
-
-        if let Some(state_init) = state_init {
-            message.set_state_init(state_init);
-        }
+    execute_sync(|| {
+        let dst = dst.to_string_from_ptr();
+        let contract_abi = contract_abi.to_string_from_ptr();
+        let method = method.to_string_from_ptr();
+        let state_init = state_init.to_optional_string_from_ptr();
+        let input = input.to_string_from_ptr();
+
+        fn internal_fn(
+            dst: String,
+            contract_abi: String,
+            method: String,
+            state_init: Option<String>,
+            input: String,
+            timeout: u32,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let dst = parse_address(&dst)?;
+            let contract_abi = parse_contract_abi(&contract_abi)?;
+            let method = parse_function(&contract_abi, &method)?;
+
+            let state_init = state_init
+                .as_deref()
+                .map(ton_block::StateInit::construct_from_base64)
+                .transpose()
+                .handle_error_as(ErrorCode::InvalidBoc)?;
+
+            let input = serde_json::from_str::<serde_json::Value>(&input).handle_error()?;
+            let input = nekoton_abi::parse_abi_tokens(&method.inputs, input)
+                .handle_error_as(ErrorCode::EncodingFailed)?;
+
+            let time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .handle_error()?
+                .as_millis() as u64;
 
-        message.set_body(SliceData::load_builder(body).unwrap());
+            let expire_at = ExpireAt::new_from_millis(Expiration::Timeout(timeout), time);
 
-        let signed_message = SignedMessage {
-            message,
-            expire_at: expire_at.timestamp,
-        };
+            let mut header = HashMap::with_capacity(3);
 
-        serde_json::to_value(signed_message).handle_error()
-    }
+            header.insert("time".to_string(), ton_abi::TokenValue::Time(time));
+            header.insert(
+                "expire".to_string(),
+                ton_abi::TokenValue::Expire(expire_at.timestamp),
+            );
+            header.insert("pubkey".to_string(), ton_abi::TokenValue::PublicKey(None));
+
+            let body = method
+                .encode_input(&header, &input, false, None, Some(dst.clone()))
+                .handle_error_as(ErrorCode::EncodingFailed)?;
+
+            let mut message =
+                ton_block::Message::with_ext_in_header(ton_block::ExternalInboundMessageHeader {
+                    dst,
+                    ..Default::default()
+                });
+
+            if let Some(state_init) = state_init {
+                message.set_state_init(state_init);
+            }
+
+            message.set_body(
+                SliceData::load_builder(body).handle_error_as(ErrorCode::EncodingFailed)?,
+            );
+
+            let signed_message = SignedMessage {
+                message,
+                expire_at: expire_at.timestamp,
+            };
+
+            serde_json::to_value(signed_message).handle_error()
+        }
 
-    internal_fn(dst, contract_abi, method, state_init, input, timeout).match_result()
+        internal_fn(dst, contract_abi, method, state_init, input, timeout)
+    })
 }
 
 #[no_mangle]
@@ -296,86 +313,90 @@ pub unsafe extern "C" fn nt_create_external_message(
     public_key: *mut c_char,
     timeout: c_uint,
 ) -> *mut c_char {
-    let dst = dst.to_string_from_ptr();
-    let contract_abi = contract_abi.to_string_from_ptr();
-    let method = method.to_string_from_ptr();
-    let state_init = state_init.to_optional_string_from_ptr();
-    let input = input.to_string_from_ptr();
-    let public_key = public_key.to_string_from_ptr();
-
-    fn internal_fn(
-        dst: String,
-        contract_abi: String,
-        method: String,
-        state_init: Option<String>,
-        input: String,
-        public_key: String,
-        timeout: u32,
-    ) -> Result<serde_json::Value, String> {
-        let dst = parse_address(&dst)?;
-        let contract_abi = parse_contract_abi(&contract_abi)?;
-        let method = contract_abi.function(&method).handle_error()?;
-
-        let state_init = state_init
-            .as_deref()
-            .map(ton_block::StateInit::construct_from_base64)
-            .transpose()
-            .handle_error()?;
-
-        let input = serde_json::from_str::<serde_json::Value>(&input).handle_error()?;
-        let input = nekoton_abi::parse_abi_tokens(&method.inputs, input).handle_error()?;
-
-        let public_key = parse_public_key(&public_key).handle_error()?;
-
-        let mut message =
-            ton_block::Message::with_ext_in_header(ton_block::ExternalInboundMessageHeader {
-                dst,
-                ..Default::default()
-            });
-
-        if let Some(state_init) = state_init {
-            message.set_state_init(state_init);
+    execute_sync(|| {
+        let dst = dst.to_string_from_ptr();
+        let contract_abi = contract_abi.to_string_from_ptr();
+        let method = method.to_string_from_ptr();
+        let state_init = state_init.to_optional_string_from_ptr();
+        let input = input.to_string_from_ptr();
+        let public_key = public_key.to_string_from_ptr();
+
+        fn internal_fn(
+            dst: String,
+            contract_abi: String,
+            method: String,
+            state_init: Option<String>,
+            input: String,
+            public_key: String,
+            timeout: u32,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let dst = parse_address(&dst)?;
+            let contract_abi = parse_contract_abi(&contract_abi)?;
+            let method = parse_function(&contract_abi, &method)?;
+
+            let state_init = state_init
+                .as_deref()
+                .map(ton_block::StateInit::construct_from_base64)
+                .transpose()
+                .handle_error_as(ErrorCode::InvalidBoc)?;
+
+            let input = serde_json::from_str::<serde_json::Value>(&input).handle_error()?;
+            let input = nekoton_abi::parse_abi_tokens(&method.inputs, input)
+                .handle_error_as(ErrorCode::EncodingFailed)?;
+
+            let public_key = parse_public_key(&public_key)?;
+
+            let mut message =
+                ton_block::Message::with_ext_in_header(ton_block::ExternalInboundMessageHeader {
+                    dst,
+                    ..Default::default()
+                });
+
+            if let Some(state_init) = state_init {
+                message.set_state_init(state_init);
+            }
+
+            let unsigned_message = make_labs_unsigned_message(
+                clock!().as_ref(),
+                message,
+                Expiration::Timeout(timeout),
+                &public_key,
+                Cow::Owned(method.to_owned()),
+                input,
+            )
+            .handle_error_as(ErrorCode::EncodingFailed)?;
+
+            let ptr = unsigned_message_new(Arc::new(RwLock::new(unsigned_message)));
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
         }
 
-        let unsigned_message = make_labs_unsigned_message(
-            clock!().as_ref(),
-            message,
-            Expiration::Timeout(timeout),
-            &public_key,
-            Cow::Owned(method.to_owned()),
+        internal_fn(
+            dst,
+            contract_abi,
+            method,
+            state_init,
             input,
+            public_key,
+            timeout,
         )
-        .handle_error()?;
-
-        let ptr = unsigned_message_new(Arc::new(RwLock::new(unsigned_message)));
-        serde_json::to_value(ptr.to_ptr_address()).handle_error()
-    }
-
-    internal_fn(
-        dst,
-        contract_abi,
-        method,
-        state_init,
-        input,
-        public_key,
-        timeout,
-    )
-    .match_result()
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_parse_known_payload(payload: *mut c_char) -> *mut c_char {
-    let payload = payload.to_string_from_ptr();
+    execute_sync(|| {
+        let payload = payload.to_string_from_ptr();
 
-    fn internal_fn(payload: String) -> Result<serde_json::Value, String> {
-        let payload = parse_slice(&payload)?;
+        fn internal_fn(payload: String) -> Result<serde_json::Value, ExecutionError> {
+            let payload = parse_slice(&payload)?;
 
-        let known_payload = parse_payload(payload);
+            let known_payload = parse_payload(payload);
 
-        serde_json::to_value(known_payload).handle_error()
-    }
+            serde_json::to_value(known_payload).handle_error()
+        }
 
-    internal_fn(payload).match_result()
+        internal_fn(payload)
+    })
 }
 
 #[no_mangle]
@@ -385,40 +406,43 @@ pub unsafe extern "C" fn nt_decode_input(
     method: *mut c_char,
     internal: c_uint,
 ) -> *mut c_char {
-    let message_body = message_body.to_string_from_ptr();
-    let contract_abi = contract_abi.to_string_from_ptr();
-    let method = method.to_optional_string_from_ptr();
-    let internal = internal != 0;
-
-    fn internal_fn(
-        message_body: String,
-        contract_abi: String,
-        method: Option<String>,
-        internal: bool,
-    ) -> Result<serde_json::Value, String> {
-        let message_body = parse_slice(&message_body)?;
-        let contract_abi = parse_contract_abi(&contract_abi)?;
-        let method = parse_method_name(method)?;
-
-        let input = nekoton_abi::decode_input(&contract_abi, message_body, &method, internal)
-            .handle_error()?;
-
-        match input {
-            Some((method, input)) => {
-                let input = nekoton_abi::make_abi_tokens(&input).handle_error()?;
-
-                let input = DecodedInput {
-                    method: method.name.to_owned(),
-                    input,
-                };
-
-                serde_json::to_value(input).handle_error()
-            },
-            None => Ok(serde_json::Value::Null),
+    execute_sync(|| {
+        let message_body = message_body.to_string_from_ptr();
+        let contract_abi = contract_abi.to_string_from_ptr();
+        let method = method.to_optional_string_from_ptr();
+        let internal = internal != 0;
+
+        fn internal_fn(
+            message_body: String,
+            contract_abi: String,
+            method: Option<String>,
+            internal: bool,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let message_body = parse_slice(&message_body)?;
+            let contract_abi = parse_contract_abi(&contract_abi)?;
+            let method = parse_method_name(method)?;
+
+            let input = nekoton_abi::decode_input(&contract_abi, message_body, &method, internal)
+                .handle_error_as(ErrorCode::DecodingFailed)?;
+
+            match input {
+                Some((method, input)) => {
+                    let input = nekoton_abi::make_abi_tokens(&input)
+                        .handle_error_as(ErrorCode::DecodingFailed)?;
+
+                    let input = DecodedInput {
+                        method: method.name.to_owned(),
+                        input,
+                    };
+
+                    serde_json::to_value(input).handle_error()
+                },
+                None => Ok(serde_json::Value::Null),
+            }
         }
-    }
 
-    internal_fn(message_body, contract_abi, method, internal).match_result()
+        internal_fn(message_body, contract_abi, method, internal)
+    })
 }
 
 #[no_mangle]
@@ -427,38 +451,41 @@ pub unsafe extern "C" fn nt_decode_event(
     contract_abi: *mut c_char,
     event: *mut c_char,
 ) -> *mut c_char {
-    let message_body = message_body.to_string_from_ptr();
-    let contract_abi = contract_abi.to_string_from_ptr();
-    let event = event.to_optional_string_from_ptr();
-
-    fn internal_fn(
-        message_body: String,
-        contract_abi: String,
-        event: Option<String>,
-    ) -> Result<serde_json::Value, String> {
-        let message_body = parse_slice(&message_body)?;
-        let contract_abi = parse_contract_abi(&contract_abi)?;
-        let event = parse_method_name(event)?;
-
-        let event =
-            nekoton_abi::decode_event(&contract_abi, message_body, &event).handle_error()?;
-
-        match event {
-            Some((event, data)) => {
-                let data = nekoton_abi::make_abi_tokens(&data).handle_error()?;
-
-                let event = DecodedEvent {
-                    event: event.name.to_owned(),
-                    data,
-                };
-
-                serde_json::to_value(event).handle_error()
-            },
-            None => Ok(serde_json::Value::Null),
+    execute_sync(|| {
+        let message_body = message_body.to_string_from_ptr();
+        let contract_abi = contract_abi.to_string_from_ptr();
+        let event = event.to_optional_string_from_ptr();
+
+        fn internal_fn(
+            message_body: String,
+            contract_abi: String,
+            event: Option<String>,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let message_body = parse_slice(&message_body)?;
+            let contract_abi = parse_contract_abi(&contract_abi)?;
+            let event = parse_method_name(event)?;
+
+            let event = nekoton_abi::decode_event(&contract_abi, message_body, &event)
+                .handle_error_as(ErrorCode::DecodingFailed)?;
+
+            match event {
+                Some((event, data)) => {
+                    let data = nekoton_abi::make_abi_tokens(&data)
+                        .handle_error_as(ErrorCode::DecodingFailed)?;
+
+                    let event = DecodedEvent {
+                        event: event.name.to_owned(),
+                        data,
+                    };
+
+                    serde_json::to_value(event).handle_error()
+                },
+                None => Ok(serde_json::Value::Null),
+            }
         }
-    }
 
-    internal_fn(message_body, contract_abi, event).match_result()
+        internal_fn(message_body, contract_abi, event)
+    })
 }
 
 #[no_mangle]
@@ -467,38 +494,41 @@ pub unsafe extern "C" fn nt_decode_output(
     contract_abi: *mut c_char,
     method: *mut c_char,
 ) -> *mut c_char {
-    let message_body = message_body.to_string_from_ptr();
-    let contract_abi = contract_abi.to_string_from_ptr();
-    let method = method.to_optional_string_from_ptr();
-
-    fn internal_fn(
-        message_body: String,
-        contract_abi: String,
-        method: Option<String>,
-    ) -> Result<serde_json::Value, String> {
-        let message_body = parse_slice(&message_body)?;
-        let contract_abi = parse_contract_abi(&contract_abi)?;
-        let method = parse_method_name(method)?;
-
-        let output =
-            nekoton_abi::decode_output(&contract_abi, message_body, &method).handle_error()?;
-
-        match output {
-            Some((method, output)) => {
-                let output = nekoton_abi::make_abi_tokens(&output).handle_error()?;
-
-                let output = DecodedOutput {
-                    method: method.name.to_owned(),
-                    output,
-                };
-
-                serde_json::to_value(output).handle_error()
-            },
-            None => Ok(serde_json::Value::Null),
+    execute_sync(|| {
+        let message_body = message_body.to_string_from_ptr();
+        let contract_abi = contract_abi.to_string_from_ptr();
+        let method = method.to_optional_string_from_ptr();
+
+        fn internal_fn(
+            message_body: String,
+            contract_abi: String,
+            method: Option<String>,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let message_body = parse_slice(&message_body)?;
+            let contract_abi = parse_contract_abi(&contract_abi)?;
+            let method = parse_method_name(method)?;
+
+            let output = nekoton_abi::decode_output(&contract_abi, message_body, &method)
+                .handle_error_as(ErrorCode::DecodingFailed)?;
+
+            match output {
+                Some((method, output)) => {
+                    let output = nekoton_abi::make_abi_tokens(&output)
+                        .handle_error_as(ErrorCode::DecodingFailed)?;
+
+                    let output = DecodedOutput {
+                        method: method.name.to_owned(),
+                        output,
+                    };
+
+                    serde_json::to_value(output).handle_error()
+                },
+                None => Ok(serde_json::Value::Null),
+            }
         }
-    }
 
-    internal_fn(message_body, contract_abi, method).match_result()
+        internal_fn(message_body, contract_abi, method)
+    })
 }
 
 #[no_mangle]
@@ -507,64 +537,78 @@ pub unsafe extern "C" fn nt_decode_transaction(
     contract_abi: *mut c_char,
     method: *mut c_char,
 ) -> *mut c_char {
-    let transaction = transaction.to_string_from_ptr();
-    let contract_abi = contract_abi.to_string_from_ptr();
-    let method = method.to_optional_string_from_ptr();
-
-    fn internal_fn(
-        transaction: String,
-        contract_abi: String,
-        method: Option<String>,
-    ) -> Result<serde_json::Value, String> {
-        let transaction = serde_json::from_str::<Transaction>(&transaction).handle_error()?;
-        let contract_abi = parse_contract_abi(&contract_abi)?;
-        let method = parse_method_name(method)?;
-
-        let internal = transaction.in_msg.src.is_some();
-
-        let in_msg_body = match transaction.in_msg.body {
-            Some(body) => SliceData::load_cell(body.data).unwrap(),
-            None => return Ok(serde_json::Value::Null),
-        };
-
-        let method = match guess_method_by_input(&contract_abi, &in_msg_body, &method, internal)
-            .handle_error()?
-        {
-            Some(method) => method,
-            None => return Ok(serde_json::Value::Null),
-        };
-
-        let input = method.decode_input(in_msg_body, internal, false).handle_error()?;
-        let input = nekoton_abi::make_abi_tokens(&input).handle_error()?;
-
-        let ext_out_msgs = transaction
-            .out_msgs
-            .iter()
-            .filter_map(|e| {
-                if e.dst.is_some() {
-                    return None;
-                };
-
-                Some(match e.body.to_owned() {
-                    Some(body) => Ok(SliceData::load_cell(body.data).unwrap()),
-                    None => Err("Expected message body").handle_error(),
+    execute_sync(|| {
+        let transaction = transaction.to_string_from_ptr();
+        let contract_abi = contract_abi.to_string_from_ptr();
+        let method = method.to_optional_string_from_ptr();
+
+        fn internal_fn(
+            transaction: String,
+            contract_abi: String,
+            method: Option<String>,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let transaction = serde_json::from_str::<Transaction>(&transaction).handle_error()?;
+            let contract_abi = parse_contract_abi(&contract_abi)?;
+            let method = parse_method_name(method)?;
+
+            let internal = transaction.in_msg.src.is_some();
+
+            let in_msg_body = match transaction.in_msg.body {
+                Some(body) => {
+                    SliceData::load_cell(body.data).handle_error_as(ErrorCode::InvalidBoc)?
+                },
+                None => return Ok(serde_json::Value::Null),
+            };
+
+            let method = match guess_method_by_input(&contract_abi, &in_msg_body, &method, internal)
+                .handle_error_as(ErrorCode::DecodingFailed)?
+            {
+                Some(method) => method,
+                None => return Ok(serde_json::Value::Null),
+            };
+
+            let input = method
+                .decode_input(in_msg_body, internal, false)
+                .handle_error_as(ErrorCode::DecodingFailed)?;
+            let input =
+                nekoton_abi::make_abi_tokens(&input).handle_error_as(ErrorCode::DecodingFailed)?;
+
+            let ext_out_msgs = transaction
+                .out_msgs
+                .iter()
+                .filter_map(|e| {
+                    if e.dst.is_some() {
+                        return None;
+                    };
+
+                    Some(match e.body.to_owned() {
+                        Some(body) => {
+                            SliceData::load_cell(body.data).handle_error_as(ErrorCode::InvalidBoc)
+                        },
+                        None => Err(ExecutionError::new(
+                            ErrorCode::DecodingFailed,
+                            "Expected message body",
+                        )),
+                    })
                 })
-            })
-            .collect::<Result<Vec<_>, String>>()?;
+                .collect::<Result<Vec<_>, ExecutionError>>()?;
 
-        let output = nekoton_abi::process_raw_outputs(&ext_out_msgs, method).handle_error()?;
-        let output = nekoton_abi::make_abi_tokens(&output).handle_error()?;
+            let output = nekoton_abi::process_raw_outputs(&ext_out_msgs, method)
+                .handle_error_as(ErrorCode::DecodingFailed)?;
+            let output =
+                nekoton_abi::make_abi_tokens(&output).handle_error_as(ErrorCode::DecodingFailed)?;
 
-        let decoded_transaction = DecodedTransaction {
-            method: method.name.to_owned(),
-            input,
-            output,
-        };
+            let decoded_transaction = DecodedTransaction {
+                method: method.name.to_owned(),
+                input,
+                output,
+            };
 
-        serde_json::to_value(decoded_transaction).handle_error()
-    }
+            serde_json::to_value(decoded_transaction).handle_error()
+        }
 
-    internal_fn(transaction, contract_abi, method).match_result()
+        internal_fn(transaction, contract_abi, method)
+    })
 }
 
 #[no_mangle]
@@ -572,69 +616,81 @@ pub unsafe extern "C" fn nt_decode_transaction_events(
     transaction: *mut c_char,
     contract_abi: *mut c_char,
 ) -> *mut c_char {
-    let transaction = transaction.to_string_from_ptr();
-    let contract_abi = contract_abi.to_string_from_ptr();
-
-    fn internal_fn(transaction: String, contract_abi: String) -> Result<serde_json::Value, String> {
-        let transaction = serde_json::from_str::<Transaction>(&transaction).handle_error()?;
-        let contract_abi = parse_contract_abi(&contract_abi)?;
-
-        let ext_out_msgs = transaction
-            .out_msgs
-            .iter()
-            .filter_map(|e| {
-                if e.dst.is_some() {
-                    return None;
-                };
-
-                Some(match e.body.to_owned() {
-                    Some(body) => Ok(SliceData::load_cell(body.data).unwrap()),
-                    None => Err("Expected message body").handle_error(),
+    execute_sync(|| {
+        let transaction = transaction.to_string_from_ptr();
+        let contract_abi = contract_abi.to_string_from_ptr();
+
+        fn internal_fn(
+            transaction: String,
+            contract_abi: String,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let transaction = serde_json::from_str::<Transaction>(&transaction).handle_error()?;
+            let contract_abi = parse_contract_abi(&contract_abi)?;
+
+            let ext_out_msgs = transaction
+                .out_msgs
+                .iter()
+                .filter_map(|e| {
+                    if e.dst.is_some() {
+                        return None;
+                    };
+
+                    Some(match e.body.to_owned() {
+                        Some(body) => {
+                            SliceData::load_cell(body.data).handle_error_as(ErrorCode::InvalidBoc)
+                        },
+                        None => Err(ExecutionError::new(
+                            ErrorCode::DecodingFailed,
+                            "Expected message body",
+                        )),
+                    })
                 })
-            })
-            .collect::<Result<Vec<_>, String>>()?;
-
-        let events = ext_out_msgs
-            .into_iter()
-            .filter_map(|e| {
-                let id = nekoton_abi::read_function_id(&e).ok()?;
-                let event = contract_abi.event_by_id(id).ok()?;
-                let tokens = event.decode_input(e).ok()?;
-
-                let data = match nekoton_abi::make_abi_tokens(&tokens) {
-                    Ok(data) => Ok(DecodedEvent {
-                        event: event.name.to_owned(),
-                        data,
-                    }),
-                    Err(err) => Err(err).handle_error(),
-                };
-
-                Some(data)
-            })
-            .collect::<Result<Vec<_>, String>>()?;
+                .collect::<Result<Vec<_>, ExecutionError>>()?;
+
+            let events = ext_out_msgs
+                .into_iter()
+                .filter_map(|e| {
+                    let id = nekoton_abi::read_function_id(&e).ok()?;
+                    let event = contract_abi.event_by_id(id).ok()?;
+                    let tokens = event.decode_input(e).ok()?;
+
+                    let data = match nekoton_abi::make_abi_tokens(&tokens) {
+                        Ok(data) => Ok(DecodedEvent {
+                            event: event.name.to_owned(),
+                            data,
+                        }),
+                        Err(err) => Err(err).handle_error_as(ErrorCode::DecodingFailed),
+                    };
+
+                    Some(data)
+                })
+                .collect::<Result<Vec<_>, ExecutionError>>()?;
 
-        serde_json::to_value(events).handle_error()
-    }
+            serde_json::to_value(events).handle_error()
+        }
 
-    internal_fn(transaction, contract_abi).match_result()
+        internal_fn(transaction, contract_abi)
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_get_boc_hash(boc: *mut c_char) -> *mut c_char {
-    let boc = boc.to_string_from_ptr();
+    execute_sync(|| {
+        let boc = boc.to_string_from_ptr();
 
-    fn internal_fn(boc: String) -> Result<serde_json::Value, String> {
-        let body = base64::decode(boc).handle_error()?;
+        fn internal_fn(boc: String) -> Result<serde_json::Value, ExecutionError> {
+            let body = base64::decode(boc).handle_error()?;
 
-        let hash = ton_types::deserialize_tree_of_cells(&mut body.as_slice())
-            .handle_error()?
-            .repr_hash()
-            .to_hex_string();
+            let hash = ton_types::deserialize_tree_of_cells(&mut body.as_slice())
+                .handle_error_as(ErrorCode::InvalidBoc)?
+                .repr_hash()
+                .to_hex_string();
 
-        serde_json::to_value(hash).handle_error()
-    }
+            serde_json::to_value(hash).handle_error()
+        }
 
-    internal_fn(boc).match_result()
+        internal_fn(boc)
+    })
 }
 
 #[no_mangle]
@@ -642,24 +698,31 @@ pub unsafe extern "C" fn nt_pack_into_cell(
     params: *mut c_char,
     tokens: *mut c_char,
 ) -> *mut c_char {
-    let params = params.to_string_from_ptr();
-    let tokens = tokens.to_string_from_ptr();
-
-    fn internal_fn(params: String, tokens: String) -> Result<serde_json::Value, String> {
-        let params = parse_params_list(&params)?;
-        let tokens = serde_json::from_str::<serde_json::Value>(&tokens).handle_error()?;
-        let tokens = nekoton_abi::parse_abi_tokens(&params, tokens).handle_error()?;
-        let version = ton_abi::contract::AbiVersion { major: 2, minor: 2 };
-
-        let cell = nekoton_abi::pack_into_cell(&tokens, version).handle_error()?;
-        let bytes = ton_types::serialize_toc(&cell).handle_error()?;
-
-        let bytes = base64::encode(bytes);
-
-        serde_json::to_value(bytes).handle_error()
-    }
+    execute_sync(|| {
+        let params = params.to_string_from_ptr();
+        let tokens = tokens.to_string_from_ptr();
+
+        fn internal_fn(
+            params: String,
+            tokens: String,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let params = parse_params_list(&params)?;
+            let tokens = serde_json::from_str::<serde_json::Value>(&tokens).handle_error()?;
+            let tokens = nekoton_abi::parse_abi_tokens(&params, tokens)
+                .handle_error_as(ErrorCode::EncodingFailed)?;
+            let version = ton_abi::contract::AbiVersion { major: 2, minor: 2 };
+
+            let cell = nekoton_abi::pack_into_cell(&tokens, version)
+                .handle_error_as(ErrorCode::EncodingFailed)?;
+            let bytes = ton_types::serialize_toc(&cell).handle_error()?;
+
+            let bytes = base64::encode(bytes);
+
+            serde_json::to_value(bytes).handle_error()
+        }
 
-    internal_fn(params, tokens).match_result()
+        internal_fn(params, tokens)
+    })
 }
 
 #[no_mangle]
@@ -668,40 +731,53 @@ pub unsafe extern "C" fn nt_unpack_from_cell(
     boc: *mut c_char,
     allow_partial: c_uint,
 ) -> *mut c_char {
-    let params = params.to_string_from_ptr();
-    let boc = boc.to_string_from_ptr();
-    let allow_partial = allow_partial != 0;
-
-    fn internal_fn(
-        params: String,
-        boc: String,
-        allow_partial: bool,
-    ) -> Result<serde_json::Value, String> {
-        let params = parse_params_list(&params)?;
-        let body = base64::decode(boc).handle_error()?;
-        let cell = ton_types::deserialize_tree_of_cells(&mut body.as_slice()).handle_error()?;
-        let version = ton_abi::contract::AbiVersion { major: 2, minor: 2 };
-
-        let tokens = nekoton_abi::unpack_from_cell(
-            &params,
-            SliceData::load_cell(cell).unwrap(),
-            allow_partial,
-            version,
-        )
-        .handle_error()
-        .and_then(|e| nekoton_abi::make_abi_tokens(&e).handle_error())?;
+    execute_sync(|| {
+        let params = params.to_string_from_ptr();
+        let boc = boc.to_string_from_ptr();
+        let allow_partial = allow_partial != 0;
+
+        fn internal_fn(
+            params: String,
+            boc: String,
+            allow_partial: bool,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let params = parse_params_list(&params)?;
+            let body = base64::decode(boc).handle_error()?;
+            let cell = ton_types::deserialize_tree_of_cells(&mut body.as_slice())
+                .handle_error_as(ErrorCode::InvalidBoc)?;
+            let version = ton_abi::contract::AbiVersion { major: 2, minor: 2 };
+
+            let tokens = nekoton_abi::unpack_from_cell(
+                &params,
+                SliceData::load_cell(cell).handle_error_as(ErrorCode::InvalidBoc)?,
+                allow_partial,
+                version,
+            )
+            .and_then(|e| nekoton_abi::make_abi_tokens(&e))
+            .handle_error_as(ErrorCode::DecodingFailed)?;
 
-        serde_json::to_value(tokens).handle_error()
-    }
+            serde_json::to_value(tokens).handle_error()
+        }
+
+        internal_fn(params, boc, allow_partial)
+    })
+}
 
-    internal_fn(params, boc, allow_partial).match_result()
+pub fn parse_contract_abi(contract_abi: &str) -> Result<ton_abi::Contract, ExecutionError> {
+    ton_abi::Contract::load(contract_abi.as_bytes()).handle_error_as(ErrorCode::InvalidAbi)
 }
 
-fn parse_contract_abi(contract_abi: &str) -> Result<ton_abi::Contract, String> {
-    ton_abi::Contract::load(contract_abi.as_bytes()).handle_error()
+fn parse_function<'a>(
+    contract_abi: &'a ton_abi::Contract,
+    method: &str,
+) -> Result<&'a ton_abi::Function, ExecutionError> {
+    contract_abi
+        .function(method)
+        .handle_error_as(ErrorCode::InvalidAbi)
+        .map_err(|e| e.with_context("method", method))
 }
 
-fn parse_method_name(value: Option<String>) -> Result<MethodName, String> {
+fn parse_method_name(value: Option<String>) -> Result<MethodName, ExecutionError> {
     match value {
         Some(value) => {
             if let Ok(value) = serde_json::from_str::<String>(&value) {
@@ -709,27 +785,28 @@ fn parse_method_name(value: Option<String>) -> Result<MethodName, String> {
             } else if let Ok(value) = serde_json::from_str::<Vec<String>>(&value) {
                 Ok(MethodName::GuessInRange(value))
             } else {
-                Err(AbiError::ExpectedStringOrArray).handle_error()
+                Err(AbiError::ExpectedStringOrArray).handle_error_as(ErrorCode::InvalidInput)
             }
         },
         None => Ok(MethodName::Guess),
     }
 }
 
-fn parse_slice(boc: &str) -> Result<SliceData, String> {
-    let body = base64::decode(boc).handle_error()?;
-    let cell = ton_types::deserialize_tree_of_cells(&mut body.as_slice()).handle_error()?;
-    SliceData::load_cell(cell).map_err(|e| e.to_string())
+fn parse_slice(boc: &str) -> Result<SliceData, ExecutionError> {
+    let body = base64::decode(boc).handle_error_as(ErrorCode::InvalidBoc)?;
+    let cell = ton_types::deserialize_tree_of_cells(&mut body.as_slice())
+        .handle_error_as(ErrorCode::InvalidBoc)?;
+    SliceData::load_cell(cell).handle_error_as(ErrorCode::InvalidBoc)
 }
 
-fn parse_params_list(params: &str) -> Result<Vec<ton_abi::Param>, String> {
+fn parse_params_list(params: &str) -> Result<Vec<ton_abi::Param>, ExecutionError> {
     let params = serde_json::from_str::<Vec<AbiParam>>(params).handle_error()?;
 
     params
         .iter()
         .map(parse_param)
         .collect::<Result<_, AbiError>>()
-        .handle_error()
+        .handle_error_as(ErrorCode::InvalidAbi)
 }
 
 fn parse_param(param: &AbiParam) -> Result<ton_abi::Param, AbiError> {
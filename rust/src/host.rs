@@ -0,0 +1,184 @@
+use std::{
+    ffi::CString,
+    os::raw::{c_char, c_longlong},
+    sync::{Arc, RwLock},
+};
+
+use allo_isolate::Isolate;
+use lazy_static::lazy_static;
+
+use crate::{
+    errors::{ErrorCode, ExecutionError},
+    execute_sync, nt_free_cstring, ToCStringPtr, ToPtrAddress,
+};
+
+lazy_static! {
+    static ref HOST_CALLBACKS: RwLock<Arc<dyn HostCallbacks>> =
+        RwLock::new(Arc::new(DartPortCallbacks));
+}
+
+/// Delivers messages produced by the library to the host application.
+///
+/// Ports are opaque ids chosen by the host, e.g. Dart native ports. Every payload is
+/// a JSON string, a `false` return value means that the message wasn't delivered.
+pub trait HostCallbacks: Send + Sync {
+    /// Serialized `ExecutionResult` of an asynchronous call.
+    fn post_result(&self, port: i64, result: String) -> bool;
+
+    /// Subscription event, no reply is expected.
+    fn post_event(&self, port: i64, event: String) -> bool;
+
+    /// Request to an external connection or storage, answered with `nt_external_resolve_request_*`.
+    fn post_request(&self, port: i64, request: String) -> bool;
+}
+
+pub fn host_callbacks() -> Arc<dyn HostCallbacks> {
+    HOST_CALLBACKS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+pub fn set_host_callbacks(callbacks: Arc<dyn HostCallbacks>) {
+    *HOST_CALLBACKS.write().unwrap_or_else(|e| e.into_inner()) = callbacks;
+}
+
+/// Host port of a single kind of messages.
+#[derive(Clone, Copy, Debug)]
+pub struct HostPort {
+    port: i64,
+    kind: MessageKind,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum MessageKind {
+    Event,
+    Request,
+}
+
+impl HostPort {
+    pub fn event(port: i64) -> Self {
+        Self {
+            port,
+            kind: MessageKind::Event,
+        }
+    }
+
+    pub fn request(port: i64) -> Self {
+        Self {
+            port,
+            kind: MessageKind::Request,
+        }
+    }
+
+    pub fn post(&self, payload: String) -> bool {
+        let callbacks = host_callbacks();
+
+        match self.kind {
+            MessageKind::Event => callbacks.post_event(self.port, payload),
+            MessageKind::Request => callbacks.post_request(self.port, payload),
+        }
+    }
+}
+
+/// Posts messages to Dart native ports.
+///
+/// Results are passed as an address of a C string which Dart releases with `nt_free_cstring`,
+/// other messages are posted as Dart strings.
+pub struct DartPortCallbacks;
+
+impl HostCallbacks for DartPortCallbacks {
+    fn post_result(&self, port: i64, result: String) -> bool {
+        let result = result.to_cstring_ptr();
+
+        let posted = Isolate::new(port).post(result.to_ptr_address());
+        if !posted {
+            unsafe { nt_free_cstring(result) };
+        }
+
+        posted
+    }
+
+    fn post_event(&self, port: i64, event: String) -> bool {
+        Isolate::new(port).post(event)
+    }
+
+    fn post_request(&self, port: i64, request: String) -> bool {
+        Isolate::new(port).post(request)
+    }
+}
+
+/// Callback of a C host, `payload` is a null-terminated JSON string
+/// which is only valid until the callback returns.
+pub type HostCallback = unsafe extern "C" fn(port: c_longlong, payload: *const c_char) -> bool;
+
+/// Calls C function pointers, for embedding without a Dart VM.
+pub struct FnPtrCallbacks {
+    pub post_result: HostCallback,
+    pub post_event: HostCallback,
+    pub post_request: HostCallback,
+}
+
+impl FnPtrCallbacks {
+    fn call(callback: HostCallback, port: i64, payload: String) -> bool {
+        match CString::new(payload) {
+            Ok(payload) => unsafe { callback(port, payload.as_ptr()) },
+            Err(err) => {
+                log::error!("Invalid payload: {}", err);
+                false
+            },
+        }
+    }
+}
+
+impl HostCallbacks for FnPtrCallbacks {
+    fn post_result(&self, port: i64, result: String) -> bool {
+        Self::call(self.post_result, port, result)
+    }
+
+    fn post_event(&self, port: i64, event: String) -> bool {
+        Self::call(self.post_event, port, event)
+    }
+
+    fn post_request(&self, port: i64, request: String) -> bool {
+        Self::call(self.post_request, port, request)
+    }
+}
+
+/// Routes all messages to the given C callbacks instead of Dart ports.
+/// Passing null for every callback restores delivery to Dart ports.
+#[no_mangle]
+pub unsafe extern "C" fn nt_set_host_callbacks(
+    post_result: Option<HostCallback>,
+    post_event: Option<HostCallback>,
+    post_request: Option<HostCallback>,
+) -> *mut c_char {
+    execute_sync(|| {
+        fn internal_fn(
+            post_result: Option<HostCallback>,
+            post_event: Option<HostCallback>,
+            post_request: Option<HostCallback>,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            match (post_result, post_event, post_request) {
+                (Some(post_result), Some(post_event), Some(post_request)) => {
+                    set_host_callbacks(Arc::new(FnPtrCallbacks {
+                        post_result,
+                        post_event,
+                        post_request,
+                    }))
+                },
+                (None, None, None) => set_host_callbacks(Arc::new(DartPortCallbacks)),
+                _ => {
+                    return Err(ExecutionError::new(
+                        ErrorCode::InvalidInput,
+                        "Either all or none of the callbacks must be set",
+                    ))
+                },
+            }
+
+            Ok(serde_json::Value::Null)
+        }
+
+        internal_fn(post_result, post_event, post_request)
+    })
+}
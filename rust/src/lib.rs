@@ -8,46 +8,44 @@
 
 mod core;
 mod crypto;
+mod errors;
 mod external;
+mod handles;
 mod helpers;
+mod host;
 mod models;
+mod operations;
+mod runtime;
+#[cfg(test)]
+mod tests;
 mod transport;
 
 use std::{
     ffi::{CStr, CString},
+    future::Future,
     intrinsics::transmute,
-    io,
     os::raw::{c_char, c_void},
+    panic::{self, AssertUnwindSafe},
     str::FromStr,
     sync::Arc,
 };
 
-use allo_isolate::{
-    ffi::{DartCObject, DartPort},
-    IntoDart, Isolate,
-};
+use allo_isolate::ffi::{DartCObject, DartPort};
 use anyhow::{Context, Result};
 use lazy_static::lazy_static;
-use nekoton_utils::SimpleClock;
+use nekoton_utils::ClockWithOffset;
 use serde::Serialize;
-use tokio::runtime::{Builder, Runtime};
 use ton_block::MsgAddressInt;
 
+use crate::{
+    errors::{ErrorCode, ExecutionError},
+    host::{host_callbacks, HostPort},
+};
+
 pub const ISOLATE_MESSAGE_POST_ERROR: &str = "Message was not posted successfully";
 
 lazy_static! {
-    static ref RUNTIME: io::Result<Runtime> = Builder::new_multi_thread()
-        .enable_all()
-        .thread_name("nekoton_flutter")
-        .build();
-    static ref CLOCK: Arc<SimpleClock> = Arc::new(SimpleClock {});
-}
-
-#[macro_export]
-macro_rules! runtime {
-    () => {
-        RUNTIME.as_ref().unwrap()
-    };
+    static ref CLOCK: Arc<ClockWithOffset> = Arc::new(ClockWithOffset::new(0));
 }
 
 #[macro_export]
@@ -59,34 +57,132 @@ macro_rules! clock {
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_store_dart_post_cobject(ptr: *mut c_void) {
-    let ptr = transmute::<
-        *mut c_void,
-        unsafe extern "C" fn(port_id: DartPort, message: *mut DartCObject) -> bool,
-    >(ptr);
-
-    allo_isolate::store_dart_post_cobject(ptr);
+    catch_panic("nt_store_dart_post_cobject", || {
+        let ptr = transmute::<
+            *mut c_void,
+            unsafe extern "C" fn(port_id: DartPort, message: *mut DartCObject) -> bool,
+        >(ptr);
+
+        allo_isolate::store_dart_post_cobject(ptr);
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_cstring_to_void_ptr(ptr: *mut c_char) -> *mut c_void {
-    ptr.to_string_from_ptr().to_ptr_from_address::<c_void>()
+    catch_panic("nt_cstring_to_void_ptr", || {
+        match ptr.to_string_from_ptr().to_ptr_from_address::<c_void>() {
+            Ok(ptr) => ptr,
+            Err(err) => {
+                log::error!("nt_cstring_to_void_ptr: {}", err);
+                std::ptr::null_mut()
+            },
+        }
+    })
+    .unwrap_or(std::ptr::null_mut())
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_void_ptr_to_c_str(ptr: *mut c_void) -> *mut c_char {
-    let string = (ptr as u64).to_string();
-    let c_string = CString::new(string).unwrap();
-
-    c_string.into_raw()
+    catch_panic("nt_void_ptr_to_c_str", || {
+        (ptr as u64).to_string().to_cstring_ptr()
+    })
+    .unwrap_or(std::ptr::null_mut())
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_free_cstring(ptr: *mut c_char) {
-    if (ptr as u64) < 65536 {
-        log::error!("nt_free_cstring: ptr is null");
-        panic!("nt_free_cstring: ptr is null");
+    catch_panic("nt_free_cstring", || {
+        if (ptr as u64) < 65536 {
+            log::error!("nt_free_cstring: ptr is null");
+            return;
+        }
+
+        drop(CString::from_raw(ptr));
+    });
+}
+
+/// Runs the body of a synchronous FFI call, a panic is returned as `ErrorCode::Panic`.
+pub fn execute_sync<F>(f: F) -> *mut c_char
+where
+    F: FnOnce() -> Result<serde_json::Value, ExecutionError>,
+{
+    panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|payload| Err(ExecutionError::from_panic(payload)))
+        .match_result()
+}
+
+/// Runs the preparation part of an asynchronous FFI call and spawns the returned future.
+/// The result is always posted to `result_port`, including preparation errors and panics.
+///
+/// Returns an operation id which can be passed to `nt_cancel_operation`.
+pub fn execute_async<F, Fut>(result_port: i64, f: F) -> u64
+where
+    F: FnOnce() -> Result<Fut, ExecutionError>,
+    Fut: Future<Output = Result<serde_json::Value, ExecutionError>> + Send + 'static,
+{
+    let id = operations::next_operation_id();
+
+    let future = panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|payload| Err(ExecutionError::from_panic(payload)));
+
+    let runtime = match runtime::runtime_handle() {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            post_result(result_port, ExecutionResult::Err(err));
+            return id;
+        },
+    };
+
+    let future = match future {
+        Ok(future) => future,
+        Err(err) => {
+            post_result(result_port, ExecutionResult::Err(err));
+            return id;
+        },
+    };
+
+    let handle = runtime.spawn(future);
+    operations::register_operation(id, handle.abort_handle());
+
+    runtime.spawn(async move {
+        let result = match handle.await {
+            Ok(result) => result.into(),
+            Err(err) if err.is_cancelled() => ExecutionResult::Cancelled,
+            Err(err) if err.is_panic() => {
+                ExecutionResult::Err(ExecutionError::from_panic(err.into_panic()))
+            },
+            Err(err) => {
+                ExecutionResult::Err(ExecutionError::new(ErrorCode::Internal, err.to_string()))
+            },
+        };
+
+        operations::finish_operation(id);
+        post_result(result_port, result);
+    });
+
+    id
+}
+
+/// Guards FFI calls that have no result to report, a panic is only logged.
+pub fn catch_panic<F, T>(name: &str, f: F) -> Option<T>
+where
+    F: FnOnce() -> T,
+{
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(value) => Some(value),
+        Err(payload) => {
+            log::error!("{}: {}", name, ExecutionError::from_panic(payload));
+            None
+        },
+    }
+}
+
+fn post_result(result_port: i64, result: ExecutionResult<serde_json::Value>) {
+    let result = serde_json::to_string(&result).unwrap();
+
+    if !host_callbacks().post_result(result_port, result) {
+        log::error!("{}", ISOLATE_MESSAGE_POST_ERROR);
     }
-    ptr.to_string_from_ptr();
 }
 
 #[derive(Serialize)]
@@ -96,22 +192,33 @@ where
     T: Serialize,
 {
     Ok(T),
-    Err(String),
+    Err(ExecutionError),
+    /// Operation was aborted with `nt_cancel_operation`.
+    Cancelled,
+}
+
+impl<T> From<Result<T, ExecutionError>> for ExecutionResult<T>
+where
+    T: Serialize,
+{
+    fn from(result: Result<T, ExecutionError>) -> Self {
+        match result {
+            Ok(ok) => Self::Ok(ok),
+            Err(err) => Self::Err(err),
+        }
+    }
 }
 
 pub trait MatchResult {
     fn match_result(self) -> *mut c_char;
 }
 
-impl<T> MatchResult for Result<T, String>
+impl<T> MatchResult for Result<T, ExecutionError>
 where
     T: Serialize,
 {
     fn match_result(self) -> *mut c_char {
-        let result = match self {
-            Ok(ok) => ExecutionResult::Ok(ok),
-            Err(err) => ExecutionResult::Err(err),
-        };
+        let result = ExecutionResult::from(self);
 
         serde_json::to_string(&result).unwrap().to_cstring_ptr()
     }
@@ -120,45 +227,63 @@ where
 pub trait HandleError {
     type Output;
 
-    fn handle_error(self) -> Result<Self::Output, String>;
+    fn handle_error(self) -> Result<Self::Output, ExecutionError>;
+
+    fn handle_error_as(self, code: ErrorCode) -> Result<Self::Output, ExecutionError>;
 }
 
 impl<T, E> HandleError for Result<T, E>
 where
-    E: ToString,
+    E: Into<anyhow::Error>,
 {
     type Output = T;
 
-    fn handle_error(self) -> Result<Self::Output, String> {
-        self.map_err(|e| e.to_string())
+    fn handle_error(self) -> Result<Self::Output, ExecutionError> {
+        self.map_err(|e| ExecutionError::from(e.into()))
+    }
+
+    fn handle_error_as(self, code: ErrorCode) -> Result<Self::Output, ExecutionError> {
+        self.map_err(|e| ExecutionError::from_anyhow(code, e.into()))
     }
 }
 
-pub trait PostWithResult {
-    fn post_with_result(&self, data: impl IntoDart) -> Result<(), String>;
+pub trait PostSerialized {
+    fn post_serialized(&self, data: &impl Serialize);
 }
 
-impl PostWithResult for Isolate {
-    fn post_with_result(&self, data: impl IntoDart) -> Result<(), String> {
-        match self.post(data) {
-            true => Ok(()),
-            false => Err(ISOLATE_MESSAGE_POST_ERROR).handle_error(),
+impl PostSerialized for HostPort {
+    fn post_serialized(&self, data: &impl Serialize) {
+        match serde_json::to_string(data) {
+            Ok(payload) => {
+                if !self.post(payload) {
+                    log::error!("{}", ISOLATE_MESSAGE_POST_ERROR);
+                }
+            },
+            Err(err) => log::error!("Failed to serialize payload: {}", err),
         }
     }
 }
 
-fn parse_hash(hash: &str) -> Result<ton_types::UInt256, String> {
-    ton_types::UInt256::from_str(hash).handle_error()
+fn parse_hash(hash: &str) -> Result<ton_types::UInt256, ExecutionError> {
+    ton_types::UInt256::from_str(hash)
+        .handle_error_as(ErrorCode::InvalidHash)
+        .map_err(|e| e.with_context("hash", hash))
 }
 
-fn parse_public_key(public_key: &str) -> Result<ed25519_dalek::PublicKey, anyhow::Error> {
-    Ok(ed25519_dalek::PublicKey::from_bytes(
-        &hex::decode(public_key).context("Bad hex data")?,
-    )?)
+fn parse_public_key(public_key: &str) -> Result<ed25519_dalek::PublicKey, ExecutionError> {
+    let bytes = hex::decode(public_key)
+        .context("Bad hex data")
+        .handle_error_as(ErrorCode::InvalidPublicKey)?;
+
+    ed25519_dalek::PublicKey::from_bytes(&bytes)
+        .handle_error_as(ErrorCode::InvalidPublicKey)
+        .map_err(|e| e.with_context("publicKey", public_key))
 }
 
-fn parse_address(address: &str) -> Result<MsgAddressInt, String> {
-    MsgAddressInt::from_str(address).handle_error()
+fn parse_address(address: &str) -> Result<MsgAddressInt, ExecutionError> {
+    MsgAddressInt::from_str(address)
+        .handle_error_as(ErrorCode::InvalidAddress)
+        .map_err(|e| e.with_context("address", address))
 }
 
 pub trait ToPtrAddress {
@@ -172,17 +297,20 @@ impl<T> ToPtrAddress for *mut T {
 }
 
 pub trait ToPtrFromAddress {
-    fn to_ptr_from_address<T>(self) -> *mut T;
+    fn to_ptr_from_address<T>(self) -> Result<*mut T, ExecutionError>;
 }
 
 impl ToPtrFromAddress for String {
-    fn to_ptr_from_address<T>(self) -> *mut T {
-        let address = u64::from_str(&self).unwrap();
+    fn to_ptr_from_address<T>(self) -> Result<*mut T, ExecutionError> {
+        let address = u64::from_str(&self).handle_error_as(ErrorCode::InvalidPointer)?;
         if address < 65536 {
-            panic!("Invalid address");
+            return Err(
+                ExecutionError::new(ErrorCode::InvalidPointer, "Invalid address")
+                    .with_context("address", address),
+            );
         }
 
-        address as *mut T
+        Ok(address as *mut T)
     }
 }
 
@@ -219,124 +347,31 @@ impl ToOptionalStringFromPtr for *mut c_char {
     }
 }
 
-/// creates ffi constructor and destructor for the given type
-/// exmaple:
-/// ```ignore
-/// ffi_constructor_and_destructor!(Arc, wallet: TONWallet);
-///
-/// // will create:
-/// fn wallet_new(wallet: TONWallet) -> *mut c_void
-/// {
-///   Box::into_raw(Box::new(Arc::new(wallet))) as *mut c_void
-/// }
-///
-/// fn nt_wallet_free_ptr(wallet: *mut c_void){
-///  let _ = unsafe { Box::from_raw(wallet as *mut Arc<TONWallet>) };
-/// }
-/// ```
-#[macro_export]
-macro_rules! ffi_box {
-    ($name:ident, $type:ty) => {
-         ::paste::paste! {
-            /// creates new ffi object of type $type
-            #[allow(clippy::disallowed_methods,clippy::ptr_as_ptr,dead_code)]
-            pub  fn [< $name _new >](data: $type) -> *mut std::ffi::c_void {
-                Box::into_raw(Box::new(data)) as *mut std::ffi::c_void
-            }
-
-            #[no_mangle]
-            #[allow(clippy::disallowed_methods,clippy::ptr_as_ptr,dead_code)]
-            pub unsafe extern "C" fn [< nt_ $name _free_ptr >](ptr: *mut std::ffi::c_void) {
-                 #[allow(clippy::disallowed_methods)]
-                 if (ptr as u64) < 65536 {
-                     log::error!(concat!("nt_", stringify!($name), "_free_ptr: ptr is null"));
-                     panic!(concat!("nt_", stringify!($name), "_free_ptr: ptr is null"));
-                 }
-                let _ = unsafe { Box::from_raw(ptr as *mut $type) };
-            }
-
-             /// creates new ffi object of type $type
-             /// SAFETY: ptr must be valid pointer to $type
-             /// Panics if ptr < 65536
-            #[allow(clippy::disallowed_methods,clippy::ptr_as_ptr,dead_code)]
-            pub unsafe fn [< $name _from_native_ptr >]<'b>(ptr: *mut std::ffi::c_void) -> &'b $type {
-                 if (ptr as u64) < 65536 {
-                    log::error!(concat!("nt_", stringify!($name), "_from_native_ptr: ptr is null"));
-                    panic!(concat!("nt_", stringify!($name), "_from_native_ptr: ptr is null"));
-                }
-                &*(ptr as *const $type)
-            }
-
-            #[allow(clippy::disallowed_methods,clippy::ptr_as_ptr,dead_code)]
-            pub unsafe fn [< $name _from_native_ptr_opt >]<'b>(ptr: *mut std::ffi::c_void) -> Option<&'b $type> {
-                if ptr.is_null() {
-                    None
-                } else {
-                    Some(&*(ptr as *const $type))
-                }
-            }
-
-            /// creates new ffi object of type $type
-            /// SAFETY: ptr must be valid pointer to $type
-            /// Panics if ptr < 65536
-            #[allow(clippy::disallowed_methods,clippy::ptr_as_ptr,dead_code)]
-            pub unsafe fn [< $name _from_native_ptr_mut >]<'b>(ptr: *mut std::ffi::c_void) -> &'b mut $type {
-                 if (ptr as u64) < 65536 {
-                    log::error!(concat!("nt_", stringify!($name), "_from_native_ptr: ptr is null"));
-                    panic!(concat!("nt_", stringify!($name), "_from_native_ptr: ptr is null"));
-                }
-                &mut *(ptr as *mut $type)
-            }
-
-
-            /// creates new ffi object of type $type
-            /// SAFETY: ptr must be valid pointer to $type
-            /// Panics if ptr < 65536
-            #[allow(clippy::disallowed_methods,clippy::ptr_as_ptr,dead_code)]
-            pub unsafe fn [< $name _from_native_ptr_owned >]<'b>(ptr: *mut std::ffi::c_void) -> Box<$type> {
-                if (ptr as u64) < 65536 {
-                    log::error!(concat!("nt_", stringify!($name), "_from_native_ptr: ptr is null"));
-                    panic!(concat!("nt_", stringify!($name), "_from_native_ptr: ptr is null"));
-                }
-                Box::from_raw(ptr as *mut $type)
-            }
-         }
-    };
+#[no_mangle]
+pub unsafe extern "C" fn nt_init_logging() {
+    catch_panic("nt_init_logging", || {
+        log_panics::init();
+        #[cfg(target_os = "android")]
+        {
+            android_logger::init_once(
+                android_logger::Config::default().with_min_level(log::Level::Debug),
+            );
+        }
+    });
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn nt_init_logging() {
-    log_panics::init();
-    #[cfg(target_os = "android")]
-    {
-        android_logger::init_once(
-            android_logger::Config::default().with_min_level(log::Level::Debug),
-        );
-    }
+pub unsafe extern "C" fn nt_debug_live_handles() -> *mut c_char {
+    execute_sync(|| serde_json::to_value(handles::handles().live()).handle_error())
 }
 
-ffi_box!(channel_err, tokio::sync::oneshot::Sender<Result<String>>);
-ffi_box!(channel_option, tokio::sync::oneshot::Sender<Option<String>>);
-ffi_box!(
-    channel_result_option,
+ffi_handle!(@owned channel_err, tokio::sync::oneshot::Sender<Result<String>>);
+ffi_handle!(@owned channel_option, tokio::sync::oneshot::Sender<Option<String>>);
+ffi_handle!(
+    @owned channel_result_option,
     tokio::sync::oneshot::Sender<Result<Option<String>>>
 );
-ffi_box!(
-    channel_result_unit,
+ffi_handle!(
+    @owned channel_result_unit,
     tokio::sync::oneshot::Sender<Result<()>>
 );
-
-#[cfg(test)]
-mod test {
-    #[test]
-    fn test_ffi() {
-        ffi_box!(test, String);
-
-        let ptr = test_new("test".to_owned());
-        let s = unsafe { test_from_native_ptr(ptr) };
-        println!("{}", s.len());
-        unsafe {
-            nt_test_free_ptr(ptr);
-        }
-    }
-}
@@ -0,0 +1,75 @@
+use std::{
+    collections::HashMap,
+    os::raw::c_ulonglong,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, MutexGuard,
+    },
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+use tokio::task::AbortHandle;
+
+use crate::catch_panic;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Zero is never assigned, so Dart can use it as "no operation".
+static NEXT_OPERATION_ID: AtomicU64 = AtomicU64::new(1);
+
+lazy_static! {
+    static ref OPERATIONS: Mutex<HashMap<u64, AbortHandle>> = Mutex::new(HashMap::new());
+}
+
+fn operations() -> MutexGuard<'static, HashMap<u64, AbortHandle>> {
+    OPERATIONS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn next_operation_id() -> u64 {
+    NEXT_OPERATION_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Makes a running task cancellable via `nt_cancel_operation`.
+pub fn register_operation(id: u64, handle: AbortHandle) {
+    operations().insert(id, handle);
+}
+
+pub fn finish_operation(id: u64) {
+    operations().remove(&id);
+}
+
+pub fn abort_operations() {
+    operations().values().for_each(AbortHandle::abort);
+}
+
+pub fn is_idle() -> bool {
+    operations().is_empty()
+}
+
+/// Waits until all running operations have posted their results.
+/// Returns `false` if some are still running at the deadline.
+pub fn wait_operations(deadline: Instant) -> bool {
+    loop {
+        if operations().is_empty() {
+            return true;
+        }
+
+        if Instant::now() >= deadline {
+            return false;
+        }
+
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Aborts the task of an asynchronous call, the call then posts a `cancelled` result
+/// to its result port. Unknown or already finished operations are ignored.
+#[no_mangle]
+pub unsafe extern "C" fn nt_cancel_operation(id: c_ulonglong) {
+    catch_panic("nt_cancel_operation", || {
+        if let Some(handle) = operations().get(&id) {
+            handle.abort();
+        }
+    });
+}
@@ -0,0 +1,206 @@
+use std::{
+    os::raw::{c_char, c_ulonglong},
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use tokio::runtime::{Builder, Handle, Runtime};
+
+use crate::{
+    catch_panic,
+    errors::{ErrorCode, ExecutionError},
+    execute_sync, handles, operations, HandleError, ToStringFromPtr, CLOCK,
+};
+
+const DEFAULT_THREAD_NAME: &str = "nekoton_flutter";
+
+/// Time given to cancelled operations to post their results.
+const ABORT_GRACE_PERIOD: Duration = Duration::from_millis(100);
+
+lazy_static! {
+    static ref RUNTIME: Mutex<Option<RuntimeState>> = Mutex::new(None);
+}
+
+struct RuntimeState {
+    runtime: Runtime,
+    /// Set for the default runtime started by an asynchronous call before `nt_runtime_init`.
+    is_implicit: bool,
+}
+
+fn runtime() -> MutexGuard<'static, Option<RuntimeState>> {
+    RUNTIME.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeConfig {
+    pub worker_threads: Option<usize>,
+    pub thread_name: Option<String>,
+    pub max_blocking_threads: Option<usize>,
+    pub clock_offset_ms: Option<i64>,
+}
+
+fn build_runtime(config: &RuntimeConfig) -> Result<Runtime, ExecutionError> {
+    let mut builder = Builder::new_multi_thread();
+
+    builder
+        .enable_all()
+        .thread_name(config.thread_name.as_deref().unwrap_or(DEFAULT_THREAD_NAME));
+
+    if let Some(worker_threads) = config.worker_threads {
+        if worker_threads == 0 {
+            return Err(ExecutionError::new(
+                ErrorCode::InvalidInput,
+                "Worker threads count must be greater than zero",
+            ));
+        }
+        builder.worker_threads(worker_threads);
+    }
+
+    if let Some(max_blocking_threads) = config.max_blocking_threads {
+        if max_blocking_threads == 0 {
+            return Err(ExecutionError::new(
+                ErrorCode::InvalidInput,
+                "Blocking threads count must be greater than zero",
+            ));
+        }
+        builder.max_blocking_threads(max_blocking_threads);
+    }
+
+    builder.build().handle_error_as(ErrorCode::RuntimeFailed)
+}
+
+/// Handle of the running runtime. A runtime with default settings is started
+/// if `nt_runtime_init` wasn't called or the runtime was shut down.
+pub fn runtime_handle() -> Result<Handle, ExecutionError> {
+    let mut runtime = runtime();
+
+    if let Some(state) = runtime.as_ref() {
+        return Ok(state.runtime.handle().clone());
+    }
+
+    let default = build_runtime(&RuntimeConfig::default())?;
+    let handle = default.handle().clone();
+    *runtime = Some(RuntimeState {
+        runtime: default,
+        is_implicit: true,
+    });
+
+    Ok(handle)
+}
+
+/// Starts the runtime with the given settings.
+///
+/// A default runtime started by an earlier asynchronous call is replaced as long as
+/// no operations are running on it and no handles are alive, since subscriptions and
+/// transports run their own tasks on it. Otherwise the call fails with
+/// `ErrorCode::RuntimeFailed`.
+#[no_mangle]
+pub unsafe extern "C" fn nt_runtime_init(config: *mut c_char) -> *mut c_char {
+    execute_sync(|| {
+        let config = config.to_string_from_ptr();
+
+        fn internal_fn(config: String) -> Result<serde_json::Value, ExecutionError> {
+            let config = serde_json::from_str::<RuntimeConfig>(&config)
+                .handle_error_as(ErrorCode::InvalidInput)?;
+
+            let mut runtime = runtime();
+
+            match runtime.as_ref() {
+                Some(state) if !state.is_implicit => {
+                    return Err(ExecutionError::new(
+                        ErrorCode::RuntimeFailed,
+                        "Runtime is already running",
+                    ));
+                },
+                Some(_) if !operations::is_idle() => {
+                    return Err(ExecutionError::new(
+                        ErrorCode::RuntimeFailed,
+                        "Default runtime has running operations",
+                    ));
+                },
+                Some(_) => {
+                    let live = handles::handles().live();
+                    if !live.is_empty() {
+                        let kinds = live.keys().copied().collect::<Vec<_>>();
+
+                        return Err(ExecutionError::new(
+                            ErrorCode::RuntimeFailed,
+                            "Default runtime is used by live handles",
+                        )
+                        .with_context("handles", kinds.join(", ")));
+                    }
+                },
+                None => {},
+            }
+
+            let new_runtime = build_runtime(&config)?;
+
+            if let Some(state) = runtime.replace(RuntimeState {
+                runtime: new_runtime,
+                is_implicit: false,
+            }) {
+                state.runtime.shutdown_background();
+            }
+
+            CLOCK.update_offset(config.clock_offset_ms.unwrap_or_default());
+
+            Ok(serde_json::Value::Null)
+        }
+
+        internal_fn(config)
+    })
+}
+
+/// Stops the runtime.
+///
+/// Running operations are given `timeout_ms` to finish, the rest are cancelled.
+/// All handles are released afterwards, so subscriptions are stopped and
+/// handles kept by Dart become invalid.
+#[no_mangle]
+pub unsafe extern "C" fn nt_runtime_shutdown(timeout_ms: c_ulonglong) {
+    catch_panic("nt_runtime_shutdown", || {
+        let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+
+        if !operations::wait_operations(deadline) {
+            operations::abort_operations();
+
+            if !operations::wait_operations(Instant::now() + ABORT_GRACE_PERIOD) {
+                log::warn!("nt_runtime_shutdown: some operations didn't post their results");
+            }
+        }
+
+        let runtime = runtime().take();
+        if let Some(state) = runtime {
+            state
+                .runtime
+                .shutdown_timeout(deadline.saturating_duration_since(Instant::now()));
+        }
+
+        CLOCK.update_offset(0);
+
+        // Dropped outside of the registry lock
+        let values = handles::handles().clear();
+        drop(values);
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::tests::{call_sync, emulated_transport, json_arg};
+
+    #[test]
+    fn default_runtime_is_kept_for_live_handles() {
+        // The runtime is shared by all tests, so it must not be replaced here
+        let _transport = emulated_transport();
+        runtime_handle().unwrap();
+
+        let error = unsafe { call_sync(nt_runtime_init(json_arg(&json!({})))) }.err();
+        assert_eq!(error["code"], "runtimeFailed");
+    }
+}
@@ -0,0 +1,52 @@
+use serde_json::json;
+
+use super::{call_sync, cstr, ffi::*, json_arg, TEST_ADDRESS};
+
+#[test]
+fn validate_and_repack_address() {
+    let valid = unsafe { call_sync(nt_validate_address(cstr(TEST_ADDRESS))) }.ok();
+    assert_eq!(valid, json!(true));
+
+    let invalid = unsafe { call_sync(nt_validate_address(cstr("0:123"))) }.ok();
+    assert_eq!(invalid, json!(false));
+
+    let repacked = unsafe { call_sync(nt_repack_address(cstr(TEST_ADDRESS))) }.ok();
+    assert_eq!(repacked, json!(TEST_ADDRESS));
+
+    let error = unsafe { call_sync(nt_repack_address(cstr("not an address"))) }.err();
+    assert_eq!(error["code"], "invalidAddress");
+    assert_eq!(error["category"], "validation");
+}
+
+#[test]
+fn pack_and_unpack_cell() {
+    let params = json!([
+        { "name": "value", "type": "uint32" },
+        { "name": "flag", "type": "bool" },
+    ]);
+    let tokens = json!({ "value": "42", "flag": true });
+
+    let boc = unsafe { call_sync(nt_pack_into_cell(json_arg(&params), json_arg(&tokens))) }.ok();
+
+    let hash = unsafe { call_sync(nt_get_boc_hash(cstr(boc.as_str().unwrap()))) }.ok();
+    assert_eq!(hash.as_str().unwrap().len(), 64);
+
+    let unpacked = unsafe {
+        call_sync(nt_unpack_from_cell(
+            json_arg(&params),
+            cstr(boc.as_str().unwrap()),
+            0,
+        ))
+    }
+    .ok();
+    assert_eq!(unpacked, tokens);
+}
+
+#[test]
+fn malformed_arguments() {
+    let error = unsafe { call_sync(nt_get_boc_hash(cstr("not base64"))) }.err_code();
+    assert_eq!(error, "invalidInput");
+
+    let error = unsafe { call_sync(nt_pack_into_cell(cstr("{"), cstr("{}"))) }.err();
+    assert_eq!(error["category"], "validation");
+}
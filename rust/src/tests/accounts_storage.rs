@@ -0,0 +1,96 @@
+use serde_json::{json, Value};
+
+use super::{call_async, call_sync, cstr, ffi::*, handle, json_arg, memory_storage};
+
+fn create_accounts_storage() -> Value {
+    let (storage, _) = memory_storage();
+
+    call_async(|port| unsafe { nt_accounts_storage_create(port, handle(&storage)) }).ok()
+}
+
+fn public_key() -> Value {
+    let mnemonic_type = json!({ "type": "legacy" });
+
+    let key = unsafe { call_sync(nt_generate_key(json_arg(&mnemonic_type))) }.ok();
+    let phrase = key["words"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|word| word.as_str().unwrap())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let keypair = unsafe {
+        call_sync(nt_derive_from_phrase(
+            cstr(phrase),
+            json_arg(&mnemonic_type),
+        ))
+    }
+    .ok();
+
+    keypair["public"].clone()
+}
+
+#[test]
+fn add_and_rename_account() {
+    let accounts_storage = create_accounts_storage();
+    let public_key = public_key();
+
+    let account = call_async(|port| unsafe {
+        nt_accounts_storage_add_account(
+            port,
+            handle(&accounts_storage),
+            json_arg(&json!({
+                "name": "Main",
+                "publicKey": public_key,
+                "contract": { "type": "walletV3" },
+                "workchain": 0,
+            })),
+        )
+    })
+    .ok();
+    assert_eq!(account["name"], "Main");
+    assert_eq!(account["tonWallet"]["publicKey"], public_key);
+    assert_eq!(
+        account["tonWallet"]["contract"],
+        json!({ "type": "walletV3" })
+    );
+
+    let address = account["tonWallet"]["address"].as_str().unwrap().to_owned();
+
+    let renamed = call_async(|port| unsafe {
+        nt_accounts_storage_rename_account(
+            port,
+            handle(&accounts_storage),
+            cstr(address.as_str()),
+            cstr("Savings"),
+        )
+    })
+    .ok();
+    assert_eq!(renamed["name"], "Savings");
+
+    let entries =
+        call_async(|port| unsafe { nt_accounts_storage_entries(port, handle(&accounts_storage)) })
+            .ok();
+    assert_eq!(entries, json!([renamed]));
+}
+
+#[test]
+fn invalid_account() {
+    let accounts_storage = create_accounts_storage();
+
+    let error = call_async(|port| unsafe {
+        nt_accounts_storage_add_account(
+            port,
+            handle(&accounts_storage),
+            json_arg(&json!({
+                "name": "Main",
+                "publicKey": "00",
+                "contract": { "type": "walletV3" },
+                "workchain": 0,
+            })),
+        )
+    })
+    .err_code();
+    assert_eq!(error, "invalidInput");
+}
@@ -0,0 +1,324 @@
+//! Entry points used by the tests, declared the same way as in `gen/bindings.h`
+//! so the tests go through the C ABI like any other host.
+
+use std::os::raw::{c_char, c_longlong, c_schar, c_uchar, c_uint, c_ulonglong, c_void};
+
+extern "C" {
+    pub fn nt_store_dart_post_cobject(ptr: *mut c_void);
+
+    pub fn nt_free_cstring(ptr: *mut c_char);
+
+    pub fn nt_cancel_operation(id: c_ulonglong);
+
+    pub fn nt_external_resolve_request_with_string(
+        tx: *mut c_void,
+        ok: *mut c_char,
+        err: *mut c_char,
+    );
+
+    pub fn nt_external_resolve_request_with_optional_string(
+        tx: *mut c_void,
+        ok: *mut c_char,
+        err: *mut c_char,
+    );
+
+    pub fn nt_external_resolve_request_with_unit(tx: *mut c_void, err: *mut c_char);
+
+    pub fn nt_storage_create(
+        get_port: c_longlong,
+        set_port: c_longlong,
+        set_unchecked_port: c_longlong,
+        remove_port: c_longlong,
+        remove_unchecked_port: c_longlong,
+        timeout_ms: c_ulonglong,
+    ) -> *mut c_char;
+
+    pub fn nt_jrpc_connection_create(
+        port: c_longlong,
+        timeout_ms: c_ulonglong,
+        retry_policy: *mut c_char,
+    ) -> *mut c_char;
+
+    pub fn nt_jrpc_connection_create_recording(
+        port: c_longlong,
+        timeout_ms: c_ulonglong,
+        retry_policy: *mut c_char,
+        path: *mut c_char,
+    ) -> *mut c_char;
+
+    pub fn nt_jrpc_connection_create_replay(path: *mut c_char) -> *mut c_char;
+
+    #[cfg(feature = "native_connections")]
+    pub fn nt_jrpc_connection_create_native(config: *mut c_char) -> *mut c_char;
+
+    pub fn nt_jrpc_transport_create(jrpc_connection: *mut c_void) -> *mut c_char;
+
+    #[cfg(feature = "native_connections")]
+    pub fn nt_gql_connection_create_native(is_local: c_uint, config: *mut c_char) -> *mut c_char;
+
+    pub fn nt_gql_transport_create(gql_connection: *mut c_void) -> *mut c_char;
+
+    #[cfg(feature = "native_connections")]
+    pub fn nt_proto_connection_create_native(config: *mut c_char) -> *mut c_char;
+
+    pub fn nt_proto_transport_create(proto_connection: *mut c_void) -> *mut c_char;
+
+    pub fn nt_emulated_transport_create(config: *mut c_char) -> *mut c_char;
+
+    pub fn nt_emulated_transport_add_account(
+        emulated_transport: *mut c_void,
+        account: *mut c_char,
+    ) -> *mut c_char;
+
+    pub fn nt_emulated_transport_latest_block(emulated_transport: *mut c_void) -> *mut c_char;
+
+    pub fn nt_failover_transport_create(transports: *mut c_char) -> *mut c_char;
+
+    pub fn nt_failover_transport_status(failover_transport: *mut c_void) -> *mut c_char;
+
+    pub fn nt_caching_transport_create(transport: *mut c_void, config: *mut c_char) -> *mut c_char;
+
+    pub fn nt_caching_transport_stats(caching_transport: *mut c_void) -> *mut c_char;
+
+    pub fn nt_caching_transport_invalidate(
+        caching_transport: *mut c_void,
+        invalidation: *mut c_char,
+    ) -> *mut c_char;
+
+    pub fn nt_throttled_transport_create(
+        transport: *mut c_void,
+        config: *mut c_char,
+    ) -> *mut c_char;
+
+    pub fn nt_transport_get_type(transport: *mut c_void) -> *mut c_char;
+
+    pub fn nt_transport_get_network_id(
+        result_port: c_longlong,
+        transport: *mut c_void,
+    ) -> c_ulonglong;
+
+    pub fn nt_transport_detect_network(
+        result_port: c_longlong,
+        transport: *mut c_void,
+    ) -> c_ulonglong;
+
+    pub fn nt_networks_get_all() -> *mut c_char;
+
+    pub fn nt_networks_register(descriptor: *mut c_char) -> *mut c_char;
+
+    pub fn nt_transport_get_latest_block_id(
+        result_port: c_longlong,
+        transport: *mut c_void,
+        address: *mut c_char,
+    ) -> c_ulonglong;
+
+    pub fn nt_transport_get_block(
+        result_port: c_longlong,
+        transport: *mut c_void,
+        id: *mut c_char,
+    ) -> c_ulonglong;
+
+    pub fn nt_transport_wait_for_next_block_id(
+        result_port: c_longlong,
+        transport: *mut c_void,
+        current_block_id: *mut c_char,
+        address: *mut c_char,
+        timeout: c_ulonglong,
+    ) -> c_ulonglong;
+
+    pub fn nt_transport_estimate_fees_breakdown(
+        result_port: c_longlong,
+        transport: *mut c_void,
+        unsigned_message: *mut c_void,
+        options: *mut c_char,
+    ) -> c_ulonglong;
+
+    pub fn nt_transport_simulate_transaction_tree_report(
+        result_port: c_longlong,
+        transport: *mut c_void,
+        signed_message: *mut c_char,
+        options: *mut c_char,
+    ) -> c_ulonglong;
+
+    pub fn nt_transport_send_and_confirm(
+        result_port: c_longlong,
+        transport: *mut c_void,
+        signed_message: *mut c_char,
+    ) -> c_ulonglong;
+
+    pub fn nt_transport_stream_accounts_by_code_hash(
+        result_port: c_longlong,
+        batch_port: c_longlong,
+        transport: *mut c_void,
+        code_hash: *mut c_char,
+        options: *mut c_char,
+    ) -> c_ulonglong;
+
+    pub fn nt_transport_stream_transactions(
+        result_port: c_longlong,
+        batch_port: c_longlong,
+        transport: *mut c_void,
+        address: *mut c_char,
+        options: *mut c_char,
+    ) -> c_ulonglong;
+
+    pub fn nt_transport_get_contract_state(
+        result_port: c_longlong,
+        transport: *mut c_void,
+        address: *mut c_char,
+    ) -> c_ulonglong;
+    pub fn nt_transport_get_full_contract_state(
+        result_port: c_longlong,
+        transport: *mut c_void,
+        address: *mut c_char,
+    ) -> c_ulonglong;
+    pub fn nt_transport_get_accounts_by_code_hash(
+        result_port: c_longlong,
+        transport: *mut c_void,
+        code_hash: *mut c_char,
+        limit: c_uchar,
+        continuation: *mut c_char,
+    ) -> c_ulonglong;
+    pub fn nt_transport_get_transactions(
+        result_port: c_longlong,
+        transport: *mut c_void,
+        address: *mut c_char,
+        from_lt: *mut c_char,
+        limit: c_uchar,
+    ) -> c_ulonglong;
+    pub fn nt_transport_get_transaction(
+        result_port: c_longlong,
+        transport: *mut c_void,
+        hash: *mut c_char,
+    ) -> c_ulonglong;
+
+    pub fn nt_generate_key(mnemonic_type: *mut c_char) -> *mut c_char;
+
+    pub fn nt_derive_from_phrase(phrase: *mut c_char, mnemonic_type: *mut c_char) -> *mut c_char;
+
+    pub fn nt_keystore_create(
+        result_port: c_longlong,
+        storage: *mut c_void,
+        connection: *mut c_void,
+        signers: *mut c_char,
+    ) -> c_ulonglong;
+
+    pub fn nt_keystore_entries(result_port: c_longlong, keystore: *mut c_void) -> c_ulonglong;
+
+    pub fn nt_keystore_add_key(
+        result_port: c_longlong,
+        keystore: *mut c_void,
+        signer: *mut c_char,
+        input: *mut c_char,
+    ) -> c_ulonglong;
+
+    pub fn nt_keystore_sign(
+        result_port: c_longlong,
+        keystore: *mut c_void,
+        signer: *mut c_char,
+        data: *mut c_char,
+        input: *mut c_char,
+        signature_id: *mut c_char,
+    ) -> c_ulonglong;
+
+    pub fn nt_ledger_connection_create(
+        get_public_key_port: c_longlong,
+        sign_port: c_longlong,
+        timeout_ms: c_ulonglong,
+    ) -> *mut c_char;
+
+    pub fn nt_accounts_storage_create(result_port: c_longlong, storage: *mut c_void)
+        -> c_ulonglong;
+
+    pub fn nt_accounts_storage_entries(
+        result_port: c_longlong,
+        accounts_storage: *mut c_void,
+    ) -> c_ulonglong;
+
+    pub fn nt_accounts_storage_add_account(
+        result_port: c_longlong,
+        accounts_storage: *mut c_void,
+        new_account: *mut c_char,
+    ) -> c_ulonglong;
+
+    pub fn nt_accounts_storage_rename_account(
+        result_port: c_longlong,
+        accounts_storage: *mut c_void,
+        account: *mut c_char,
+        name: *mut c_char,
+    ) -> c_ulonglong;
+
+    pub fn nt_validate_address(address: *mut c_char) -> *mut c_char;
+
+    pub fn nt_repack_address(address: *mut c_char) -> *mut c_char;
+
+    pub fn nt_get_boc_hash(boc: *mut c_char) -> *mut c_char;
+
+    pub fn nt_pack_into_cell(params: *mut c_char, tokens: *mut c_char) -> *mut c_char;
+
+    pub fn nt_unpack_from_cell(
+        params: *mut c_char,
+        boc: *mut c_char,
+        allow_partial: c_uint,
+    ) -> *mut c_char;
+
+    pub fn nt_ton_wallet_subscribe(
+        result_port: c_longlong,
+        on_message_sent_port: c_longlong,
+        on_message_expired_port: c_longlong,
+        on_state_changed_port: c_longlong,
+        on_transactions_found_port: c_longlong,
+        transport: *mut c_void,
+        workchain: c_schar,
+        public_key: *mut c_char,
+        contract: *mut c_char,
+    ) -> c_ulonglong;
+
+    pub fn nt_ton_wallet_address(result_port: c_longlong, ton_wallet: *mut c_void) -> c_ulonglong;
+
+    pub fn nt_ton_wallet_send(
+        result_port: c_longlong,
+        ton_wallet: *mut c_void,
+        signed_message: *mut c_char,
+    ) -> c_ulonglong;
+    pub fn nt_ton_wallet_prepare_transfer(
+        result_port: c_longlong,
+        ton_wallet: *mut c_void,
+        contract_state: *mut c_char,
+        public_key: *mut c_char,
+        destination: *mut c_char,
+        amount: *mut c_char,
+        bounce: c_uint,
+        body: *mut c_char,
+        expiration: *mut c_char,
+    ) -> c_ulonglong;
+    pub fn nt_unsigned_message_hash(
+        result_port: c_longlong,
+        unsigned_message: *mut c_void,
+    ) -> c_ulonglong;
+    pub fn nt_unsigned_message_sign(
+        result_port: c_longlong,
+        unsigned_message: *mut c_void,
+        signature: *mut c_char,
+    ) -> c_ulonglong;
+
+    pub fn nt_subscription_scheduler_create(
+        transport: *mut c_void,
+        config: *mut c_char,
+    ) -> *mut c_char;
+
+    pub fn nt_subscription_scheduler_add(
+        subscription_scheduler: *mut c_void,
+        subscription: *mut c_void,
+    ) -> *mut c_char;
+
+    pub fn nt_subscription_scheduler_remove(
+        subscription_scheduler: *mut c_void,
+        subscription: *mut c_void,
+    ) -> *mut c_char;
+
+    pub fn nt_subscription_scheduler_run(
+        result_port: c_longlong,
+        subscription_scheduler: *mut c_void,
+    ) -> c_ulonglong;
+}
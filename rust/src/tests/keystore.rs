@@ -0,0 +1,254 @@
+use std::ptr;
+
+use serde_json::{json, Value};
+
+use super::{
+    call_async, call_sync, cstr, ffi::*, handle, json_arg, memory_storage, port, request_tx,
+};
+
+const ENCRYPTED_KEY_SIGNER: &str = "EncryptedKeySigner";
+
+const LEDGER_KEY_SIGNER: &str = "LedgerKeySigner";
+
+const LEDGER_PUBLIC_KEY: &str = "5866666666666666666666666666666666666666666666666666666666666666";
+
+fn create_keystore(signers: &[&str]) -> Value {
+    let (storage, _) = memory_storage();
+
+    call_async(|port| unsafe {
+        nt_keystore_create(
+            port,
+            handle(&storage),
+            ptr::null_mut(),
+            json_arg(&json!(signers)),
+        )
+    })
+    .ok()
+}
+
+fn encrypted_key_input(name: &str) -> Value {
+    let mnemonic_type = json!({ "type": "legacy" });
+
+    let key = unsafe { call_sync(nt_generate_key(json_arg(&mnemonic_type))) }.ok();
+    let words = key["words"].as_array().unwrap();
+    assert_eq!(words.len(), 24);
+
+    let phrase = words
+        .iter()
+        .map(|word| word.as_str().unwrap())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    json!({
+        "name": name,
+        "phrase": phrase,
+        "mnemonicType": mnemonic_type,
+        "password": {
+            "type": "explicit",
+            "data": { "password": "1234", "cache_behavior": { "type": "remove" } },
+        },
+    })
+}
+
+#[test]
+fn add_encrypted_key() {
+    let keystore = create_keystore(&[ENCRYPTED_KEY_SIGNER]);
+
+    let input = encrypted_key_input("Main");
+
+    let entry = call_async(|port| unsafe {
+        nt_keystore_add_key(
+            port,
+            handle(&keystore),
+            cstr(ENCRYPTED_KEY_SIGNER),
+            json_arg(&input),
+        )
+    })
+    .ok();
+    assert_eq!(entry["signer_name"], ENCRYPTED_KEY_SIGNER);
+    assert_eq!(entry["name"], "Main");
+
+    let keypair = unsafe {
+        call_sync(nt_derive_from_phrase(
+            cstr(input["phrase"].as_str().unwrap()),
+            json_arg(&input["mnemonicType"]),
+        ))
+    }
+    .ok();
+    assert_eq!(entry["public_key"], keypair["public"]);
+
+    let entries = call_async(|port| unsafe { nt_keystore_entries(port, handle(&keystore)) }).ok();
+    assert_eq!(entries, json!([entry]));
+}
+
+#[test]
+fn keys_are_persisted() {
+    let (storage, data) = memory_storage();
+
+    let keystore = call_async(|port| unsafe {
+        nt_keystore_create(
+            port,
+            handle(&storage),
+            ptr::null_mut(),
+            json_arg(&json!([ENCRYPTED_KEY_SIGNER])),
+        )
+    })
+    .ok();
+
+    call_async(|port| unsafe {
+        nt_keystore_add_key(
+            port,
+            handle(&keystore),
+            cstr(ENCRYPTED_KEY_SIGNER),
+            json_arg(&encrypted_key_input("Main")),
+        )
+    })
+    .ok();
+    assert!(!data.lock().unwrap().is_empty());
+
+    let reloaded = call_async(|port| unsafe {
+        nt_keystore_create(
+            port,
+            handle(&storage),
+            ptr::null_mut(),
+            json_arg(&json!([ENCRYPTED_KEY_SIGNER])),
+        )
+    })
+    .ok();
+
+    let entries = call_async(|port| unsafe { nt_keystore_entries(port, handle(&reloaded)) }).ok();
+    assert_eq!(entries.as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn unknown_signer() {
+    let keystore = create_keystore(&[ENCRYPTED_KEY_SIGNER]);
+
+    let error = call_async(|port| unsafe {
+        nt_keystore_add_key(port, handle(&keystore), cstr("UnknownSigner"), cstr("{}"))
+    })
+    .err();
+    assert_eq!(error["code"], "unknownSigner");
+    assert_eq!(error["context"]["signer"], "UnknownSigner");
+}
+
+#[test]
+fn ledger_signer_requires_connection() {
+    let (storage, _) = memory_storage();
+
+    let error = call_async(|port| unsafe {
+        nt_keystore_create(
+            port,
+            handle(&storage),
+            ptr::null_mut(),
+            json_arg(&json!(["LedgerKeySigner"])),
+        )
+    })
+    .err_code();
+    assert_eq!(error, "invalidInput");
+}
+
+/// Ledger connection whose sign requests are answered by `sign`, with either a hex signature
+/// or an error reply.
+fn ledger_connection(
+    sign: impl Fn(&Value) -> Result<String, Value> + Send + Sync + 'static,
+) -> Value {
+    let get_public_key_port = port(|request| {
+        let request = serde_json::from_str::<Value>(&request).unwrap();
+
+        unsafe {
+            nt_external_resolve_request_with_string(
+                request_tx(&request),
+                cstr(LEDGER_PUBLIC_KEY),
+                ptr::null_mut(),
+            )
+        };
+    });
+
+    let sign_port = port(move |request| {
+        let request = serde_json::from_str::<Value>(&request).unwrap();
+
+        let (ok, err) = match sign(&request[1]) {
+            Ok(signature) => (cstr(signature), ptr::null_mut()),
+            Err(Value::String(error)) => (ptr::null_mut(), cstr(error)),
+            Err(error) => (ptr::null_mut(), cstr(error.to_string())),
+        };
+
+        unsafe { nt_external_resolve_request_with_string(request_tx(&request), ok, err) };
+    });
+
+    unsafe {
+        call_sync(nt_ledger_connection_create(
+            get_public_key_port,
+            sign_port,
+            0,
+        ))
+    }
+    .ok()
+}
+
+#[test]
+fn sign_with_ledger() {
+    // The signature ID picks the reply of the device
+    let connection = ledger_connection(|request| {
+        if request["account"] != 0 || !request["message"].is_array() {
+            return Err(json!({ "message": "Unexpected request" }));
+        }
+
+        match request["signatureId"].as_i64() {
+            None => Ok("00".repeat(64)),
+            Some(1) => Err(json!({ "kind": "userRejected", "message": "Rejected" })),
+            Some(2) => Err(json!({ "statusCode": 0x5515, "message": "Locked" })),
+            Some(3) => Err(json!({ "statusCode": 0x6e00, "message": "Wrong app" })),
+            Some(_) => Err(Value::from("Disconnected")),
+        }
+    });
+
+    let (storage, _) = memory_storage();
+    let keystore = call_async(|port| unsafe {
+        nt_keystore_create(
+            port,
+            handle(&storage),
+            handle(&connection),
+            json_arg(&json!([LEDGER_KEY_SIGNER])),
+        )
+    })
+    .ok();
+
+    let entry = call_async(|port| unsafe {
+        nt_keystore_add_key(
+            port,
+            handle(&keystore),
+            cstr(LEDGER_KEY_SIGNER),
+            json_arg(&json!({ "accountId": 0 })),
+        )
+    })
+    .ok();
+    assert_eq!(entry["public_key"], LEDGER_PUBLIC_KEY);
+
+    let sign = |signature_id: Option<&str>| {
+        call_async(|port| unsafe {
+            nt_keystore_sign(
+                port,
+                handle(&keystore),
+                cstr(LEDGER_KEY_SIGNER),
+                cstr(base64::encode([1, 2, 3])),
+                json_arg(&json!({ "publicKey": LEDGER_PUBLIC_KEY })),
+                signature_id.map(cstr).unwrap_or(ptr::null_mut()),
+            )
+        })
+    };
+
+    let signature = sign(None).ok();
+    assert_eq!(signature, base64::encode([0; 64]));
+
+    assert_eq!(sign(Some("1")).err_code(), "ledgerUserRejected");
+
+    let error = sign(Some("2")).err();
+    assert_eq!(error["code"], "ledgerDeviceLocked");
+    assert_eq!(error["category"], "keystore");
+    assert_eq!(error["context"]["statusCode"], "0x5515");
+
+    assert_eq!(sign(Some("3")).err_code(), "ledgerWrongApp");
+    assert_eq!(sign(Some("4")).err_code(), "signingFailed");
+}
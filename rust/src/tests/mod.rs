@@ -0,0 +1,564 @@
+//! End-to-end harness for the FFI layer.
+//!
+//! A fake `store_dart_post_cobject` captures messages posted to Dart ports, so `nt_*` entry
+//! points are driven exactly as Dart drives them: arguments are passed as C strings, results
+//! are read from the posted `ExecutionResult` JSON and external requests are answered with
+//! `nt_external_resolve_request_*`.
+
+mod abi;
+mod accounts_storage;
+mod ffi;
+mod keystore;
+mod transports;
+mod wallets;
+
+use std::{
+    collections::HashMap,
+    ffi::{CStr, CString},
+    future::Future,
+    os::raw::{c_char, c_void},
+    ptr,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        mpsc::{channel, Receiver},
+        Arc, Mutex, Once,
+    },
+    time::Duration,
+};
+
+use allo_isolate::ffi::{DartCObject, DartCObjectType, DartPort};
+use ed25519_dalek::Signer;
+use lazy_static::lazy_static;
+use nekoton::transport::{models::RawContractState, Transport};
+use nekoton_utils::Clock;
+use serde_json::{json, Value};
+use ton_block::{
+    Account, AccountState, CurrencyCollection, ExternalInboundMessageHeader, Message,
+    MsgAddressInt, Serializable, StateInit,
+};
+use ton_types::{BuilderData, SliceData};
+
+use self::ffi::*;
+use crate::{runtime::runtime_handle, transport::transport_from_ptr, CLOCK};
+
+const RESULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+type PortHandler = Arc<dyn Fn(String) + Send + Sync>;
+
+static INIT: Once = Once::new();
+
+static NEXT_PORT: AtomicI64 = AtomicI64::new(1);
+
+lazy_static! {
+    static ref PORTS: Mutex<HashMap<DartPort, PortHandler>> = Mutex::new(HashMap::new());
+}
+
+unsafe extern "C" fn post_cobject(port: DartPort, message: *mut DartCObject) -> bool {
+    let message = &*message;
+
+    if !matches!(message.ty, DartCObjectType::DartString) {
+        return false;
+    }
+
+    // The message is released right after this call, so the string is copied
+    let payload = CStr::from_ptr(message.value.as_string)
+        .to_string_lossy()
+        .into_owned();
+
+    let handler = PORTS.lock().unwrap().get(&port).cloned();
+
+    match handler {
+        Some(handler) => {
+            handler(payload);
+            true
+        },
+        None => false,
+    }
+}
+
+pub fn init() {
+    INIT.call_once(|| unsafe { nt_store_dart_post_cobject(post_cobject as *mut c_void) });
+}
+
+/// Registers a port, messages posted to it are passed to `handler` on the posting thread.
+pub fn port(handler: impl Fn(String) + Send + Sync + 'static) -> i64 {
+    init();
+
+    let port = NEXT_PORT.fetch_add(1, Ordering::Relaxed);
+    PORTS.lock().unwrap().insert(port, Arc::new(handler));
+
+    port
+}
+
+pub fn capture_port() -> (i64, Receiver<String>) {
+    let (tx, rx) = channel();
+    let tx = Mutex::new(tx);
+
+    let port = port(move |message| {
+        let _ = tx.lock().unwrap().send(message);
+    });
+
+    (port, rx)
+}
+
+pub fn close_port(port: i64) {
+    PORTS.lock().unwrap().remove(&port);
+}
+
+/// Port that silently drops everything, e.g. for subscription events a test doesn't check.
+pub fn null_port() -> i64 {
+    port(|_| {})
+}
+
+/// Arguments are only read during the call, so leaking them is fine for tests.
+pub fn cstr(value: impl Into<Vec<u8>>) -> *mut c_char {
+    CString::new(value).unwrap().into_raw()
+}
+
+pub fn json_arg(value: &Value) -> *mut c_char {
+    cstr(value.to_string())
+}
+
+/// Handles are passed to Dart as decimal strings.
+pub fn handle(address: &Value) -> *mut c_void {
+    address.as_str().unwrap().parse::<usize>().unwrap() as *mut c_void
+}
+
+/// Handle that was never issued.
+pub fn unknown_handle() -> *mut c_void {
+    (0xdead_usize << (usize::BITS / 2)) as *mut c_void
+}
+
+/// `ExecutionResult` as Dart sees it.
+#[derive(Debug)]
+pub enum CallResult {
+    Ok(Value),
+    Err(Value),
+    Cancelled,
+}
+
+impl CallResult {
+    pub fn ok(self) -> Value {
+        match self {
+            Self::Ok(value) => value,
+            result => panic!("Expected ok, got {:?}", result),
+        }
+    }
+
+    pub fn err(self) -> Value {
+        match self {
+            Self::Err(error) => error,
+            result => panic!("Expected err, got {:?}", result),
+        }
+    }
+
+    pub fn err_code(self) -> String {
+        self.err()["code"].as_str().unwrap().to_owned()
+    }
+}
+
+fn parse_result(json: &str) -> CallResult {
+    let result = serde_json::from_str::<Value>(json).unwrap();
+
+    match result["type"].as_str() {
+        Some("ok") => CallResult::Ok(result["data"].clone()),
+        Some("err") => CallResult::Err(result["data"].clone()),
+        Some("cancelled") => CallResult::Cancelled,
+        _ => panic!("Unexpected result: {}", json),
+    }
+}
+
+/// Reads and frees a string returned by a synchronous call.
+pub unsafe fn call_sync(result: *mut c_char) -> CallResult {
+    let json = CStr::from_ptr(result).to_str().unwrap().to_owned();
+    nt_free_cstring(result);
+
+    parse_result(&json)
+}
+
+/// Waits for the result of an asynchronous call, `f` receives the result port.
+pub fn call_async(f: impl FnOnce(i64) -> u64) -> CallResult {
+    call_async_with(f, |_| {})
+}
+
+/// Same as `call_async`, `started` receives the operation id right after the call.
+pub fn call_async_with(f: impl FnOnce(i64) -> u64, started: impl FnOnce(u64)) -> CallResult {
+    let (port, rx) = capture_port();
+
+    let operation_id = f(port);
+    assert_ne!(operation_id, 0);
+    started(operation_id);
+
+    let message = rx
+        .recv_timeout(RESULT_TIMEOUT)
+        .expect("Result was not posted");
+    close_port(port);
+
+    let result = message.parse::<usize>().unwrap() as *mut c_char;
+    unsafe { call_sync(result) }
+}
+
+fn request_tx(request: &Value) -> *mut c_void {
+    request[0].as_str().unwrap().parse::<usize>().unwrap() as *mut c_void
+}
+
+/// `nt_storage_create` backed by a map, requests are answered on the posting thread.
+pub fn memory_storage() -> (Value, Arc<Mutex<HashMap<String, String>>>) {
+    let data = Arc::new(Mutex::new(HashMap::<String, String>::new()));
+
+    let get_port = port({
+        let data = data.clone();
+        move |request| {
+            let request = serde_json::from_str::<Value>(&request).unwrap();
+            let value = data
+                .lock()
+                .unwrap()
+                .get(request[1].as_str().unwrap())
+                .cloned();
+
+            let value = value.map(cstr).unwrap_or(ptr::null_mut());
+            unsafe {
+                nt_external_resolve_request_with_optional_string(
+                    request_tx(&request),
+                    value,
+                    ptr::null_mut(),
+                )
+            };
+        }
+    });
+
+    let set_port = port({
+        let data = data.clone();
+        move |request| {
+            let request = serde_json::from_str::<Value>(&request).unwrap();
+            data.lock().unwrap().insert(
+                request[1].as_str().unwrap().to_owned(),
+                request[2].as_str().unwrap().to_owned(),
+            );
+
+            unsafe { nt_external_resolve_request_with_unit(request_tx(&request), ptr::null_mut()) };
+        }
+    });
+
+    let set_unchecked_port = port({
+        let data = data.clone();
+        move |request| {
+            let request = serde_json::from_str::<Value>(&request).unwrap();
+            data.lock().unwrap().insert(
+                request[0].as_str().unwrap().to_owned(),
+                request[1].as_str().unwrap().to_owned(),
+            );
+        }
+    });
+
+    let remove_port = port({
+        let data = data.clone();
+        move |request| {
+            let request = serde_json::from_str::<Value>(&request).unwrap();
+            data.lock().unwrap().remove(request[1].as_str().unwrap());
+
+            unsafe { nt_external_resolve_request_with_unit(request_tx(&request), ptr::null_mut()) };
+        }
+    });
+
+    let remove_unchecked_port = port({
+        let data = data.clone();
+        move |request| {
+            let key = serde_json::from_str::<String>(&request).unwrap();
+            data.lock().unwrap().remove(&key);
+        }
+    });
+
+    let storage = unsafe {
+        call_sync(nt_storage_create(
+            get_port,
+            set_port,
+            set_unchecked_port,
+            remove_port,
+            remove_unchecked_port,
+            0,
+        ))
+    }
+    .ok();
+
+    (storage, data)
+}
+
+/// Reply of a fake JRPC endpoint, `None` leaves the request unanswered.
+pub type JrpcReply = Option<Result<Value, String>>;
+
+/// Port of a JRPC connection whose requests are answered by `respond`.
+pub fn jrpc_port(respond: impl Fn(&Value) -> JrpcReply + Send + Sync + 'static) -> i64 {
+    port(move |request| {
+        let request = serde_json::from_str::<Value>(&request).unwrap();
+        let data = serde_json::from_str::<Value>(request[1].as_str().unwrap()).unwrap();
+
+        let (ok, err) = match respond(&data) {
+            Some(Ok(result)) => {
+                let response = json!({ "jsonrpc": "2.0", "id": data["id"], "result": result });
+                (cstr(response.to_string()), ptr::null_mut())
+            },
+            Some(Err(error)) => (ptr::null_mut(), cstr(error)),
+            None => return,
+        };
+
+        unsafe { nt_external_resolve_request_with_string(request_tx(&request), ok, err) };
+    })
+}
+
+/// JRPC transport whose requests are answered by `respond`, returns the transport handle.
+pub fn jrpc_transport(
+    timeout_ms: u64,
+    respond: impl Fn(&Value) -> JrpcReply + Send + Sync + 'static,
+) -> Value {
+    let connection = unsafe {
+        call_sync(nt_jrpc_connection_create(
+            jrpc_port(respond),
+            timeout_ms,
+            ptr::null_mut(),
+        ))
+    }
+    .ok();
+
+    unsafe { call_sync(nt_jrpc_transport_create(handle(&connection))) }.ok()
+}
+
+/// JRPC transport where every request fails.
+pub fn unavailable_transport() -> Value {
+    jrpc_transport(0, |_| Some(Err("Endpoint is unavailable".to_owned())))
+}
+
+pub const TEST_ADDRESS: &str = "0:0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Runs a future on the library runtime, e.g. to read raw data from a transport.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    runtime_handle().unwrap().block_on(future)
+}
+
+/// Transport behind a handle, for data which the entry points don't return as is.
+pub fn raw_transport(transport: &Value) -> Arc<dyn Transport> {
+    unsafe { transport_from_ptr(handle(transport)) }.unwrap()
+}
+
+pub fn emulated_transport() -> Value {
+    unsafe { call_sync(nt_emulated_transport_create(json_arg(&json!({})))) }.ok()
+}
+
+pub fn base64_boc(value: &impl Serializable) -> String {
+    let cell = value.serialize().unwrap();
+    base64::encode(ton_types::serialize_toc(&cell).unwrap())
+}
+
+/// `WalletV3` on an emulated transport, signed with a local key. The account is funded
+/// but not deployed, the first transfer deploys it.
+pub struct TestWallet {
+    pub transport: Value,
+    pub address: String,
+    ton_wallet: Value,
+    keypair: ed25519_dalek::Keypair,
+}
+
+impl TestWallet {
+    /// Every seed gives a separate key, so a separate wallet with the same code.
+    pub fn new(transport: &Value, seed: u8, balance: u64) -> Self {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        let keypair = ed25519_dalek::Keypair { secret, public };
+
+        let ton_wallet = call_async(|port| unsafe {
+            nt_ton_wallet_subscribe(
+                port,
+                null_port(),
+                null_port(),
+                null_port(),
+                null_port(),
+                handle(transport),
+                0,
+                cstr(hex::encode(public.as_bytes())),
+                json_arg(&json!({ "type": "walletV3" })),
+            )
+        })
+        .ok();
+
+        let address =
+            call_async(|port| unsafe { nt_ton_wallet_address(port, handle(&ton_wallet)) }).ok();
+        let address = address.as_str().unwrap().to_owned();
+
+        let account = Account::with_address_and_ballance(
+            &MsgAddressInt::from_str(&address).unwrap(),
+            &CurrencyCollection::with_grams(balance),
+        );
+        unsafe {
+            call_sync(nt_emulated_transport_add_account(
+                handle(transport),
+                cstr(base64_boc(&account)),
+            ))
+        }
+        .ok();
+
+        Self {
+            transport: transport.clone(),
+            address,
+            ton_wallet,
+            keypair,
+        }
+    }
+
+    /// Funds and deploys the wallet.
+    pub fn deployed(transport: &Value, seed: u8, balance: u64) -> Self {
+        let wallet = Self::new(transport, seed, balance);
+        wallet.send(&wallet.transfer(TEST_ADDRESS, 1_000_000, false, None));
+        wallet
+    }
+
+    pub fn public_key(&self) -> String {
+        hex::encode(self.keypair.public.as_bytes())
+    }
+
+    pub fn contract_state(&self) -> Value {
+        call_async(|port| unsafe {
+            nt_transport_get_contract_state(
+                port,
+                handle(&self.transport),
+                cstr(self.address.as_str()),
+            )
+        })
+        .ok()
+    }
+
+    /// Full contract state, `null` if the account doesn't exist.
+    pub fn full_contract_state(&self) -> Value {
+        call_async(|port| unsafe {
+            nt_transport_get_full_contract_state(
+                port,
+                handle(&self.transport),
+                cstr(self.address.as_str()),
+            )
+        })
+        .ok()
+    }
+
+    pub fn balance(&self) -> u128 {
+        let state = self.full_contract_state();
+        state["balance"].as_str().unwrap().parse().unwrap()
+    }
+
+    /// State init of the deployed wallet.
+    pub fn state_init(&self) -> StateInit {
+        let address = MsgAddressInt::from_str(&self.address).unwrap();
+
+        match block_on(raw_transport(&self.transport).get_contract_state(&address)).unwrap() {
+            RawContractState::Exists(contract) => match contract.account.storage.state {
+                AccountState::AccountActive { state_init } => state_init,
+                _ => panic!("Wallet is not deployed"),
+            },
+            RawContractState::NotExists { .. } => panic!("Wallet doesn't exist"),
+        }
+    }
+
+    /// Unsigned message handle with a single transfer, `body` is a comment or a base64
+    /// encoded cell.
+    pub fn prepare_transfer(
+        &self,
+        destination: &str,
+        amount: u64,
+        bounce: bool,
+        body: Option<&str>,
+    ) -> Value {
+        let contract_state = self.contract_state();
+
+        call_async(|port| unsafe {
+            nt_ton_wallet_prepare_transfer(
+                port,
+                handle(&self.ton_wallet),
+                json_arg(&contract_state),
+                cstr(self.public_key()),
+                cstr(destination),
+                cstr(amount.to_string()),
+                bounce as u32,
+                body.map(cstr).unwrap_or(ptr::null_mut()),
+                json_arg(&json!({ "type": "timeout", "data": 60 })),
+            )
+        })
+        .ok()
+    }
+
+    /// Signed message with a single transfer, see `prepare_transfer`.
+    pub fn transfer(
+        &self,
+        destination: &str,
+        amount: u64,
+        bounce: bool,
+        body: Option<&str>,
+    ) -> Value {
+        let unsigned_message = self.prepare_transfer(destination, amount, bounce, body);
+        let unsigned_message = handle(&unsigned_message);
+
+        let hash =
+            call_async(|port| unsafe { nt_unsigned_message_hash(port, unsigned_message) }).ok();
+        let hash = base64::decode(hash.as_str().unwrap()).unwrap();
+        let signature = base64::encode(self.keypair.sign(&hash).to_bytes());
+
+        call_async(|port| unsafe {
+            nt_unsigned_message_sign(port, unsigned_message, cstr(signature))
+        })
+        .ok()
+    }
+
+    /// Signed message which sends `messages` as is, e.g. external outbound messages which
+    /// `transfer` can't make. The wallet must be deployed.
+    pub fn raw_transfer(&self, messages: &[Message]) -> Value {
+        let mut data = SliceData::load_cell(self.state_init().data.unwrap()).unwrap();
+        let seqno = data.get_next_u32().unwrap();
+        let wallet_id = data.get_next_u32().unwrap();
+
+        let expire_at = CLOCK.now_sec_u64() as u32 + 60;
+
+        let payload = || {
+            let mut payload = BuilderData::new();
+            payload.append_u32(wallet_id).unwrap();
+            payload.append_u32(expire_at).unwrap();
+            payload.append_u32(seqno).unwrap();
+            for message in messages {
+                // Pay fees separately and ignore errors
+                payload.append_u8(3).unwrap();
+                payload
+                    .checked_append_reference(message.serialize().unwrap())
+                    .unwrap();
+            }
+            payload
+        };
+
+        let hash = payload().into_cell().unwrap().repr_hash();
+        let signature = self.keypair.sign(hash.as_slice()).to_bytes();
+
+        let mut body = BuilderData::new();
+        body.append_raw(&signature, signature.len() * 8).unwrap();
+        body.append_builder(&payload()).unwrap();
+
+        let mut message = Message::with_ext_in_header(ExternalInboundMessageHeader {
+            dst: MsgAddressInt::from_str(&self.address).unwrap(),
+            ..Default::default()
+        });
+        message.set_body(SliceData::load_cell(body.into_cell().unwrap()).unwrap());
+
+        let cell = message.serialize().unwrap();
+        json!({
+            "hash": cell.repr_hash().to_hex_string(),
+            "expireAt": expire_at,
+            "boc": base64::encode(ton_types::serialize_toc(&cell).unwrap()),
+        })
+    }
+
+    /// Sends the message and waits for its transaction.
+    pub fn send(&self, signed_message: &Value) -> Value {
+        let status = call_async(|port| unsafe {
+            nt_transport_send_and_confirm(port, handle(&self.transport), json_arg(signed_message))
+        })
+        .ok();
+        assert_eq!(status["type"], "confirmed");
+
+        status["data"].clone()
+    }
+}
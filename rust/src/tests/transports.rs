@@ -0,0 +1,1500 @@
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use nekoton_utils::Clock;
+use serde_json::{json, Value};
+use ton_block::{
+    Block, CurrencyCollection, Deserializable, ExtOutMessageHeader, HashmapAugType, InRefValue,
+    InternalMessageHeader, Message, MsgAddressInt,
+};
+use ton_types::{BuilderData, SliceData};
+
+use crate::{
+    external::{
+        recording::ConnectionKind,
+        retry::{is_idempotent, ConnectionError, ErrorClass, RetryPolicy},
+    },
+    CLOCK,
+};
+
+use super::{
+    base64_boc, block_on, call_async, call_async_with, call_sync, capture_port, close_port, cstr,
+    emulated_transport, ffi::*, handle, jrpc_port, jrpc_transport, json_arg, port, raw_transport,
+    request_tx, unavailable_transport, unknown_handle, CallResult, TestWallet, TEST_ADDRESS,
+};
+
+const ONE_EVER: u64 = 1_000_000_000;
+
+/// Default global id of the emulated transport.
+const EMULATED_GLOBAL_ID: i32 = 31337;
+
+/// Account which never exists on the emulated transports of the tests.
+const EMPTY_ADDRESS: &str = "0:1111111111111111111111111111111111111111111111111111111111111111";
+
+/// Amounts and lts are passed as decimal strings.
+fn amount(value: &Value) -> u128 {
+    value.as_str().unwrap().parse().unwrap()
+}
+
+fn get_contract_state(transport: &Value) -> CallResult {
+    let transport = handle(transport);
+
+    call_async(|port| unsafe {
+        nt_transport_get_contract_state(port, transport, cstr(TEST_ADDRESS))
+    })
+}
+
+#[test]
+fn request_contract() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let transport = jrpc_transport(0, {
+        let requests = requests.clone();
+        move |request| {
+            requests.lock().unwrap().push(request.clone());
+            Some(Err("Endpoint is unavailable".to_owned()))
+        }
+    });
+
+    let error = get_contract_state(&transport).err();
+    assert_eq!(error["code"], "requestFailed");
+    assert_eq!(error["category"], "transport");
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0]["method"], "getContractState");
+    assert_eq!(requests[0]["params"]["address"], TEST_ADDRESS);
+}
+
+#[test]
+fn request_timeout() {
+    let transport = jrpc_transport(50, |_| None);
+
+    let error = get_contract_state(&transport).err();
+    assert_eq!(error["code"], "requestTimeout");
+    assert_eq!(error["context"]["timeoutMs"], "50");
+}
+
+#[test]
+fn cancel_pending_request() {
+    let transport = jrpc_transport(0, |_| None);
+    let transport = handle(&transport);
+
+    let result = call_async_with(
+        |port| unsafe { nt_transport_get_contract_state(port, transport, cstr(TEST_ADDRESS)) },
+        |operation_id| unsafe { nt_cancel_operation(operation_id) },
+    );
+    assert!(matches!(result, CallResult::Cancelled));
+}
+
+#[test]
+fn invalid_arguments() {
+    let transport = unavailable_transport();
+
+    let transport_type = unsafe { call_sync(nt_transport_get_type(handle(&transport))) }.ok();
+    assert_eq!(transport_type, "jrpc");
+
+    // Handles of other kinds are rejected instead of being used as transports
+    let connection = unsafe {
+        call_sync(nt_jrpc_connection_create(
+            jrpc_port(|_| None),
+            0,
+            std::ptr::null_mut(),
+        ))
+    }
+    .ok();
+    let error = get_contract_state(&connection).err_code();
+    assert_eq!(error, "invalidHandle");
+
+    let error = call_async(|port| unsafe {
+        nt_transport_get_contract_state(port, unknown_handle(), cstr(TEST_ADDRESS))
+    })
+    .err_code();
+    assert_eq!(error, "invalidHandle");
+
+    let error = call_async(|port| unsafe {
+        nt_transport_get_contract_state(port, handle(&transport), cstr("0:123"))
+    })
+    .err_code();
+    assert_eq!(error, "invalidAddress");
+}
+
+#[test]
+fn emulated_transport() {
+    let transport = unsafe { call_sync(nt_emulated_transport_create(json_arg(&json!({})))) }.ok();
+
+    let state = get_contract_state(&transport).ok();
+    assert_eq!(state["type"], "notExists");
+
+    let block = unsafe { call_sync(nt_emulated_transport_latest_block(handle(&transport))) }.ok();
+    assert!(block.is_null());
+
+    let error = unsafe {
+        call_sync(nt_emulated_transport_add_account(
+            handle(&transport),
+            cstr("not a boc"),
+        ))
+    }
+    .err_code();
+    assert_eq!(error, "invalidBoc");
+
+    let error = unsafe {
+        call_sync(nt_emulated_transport_create(json_arg(
+            &json!({ "config": "not a boc" }),
+        )))
+    }
+    .err_code();
+    assert_eq!(error, "invalidBoc");
+}
+
+#[test]
+fn emulated_transport_execution() {
+    let transport = emulated_transport();
+    let wallet = TestWallet::new(&transport, 3, 10 * ONE_EVER);
+    let receiver = TestWallet::new(&transport, 4, ONE_EVER);
+
+    let latest_block =
+        || unsafe { call_sync(nt_emulated_transport_latest_block(handle(&transport))) }.ok();
+    let get_transactions = |from_lt: Option<&str>, limit: u8| {
+        call_async(|port| unsafe {
+            nt_transport_get_transactions(
+                port,
+                handle(&transport),
+                cstr(wallet.address.as_str()),
+                from_lt.map(cstr).unwrap_or(std::ptr::null_mut()),
+                limit,
+            )
+        })
+        .ok()
+    };
+
+    // The first transfer deploys the wallet
+    let wallet_balance = wallet.balance();
+    let receiver_balance = receiver.balance();
+    let message = wallet.transfer(&receiver.address, ONE_EVER, false, Some("hello"));
+    let transaction = wallet.send(&message);
+
+    assert_eq!(transaction["aborted"], false);
+    assert_eq!(transaction["inMessage"]["dst"], wallet.address);
+    assert_eq!(transaction["outMessages"][0]["dst"], receiver.address);
+    assert_eq!(
+        amount(&transaction["outMessages"][0]["value"]),
+        ONE_EVER as u128
+    );
+    assert_eq!(wallet.full_contract_state()["isDeployed"], true);
+
+    // Forward fees of the sent message are paid on top of the transaction fees
+    let spent = wallet_balance - wallet.balance();
+    let fees = amount(&transaction["totalFees"]);
+    assert!(spent > ONE_EVER as u128 + fees);
+    assert!(spent < ONE_EVER as u128 + fees + 10_000_000);
+
+    let received = receiver.balance() - receiver_balance;
+    assert!(received <= ONE_EVER as u128);
+    assert!(received > ONE_EVER as u128 - 10_000_000);
+
+    // The whole cascade goes into one block in execution order
+    let block = latest_block();
+    assert_eq!(block["seqno"], 1);
+    let hashes = block["transactions"].as_array().unwrap();
+    assert_eq!(hashes.len(), 2);
+    assert_eq!(hashes[0], transaction["id"]["hash"]);
+
+    let incoming = call_async(|port| unsafe {
+        nt_transport_get_transaction(port, handle(&transport), cstr(hashes[1].as_str().unwrap()))
+    })
+    .ok();
+    assert_eq!(incoming["inMessage"]["src"], wallet.address);
+    assert_eq!(incoming["inMessage"]["dst"], receiver.address);
+
+    let (start_lt, end_lt) = (amount(&block["startLt"]), amount(&block["endLt"]));
+    let sent_lt = amount(&transaction["id"]["lt"]);
+    let received_lt = amount(&incoming["id"]["lt"]);
+    assert!(start_lt <= sent_lt && sent_lt < received_lt && received_lt <= end_lt);
+
+    let second = wallet.send(&wallet.transfer(&receiver.address, ONE_EVER, false, None));
+    assert_eq!(latest_block()["seqno"], 2);
+    assert!(amount(&second["id"]["lt"]) > end_lt);
+
+    // Newest first, the continuation points to the older transaction
+    let page = get_transactions(None, 1);
+    assert_eq!(page["transactions"][0]["id"], second["id"]);
+    assert_eq!(page["continuation"], transaction["id"]);
+
+    let page = get_transactions(transaction["id"]["lt"].as_str(), 10);
+    assert_eq!(page["transactions"].as_array().unwrap().len(), 1);
+    assert_eq!(page["transactions"][0]["id"], transaction["id"]);
+    assert!(page["continuation"].is_null());
+
+    let unknown = "0000000000000000000000000000000000000000000000000000000000000000";
+    let missing = call_async(|port| unsafe {
+        nt_transport_get_transaction(port, handle(&transport), cstr(unknown))
+    })
+    .ok();
+    assert!(missing.is_null());
+
+    // Only the deployed wallet has the code
+    let code_hash = wallet
+        .state_init()
+        .code
+        .unwrap()
+        .repr_hash()
+        .to_hex_string();
+    let accounts = call_async(|port| unsafe {
+        nt_transport_get_accounts_by_code_hash(
+            port,
+            handle(&transport),
+            cstr(code_hash.as_str()),
+            10,
+            std::ptr::null_mut(),
+        )
+    })
+    .ok();
+    assert_eq!(accounts["accounts"], json!([wallet.address]));
+    assert!(accounts["continuation"].is_null());
+
+    // A replayed message is rejected by the wallet and nothing is applied
+    let balance = wallet.balance();
+    let error = call_async(|port| unsafe {
+        nt_transport_send_and_confirm(port, handle(&transport), json_arg(&message))
+    })
+    .err_code();
+    assert_eq!(error, "sendFailed");
+    assert_eq!(latest_block()["seqno"], 2);
+    assert_eq!(wallet.balance(), balance);
+    assert_eq!(
+        get_transactions(None, 10)["transactions"]
+            .as_array()
+            .unwrap()
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn record_and_replay() {
+    let path = std::env::temp_dir().join(format!("nekoton-session-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let path = path.to_str().unwrap().to_owned();
+
+    let port = jrpc_port(|_| Some(Err("Endpoint is unavailable".to_owned())));
+    let connection = unsafe {
+        call_sync(nt_jrpc_connection_create_recording(
+            port,
+            0,
+            std::ptr::null_mut(),
+            cstr(path.as_str()),
+        ))
+    }
+    .ok();
+    let transport = unsafe { call_sync(nt_jrpc_transport_create(handle(&connection))) }.ok();
+
+    let recorded = get_contract_state(&transport).err();
+
+    let session = std::fs::read_to_string(&path).unwrap();
+    let line = serde_json::from_str::<Value>(session.lines().next().unwrap()).unwrap();
+    assert_eq!(line["connection"], "jrpc");
+    assert_eq!(line["error"], "Endpoint is unavailable");
+
+    let connection =
+        unsafe { call_sync(nt_jrpc_connection_create_replay(cstr(path.as_str()))) }.ok();
+    let transport = unsafe { call_sync(nt_jrpc_transport_create(handle(&connection))) }.ok();
+
+    // Repeated requests get the last recorded response
+    for _ in 0..2 {
+        let replayed = get_contract_state(&transport).err();
+        assert_eq!(replayed["code"], recorded["code"]);
+        assert_eq!(replayed["message"], recorded["message"]);
+    }
+
+    let error =
+        unsafe { call_sync(nt_jrpc_connection_create_replay(cstr("/nonexistent"))) }.err_code();
+    assert_eq!(error, "invalidInput");
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn failover_transport() {
+    let unavailable = unavailable_transport();
+    let emulated = unsafe { call_sync(nt_emulated_transport_create(json_arg(&json!({})))) }.ok();
+
+    let transport = unsafe {
+        call_sync(nt_failover_transport_create(json_arg(&json!([
+            unavailable,
+            emulated,
+        ]))))
+    }
+    .ok();
+
+    let state = get_contract_state(&transport).ok();
+    assert_eq!(state["type"], "notExists");
+
+    let status = unsafe { call_sync(nt_failover_transport_status(handle(&transport))) }.ok();
+    let error_rate = status[0]["errorRate"].clone();
+    assert!(error_rate.as_f64().unwrap() > 0.0);
+    assert_eq!(status[1]["errorRate"], 0.0);
+
+    // The failed endpoint is now ranked last
+    let state = get_contract_state(&transport).ok();
+    assert_eq!(state["type"], "notExists");
+
+    let status = unsafe { call_sync(nt_failover_transport_status(handle(&transport))) }.ok();
+    assert_eq!(status[0]["errorRate"], error_rate);
+    assert!(status[1]["latencyMs"].is_number());
+
+    let error = unsafe { call_sync(nt_failover_transport_create(json_arg(&json!([])))) }.err_code();
+    assert_eq!(error, "invalidInput");
+}
+
+#[test]
+fn caching_transport() {
+    let emulated = unsafe { call_sync(nt_emulated_transport_create(json_arg(&json!({})))) }.ok();
+
+    let transport = unsafe {
+        call_sync(nt_caching_transport_create(
+            handle(&emulated),
+            json_arg(&json!({ "contractStateTtlMs": 60000 })),
+        ))
+    }
+    .ok();
+    let stats = || unsafe { call_sync(nt_caching_transport_stats(handle(&transport))) }.ok();
+
+    for _ in 0..2 {
+        get_contract_state(&transport).ok();
+    }
+    assert_eq!(stats()["contractStates"], json!({ "hits": 1, "misses": 1 }));
+
+    unsafe {
+        call_sync(nt_caching_transport_invalidate(
+            handle(&transport),
+            json_arg(&json!({ "type": "contractState", "data": TEST_ADDRESS })),
+        ))
+    }
+    .ok();
+
+    get_contract_state(&transport).ok();
+    assert_eq!(stats()["contractStates"], json!({ "hits": 1, "misses": 2 }));
+
+    for _ in 0..2 {
+        let network_id =
+            call_async(|port| unsafe { nt_transport_get_network_id(port, handle(&transport)) })
+                .ok();
+        assert_eq!(network_id, EMULATED_GLOBAL_ID);
+    }
+    assert_eq!(stats()["capabilities"], json!({ "hits": 1, "misses": 1 }));
+
+    let error = unsafe {
+        call_sync(nt_caching_transport_invalidate(
+            handle(&transport),
+            json_arg(&json!({ "type": "unknown" })),
+        ))
+    }
+    .err_code();
+    assert_eq!(error, "invalidInput");
+}
+
+fn throttled_transport(transport: &Value, config: Value) -> CallResult {
+    unsafe {
+        call_sync(nt_throttled_transport_create(
+            handle(transport),
+            json_arg(&config),
+        ))
+    }
+}
+
+#[test]
+fn throttled_transport_coalesces_requests() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let post_port = port({
+        let requests = requests.clone();
+        move |request| requests.lock().unwrap().push(request)
+    });
+    let connection = unsafe {
+        call_sync(nt_jrpc_connection_create(
+            post_port,
+            0,
+            std::ptr::null_mut(),
+        ))
+    }
+    .ok();
+    let jrpc = unsafe { call_sync(nt_jrpc_transport_create(handle(&connection))) }.ok();
+
+    let transport = throttled_transport(&jrpc, json!({})).ok();
+
+    let callers = (0..2)
+        .map(|_| {
+            let transport = transport.clone();
+            thread::spawn(move || {
+                call_async(|port| unsafe {
+                    nt_transport_get_contract_state(port, handle(&transport), cstr(TEST_ADDRESS))
+                })
+                .err()
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Give both calls time to reach the transport
+    thread::sleep(Duration::from_millis(200));
+
+    let request = {
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        serde_json::from_str::<Value>(&requests[0]).unwrap()
+    };
+    unsafe {
+        nt_external_resolve_request_with_string(
+            request_tx(&request),
+            std::ptr::null_mut(),
+            cstr("Endpoint is unavailable"),
+        )
+    };
+
+    for caller in callers {
+        let error = caller.join().unwrap();
+        assert_eq!(error["code"], "requestFailed");
+    }
+}
+
+#[test]
+fn throttled_transport_limits_rate() {
+    let emulated = unsafe { call_sync(nt_emulated_transport_create(json_arg(&json!({})))) }.ok();
+
+    let transport =
+        throttled_transport(&emulated, json!({ "requestsPerSecond": 20.0, "burst": 1 })).ok();
+
+    let started_at = Instant::now();
+    for _ in 0..3 {
+        get_contract_state(&transport).ok();
+    }
+    assert!(started_at.elapsed() >= Duration::from_millis(90));
+
+    // Waits for such rates can't be represented
+    for requests_per_second in [0.0, -1.0, 1e-4, 1e-300, 5e-324] {
+        let config = json!({ "requestsPerSecond": requests_per_second });
+        let error = throttled_transport(&emulated, config).err_code();
+        assert_eq!(error, "invalidInput");
+    }
+    throttled_transport(&emulated, json!({ "requestsPerSecond": 0.001 })).ok();
+}
+
+#[test]
+fn retry_policy_reports_attempts() {
+    let attempts = Arc::new(Mutex::new(0));
+
+    let post_port = jrpc_port({
+        let attempts = attempts.clone();
+        move |_| {
+            *attempts.lock().unwrap() += 1;
+            Some(Err(
+                json!({ "kind": "connect", "message": "Connection refused" }).to_string(),
+            ))
+        }
+    });
+    let policy = json!({ "maxAttempts": 3, "initialBackoffMs": 1 });
+    let connection =
+        unsafe { call_sync(nt_jrpc_connection_create(post_port, 0, json_arg(&policy))) }.ok();
+    let transport = unsafe { call_sync(nt_jrpc_transport_create(handle(&connection))) }.ok();
+
+    let error = get_contract_state(&transport).err();
+    assert_eq!(error["code"], "requestFailed");
+    assert_eq!(error["context"]["attempts"], "3");
+    assert_eq!(*attempts.lock().unwrap(), 3);
+
+    let error = unsafe {
+        call_sync(nt_jrpc_connection_create(
+            post_port,
+            0,
+            json_arg(&json!({ "maxAttempts": 0 })),
+        ))
+    }
+    .err_code();
+    assert_eq!(error, "invalidInput");
+}
+
+#[test]
+fn retry_policy_keeps_sent_messages() {
+    let policy = RetryPolicy::from_json(Some(json!({ "maxAttempts": 3 }).to_string())).unwrap();
+
+    let send_message = json!({ "jsonrpc": "2.0", "id": 1, "method": "sendMessage" }).to_string();
+    let get_state = json!({ "jsonrpc": "2.0", "id": 1, "method": "getContractState" }).to_string();
+
+    assert!(!is_idempotent(ConnectionKind::Jrpc, &send_message));
+    assert!(is_idempotent(ConnectionKind::Jrpc, &get_state));
+
+    let gql =
+        |query: &str| is_idempotent(ConnectionKind::Gql, &json!({ "query": query }).to_string());
+    assert!(!gql("mutation { postRequests }"));
+    assert!(!gql(
+        "# comment\nmutation Send($id: String) { postRequests(id: $id) }"
+    ));
+    assert!(!gql("query A { a } mutation B { b }"));
+    assert!(!gql("subscription { blocks { id } }"));
+    assert!(!gql("{ unbalanced"));
+    assert!(gql("{ accounts { mutationCount } }"));
+    assert!(gql(
+        r#"query Q($f: Filter = { a: "mutation" }) { blocks(filter: $f) { id } } fragment F on Block { id }"#
+    ));
+    assert!(gql(r#"query { a(text: """mutation { b }""") }"#));
+    assert!(!is_idempotent(ConnectionKind::Gql, "{}"));
+    assert!(is_idempotent(
+        ConnectionKind::Gql,
+        "query { accounts { id } }"
+    ));
+
+    // A message may have been delivered before the connection broke
+    assert!(policy.backoff(1, ErrorClass::Network, false).is_none());
+    assert!(policy.backoff(1, ErrorClass::Timeout, false).is_none());
+    assert!(policy.backoff(1, ErrorClass::Connect, false).is_some());
+    assert!(policy.backoff(1, ErrorClass::Network, true).is_some());
+    assert!(policy.backoff(3, ErrorClass::Network, true).is_none());
+}
+
+#[test]
+fn retry_error_classes() {
+    let class =
+        |error: &str| ErrorClass::of(&ConnectionError::from_host(anyhow!(error.to_owned())));
+
+    let error = json!({ "statusCode": 429, "message": "Too many requests" });
+    assert_eq!(class(&error.to_string()), ErrorClass::RateLimited);
+
+    let error = json!({ "kind": "network", "statusCode": 503, "message": "Unavailable" });
+    assert_eq!(class(&error.to_string()), ErrorClass::Server);
+
+    let error = json!({ "statusCode": 404, "message": "Not found" });
+    assert_eq!(class(&error.to_string()), ErrorClass::Other);
+
+    let error = json!({ "kind": "timeout", "message": "Timed out" });
+    assert_eq!(class(&error.to_string()), ErrorClass::Timeout);
+
+    // Numbers in a plain message, e.g. in a hash or an lt, don't make the request retryable
+    assert_eq!(
+        class("Message 429503 was rejected after timeout"),
+        ErrorClass::Other
+    );
+}
+
+/// Logical times of the transactions of a base64 encoded block in the block order.
+fn block_transactions(block: &Value) -> Vec<u64> {
+    let block = Block::construct_from_base64(block.as_str().unwrap()).unwrap();
+    let account_blocks = block.read_extra().unwrap().read_account_blocks().unwrap();
+
+    let mut lts = Vec::new();
+    account_blocks
+        .iterate_objects(|account_block| {
+            account_block
+                .transactions()
+                .iterate_objects(|InRefValue(transaction)| {
+                    lts.push(transaction.lt);
+                    Ok(true)
+                })
+        })
+        .unwrap();
+
+    lts
+}
+
+#[test]
+fn polled_blocks() {
+    let transport = emulated_transport();
+    let wallet = TestWallet::deployed(&transport, 7, 100 * ONE_EVER);
+    let address = wallet.address.as_str();
+    let transport = handle(&transport);
+
+    let get_block = |id: &Value| {
+        call_async(|port| unsafe {
+            nt_transport_get_block(port, transport, cstr(id.as_str().unwrap()))
+        })
+        .ok()
+    };
+    let wait_for_next_block_id = |id: &Value, timeout: u64| {
+        call_async(|port| unsafe {
+            nt_transport_wait_for_next_block_id(
+                port,
+                transport,
+                cstr(id.as_str().unwrap()),
+                cstr(address),
+                timeout,
+            )
+        })
+        .ok()
+    };
+
+    let id = call_async(|port| unsafe {
+        nt_transport_get_latest_block_id(port, transport, cstr(address))
+    })
+    .ok();
+    // The latest block id only marks the starting point
+    assert!(block_transactions(&get_block(&id)).is_empty());
+
+    // Every bounced transfer gives two transactions, more than a single page
+    for _ in 0..9 {
+        wallet.send(&wallet.transfer(EMPTY_ADDRESS, ONE_EVER, true, None));
+    }
+
+    let next_id = wait_for_next_block_id(&id, 1000);
+    let block = get_block(&next_id);
+
+    let page = call_async(|port| unsafe {
+        nt_transport_get_transactions(port, transport, cstr(address), std::ptr::null_mut(), 50)
+    })
+    .ok();
+    let mut expected = page["transactions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|transaction| amount(&transaction["id"]["lt"]) as u64)
+        .collect::<Vec<_>>();
+    // The deploy transaction was there before the starting point
+    expected.pop();
+    expected.reverse();
+
+    let lts = block_transactions(&block);
+    assert_eq!(lts.len(), 18);
+    assert_eq!(lts, expected);
+
+    let info = Block::construct_from_base64(block.as_str().unwrap())
+        .unwrap()
+        .read_info()
+        .unwrap();
+    assert_eq!(info.end_lt(), lts[17] + 1);
+    assert!(info.gen_utime().as_u32() > 0);
+    assert!(info.gen_utime().as_u32() as u64 <= CLOCK.now_sec_u64());
+
+    // Nothing happens to the account, so an empty block is produced on timeout
+    let started_at = Instant::now();
+    let empty_id = wait_for_next_block_id(&next_id, 50);
+    assert!(started_at.elapsed() >= Duration::from_millis(50));
+    assert!(block_transactions(&get_block(&empty_id)).is_empty());
+
+    let error =
+        call_async(|port| unsafe { nt_transport_get_block(port, transport, cstr("unknown")) })
+            .err_code();
+    assert_eq!(error, "invalidInput");
+}
+
+/// Runs a stream to the end, returns the summary and the posted batches.
+fn run_stream(f: impl FnOnce(i64, i64) -> u64) -> (CallResult, Vec<Value>) {
+    let (batch_port, batches) = capture_port();
+
+    // Batches are posted before the result
+    let summary = call_async(|port| f(port, batch_port));
+    close_port(batch_port);
+
+    let batches = batches
+        .try_iter()
+        .map(|batch| serde_json::from_str::<Value>(&batch).unwrap())
+        .collect();
+
+    (summary, batches)
+}
+
+#[test]
+fn stream_transactions() {
+    let transport = emulated_transport();
+    let wallet = TestWallet::deployed(&transport, 8, 10 * ONE_EVER);
+    let address = wallet.address.as_str();
+
+    for _ in 0..5 {
+        wallet.send(&wallet.transfer(EMPTY_ADDRESS, ONE_EVER / 10, false, None));
+    }
+
+    let stream = |transport: &Value, options: Value| {
+        run_stream(|port, batch_port| unsafe {
+            nt_transport_stream_transactions(
+                port,
+                batch_port,
+                handle(transport),
+                cstr(address),
+                json_arg(&options),
+            )
+        })
+    };
+    let lts = |batches: &[Value]| {
+        batches
+            .iter()
+            .flat_map(|batch| batch["transactions"].as_array().unwrap().clone())
+            .map(|transaction| transaction["id"]["lt"].clone())
+            .collect::<Vec<_>>()
+    };
+
+    // Newest first
+    let history = call_async(|port| unsafe {
+        nt_transport_get_transactions(
+            port,
+            handle(&transport),
+            cstr(address),
+            std::ptr::null_mut(),
+            50,
+        )
+    })
+    .ok()["transactions"]
+        .as_array()
+        .unwrap()
+        .clone();
+    assert_eq!(history.len(), 6);
+    let history_lts = history
+        .iter()
+        .map(|transaction| transaction["id"]["lt"].clone())
+        .collect::<Vec<_>>();
+
+    let (summary, batches) = stream(&transport, json!({ "batchSize": 2 }));
+    assert_eq!(summary.ok(), json!({ "count": 6 }));
+    assert_eq!(batches.len(), 3);
+    assert_eq!(lts(&batches), history_lts);
+
+    // The continuation points to the next transaction to resume from
+    let (summary, batches) = stream(&transport, json!({ "batchSize": 3, "limit": 4 }));
+    let summary = summary.ok();
+    assert_eq!(summary["count"], 4);
+    assert_eq!(summary["continuation"], history[4]["id"]);
+    assert_eq!(batches.len(), 2);
+    assert_eq!(lts(&batches), history_lts[..4]);
+
+    let continuation = &summary["continuation"];
+    let (summary, batches) = stream(
+        &transport,
+        json!({ "fromLt": continuation["lt"], "fromHash": continuation["hash"], "batchSize": 3 }),
+    );
+    assert_eq!(summary.ok(), json!({ "count": 2 }));
+    assert_eq!(lts(&batches), history_lts[4..]);
+
+    let (summary, batches) = stream(&transport, json!({ "minLt": history_lts[2] }));
+    assert_eq!(summary.ok(), json!({ "count": 3 }));
+    assert_eq!(lts(&batches), history_lts[..3]);
+
+    let times = history
+        .iter()
+        .map(|transaction| transaction["createdAt"].as_u64().unwrap())
+        .collect::<Vec<_>>();
+    let (min_time, max_time) = (*times.iter().min().unwrap(), *times.iter().max().unwrap());
+
+    let (summary, batches) = stream(
+        &transport,
+        json!({ "minUtime": min_time, "maxUtime": max_time }),
+    );
+    assert_eq!(summary.ok(), json!({ "count": 6 }));
+    assert_eq!(lts(&batches), history_lts);
+
+    let (summary, batches) = stream(&transport, json!({ "maxUtime": min_time - 1 }));
+    assert_eq!(summary.ok(), json!({ "count": 0 }));
+    assert!(batches.is_empty());
+
+    let (summary, batches) = stream(&transport, json!({ "minUtime": max_time + 1 }));
+    assert_eq!(summary.ok(), json!({ "count": 0 }));
+    assert!(batches.is_empty());
+
+    // An endpoint which ignores the starting lt returns the same page again
+    let page = block_on(raw_transport(&transport).get_transactions(
+        &MsgAddressInt::from_str(address).unwrap(),
+        u64::MAX,
+        3,
+    ))
+    .unwrap()
+    .iter()
+    .map(|transaction| base64_boc(&transaction.data))
+    .collect::<Vec<_>>();
+
+    let requests = Arc::new(Mutex::new(0));
+    let replaying = jrpc_transport(0, {
+        let requests = requests.clone();
+        move |_| {
+            *requests.lock().unwrap() += 1;
+            Some(Ok(json!(page)))
+        }
+    });
+
+    let (summary, batches) = stream(&replaying, json!({ "batchSize": 3 }));
+    assert_eq!(summary.ok(), json!({ "count": 3 }));
+    assert_eq!(lts(&batches), history_lts[..3]);
+    assert_eq!(*requests.lock().unwrap(), 2);
+
+    let hash = "0000000000000000000000000000000000000000000000000000000000000000";
+    let (error, _) = stream(
+        &transport,
+        json!({ "fromLt": history_lts[1], "fromHash": hash }),
+    );
+    assert_eq!(error.err_code(), "invalidInput");
+
+    let (error, _) = stream(&transport, json!({ "fromHash": history[1]["id"]["hash"] }));
+    assert_eq!(error.err_code(), "invalidInput");
+
+    let (error, _) = stream(&transport, json!({ "minLt": "not a number" }));
+    assert_eq!(error.err_code(), "invalidInput");
+}
+
+#[test]
+fn stream_accounts_by_code_hash() {
+    let transport = emulated_transport();
+    let wallets = (20..25)
+        .map(|seed| TestWallet::deployed(&transport, seed, 10 * ONE_EVER))
+        .collect::<Vec<_>>();
+    let code_hash = wallets[0]
+        .state_init()
+        .code
+        .unwrap()
+        .repr_hash()
+        .to_hex_string();
+
+    let stream = |code_hash: &str, options: Value| {
+        run_stream(|port, batch_port| unsafe {
+            nt_transport_stream_accounts_by_code_hash(
+                port,
+                batch_port,
+                handle(&transport),
+                cstr(code_hash),
+                json_arg(&options),
+            )
+        })
+    };
+    let addresses = |batches: &[Value]| {
+        batches
+            .iter()
+            .flat_map(|batch| batch["accounts"].as_array().unwrap().clone())
+            .map(|account| account["address"].as_str().unwrap().to_owned())
+            .collect::<Vec<_>>()
+    };
+    let sorted = |mut addresses: Vec<String>| {
+        addresses.sort();
+        addresses
+    };
+
+    let all = sorted(
+        wallets
+            .iter()
+            .map(|wallet| wallet.address.clone())
+            .collect(),
+    );
+
+    // The third page is not full, so the enumeration ends without an empty request
+    let (summary, batches) = stream(&code_hash, json!({ "batchSize": 2 }));
+    assert_eq!(summary.ok(), json!({ "count": 5 }));
+    let sizes = batches
+        .iter()
+        .map(|batch| batch["accounts"].as_array().unwrap().len())
+        .collect::<Vec<_>>();
+    assert_eq!(sizes, [2, 2, 1]);
+    for batch in &batches {
+        let accounts = batch["accounts"].as_array().unwrap();
+        assert_eq!(batch["continuation"], accounts.last().unwrap()["address"]);
+        assert!(accounts
+            .iter()
+            .all(|account| account.get("state").is_none()));
+    }
+    assert_eq!(sorted(addresses(&batches)), all);
+
+    let (summary, batches) = stream(&code_hash, json!({ "batchSize": 2, "limit": 3 }));
+    let summary = summary.ok();
+    assert_eq!(summary["count"], 3);
+    let first = addresses(&batches);
+    assert_eq!(first.len(), 3);
+    assert_eq!(summary["continuation"], first[2]);
+
+    let (summary, batches) = stream(
+        &code_hash,
+        json!({ "batchSize": 2, "continuation": summary["continuation"] }),
+    );
+    assert_eq!(summary.ok(), json!({ "count": 2 }));
+    let rest = addresses(&batches);
+    assert_eq!(sorted([first, rest].concat()), all);
+
+    let (summary, batches) = stream(
+        &code_hash,
+        json!({ "withState": true, "requestsPerSecond": 50.0 }),
+    );
+    assert_eq!(summary.ok(), json!({ "count": 5 }));
+    assert_eq!(batches.len(), 1);
+    for account in batches[0]["accounts"].as_array().unwrap() {
+        let wallet = wallets
+            .iter()
+            .find(|wallet| account["address"] == wallet.address)
+            .unwrap();
+        let state = &account["state"];
+        assert_eq!(state["isDeployed"], true);
+        assert_eq!(amount(&state["balance"]), wallet.balance());
+    }
+
+    let hash = "0000000000000000000000000000000000000000000000000000000000000000";
+    let (summary, batches) = stream(hash, json!({ "withState": true }));
+    assert_eq!(summary.ok(), json!({ "count": 0 }));
+    assert!(batches.is_empty());
+
+    let (error, _) = stream(hash, json!({ "batchSize": 0 }));
+    assert_eq!(error.err_code(), "invalidInput");
+
+    let (error, _) = stream(hash, json!({ "requestsPerSecond": 0.0 }));
+    assert_eq!(error.err_code(), "invalidInput");
+
+    let (error, _) = stream("not a hash", json!({}));
+    assert_eq!(error.err_code(), "invalidHash");
+}
+
+#[test]
+fn send_and_confirm() {
+    let transport = emulated_transport();
+    let wallet = TestWallet::new(&transport, 5, 10 * ONE_EVER);
+
+    let send_and_confirm = |transport: &Value, message: &Value| {
+        call_async(|port| unsafe {
+            nt_transport_send_and_confirm(port, handle(transport), json_arg(message))
+        })
+    };
+
+    let message = wallet.transfer(EMPTY_ADDRESS, ONE_EVER, false, None);
+    let status = send_and_confirm(&transport, &message).ok();
+    assert_eq!(status["type"], "confirmed");
+
+    let transaction = &status["data"];
+    assert_eq!(transaction["inMessage"]["hash"], message["hash"]);
+    assert_eq!(transaction["inMessage"]["dst"], wallet.address);
+
+    let block = unsafe { call_sync(nt_emulated_transport_latest_block(handle(&transport))) }.ok();
+    assert_eq!(block["transactions"][0], transaction["id"]["hash"]);
+
+    // More transactions than one page of the tracker
+    for _ in 0..17 {
+        wallet.send(&wallet.transfer(EMPTY_ADDRESS, ONE_EVER / 10, false, None));
+    }
+
+    let message = wallet.transfer(EMPTY_ADDRESS, ONE_EVER / 10, false, None);
+    let status = send_and_confirm(&transport, &message).ok();
+    assert_eq!(status["type"], "confirmed");
+    assert_eq!(status["data"]["inMessage"]["hash"], message["hash"]);
+
+    // The endpoint takes the message, but it never gets into a block, so every poll
+    // finds only the known transactions
+    let page = block_on(raw_transport(&transport).get_transactions(
+        &MsgAddressInt::from_str(&wallet.address).unwrap(),
+        u64::MAX,
+        16,
+    ))
+    .unwrap()
+    .iter()
+    .map(|transaction| base64_boc(&transaction.data))
+    .collect::<Vec<_>>();
+    assert_eq!(page.len(), 16);
+
+    let methods = Arc::new(Mutex::new(Vec::new()));
+    let silent = jrpc_transport(0, {
+        let methods = methods.clone();
+        move |request| {
+            methods.lock().unwrap().push(request["method"].clone());
+            match request["method"].as_str() {
+                Some("sendMessage") => Some(Ok(Value::Null)),
+                _ => Some(Ok(json!(page))),
+            }
+        }
+    });
+
+    let mut message = wallet.transfer(EMPTY_ADDRESS, ONE_EVER, false, None);
+    message["expireAt"] = json!(1);
+
+    let status = send_and_confirm(&silent, &message).ok();
+    assert_eq!(status, json!({ "type": "expired" }));
+
+    // The latest lt before sending and a single poll
+    let methods = methods.lock().unwrap();
+    assert!(methods.contains(&json!("sendMessage")));
+    let polls = methods
+        .iter()
+        .filter(|method| *method == "getTransactionsList")
+        .count();
+    assert_eq!(polls, 2);
+
+    let error = send_and_confirm(&transport, &json!("not a message")).err_code();
+    assert_eq!(error, "invalidInput");
+}
+
+/// Function and event with fixed ids, so their bodies can be built by hand.
+const PING_ABI: &str = r#"{
+    "ABI version": 2,
+    "version": "2.2",
+    "header": [],
+    "functions": [
+        { "name": "ping", "id": "0x00000101", "inputs": [{ "name": "value", "type": "uint32" }], "outputs": [] }
+    ],
+    "events": [
+        { "name": "Pinged", "id": "0x00000102", "inputs": [{ "name": "value", "type": "uint32" }] }
+    ],
+    "data": [],
+    "fields": []
+}"#;
+
+fn ping_body(id: u32, value: u32) -> SliceData {
+    let mut body = BuilderData::new();
+    body.append_u32(id).unwrap();
+    body.append_u32(value).unwrap();
+    SliceData::load_cell(body.into_cell().unwrap()).unwrap()
+}
+
+#[test]
+fn simulate_transaction_tree_report() {
+    let transport = emulated_transport();
+    let wallet = TestWallet::deployed(&transport, 6, 10 * ONE_EVER);
+
+    // The ping goes to an empty account and is bounced, the event is emitted by the wallet
+    let mut ping = Message::with_int_header(InternalMessageHeader {
+        ihr_disabled: true,
+        bounce: true,
+        dst: MsgAddressInt::from_str(EMPTY_ADDRESS).unwrap(),
+        value: CurrencyCollection::with_grams(ONE_EVER),
+        ..Default::default()
+    });
+    ping.set_body(ping_body(0x101, 7));
+
+    let mut pinged = Message::with_ext_out_header(ExtOutMessageHeader::default());
+    pinged.set_body(ping_body(0x102, 7));
+
+    let message = wallet.raw_transfer(&[ping, pinged]);
+
+    let report = |options: Value| {
+        call_async(|port| unsafe {
+            nt_transport_simulate_transaction_tree_report(
+                port,
+                handle(&transport),
+                json_arg(&message),
+                json_arg(&options),
+            )
+        })
+    };
+    let delta = |value: &Value| value.as_str().unwrap().parse::<i128>().unwrap();
+
+    let full = report(json!({ "abis": [PING_ABI] })).ok();
+    assert_eq!(full["isTruncated"], false);
+
+    let transactions = full["transactions"].as_array().unwrap();
+    let tree = transactions
+        .iter()
+        .map(|item| (item["depth"].clone(), item["account"].clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tree,
+        [
+            (json!(0), json!(wallet.address)),
+            (json!(1), json!(EMPTY_ADDRESS)),
+            (json!(2), json!(wallet.address)),
+        ]
+    );
+
+    assert_eq!(
+        transactions[0]["events"],
+        json!([{ "event": "Pinged", "data": { "value": "7" } }])
+    );
+    assert_eq!(
+        transactions[1]["decodedInput"],
+        json!({ "method": "ping", "input": { "value": "7" } })
+    );
+    assert!(transactions[1]["events"].as_array().unwrap().is_empty());
+
+    let bounced = full["bouncedMessages"].as_array().unwrap();
+    assert_eq!(bounced.len(), 1);
+    assert_eq!(bounced[0]["src"], EMPTY_ADDRESS);
+    assert_eq!(bounced[0]["dst"], wallet.address);
+    assert!(amount(&bounced[0]["value"]) < ONE_EVER as u128);
+    assert_eq!(
+        bounced[0]["transactionHash"],
+        transactions[1]["transaction"]["id"]["hash"]
+    );
+
+    // Deltas of the accounts sum up the deltas of their transactions
+    let deltas = full["balanceDeltas"].as_array().unwrap();
+    assert_eq!(deltas.len(), 2);
+    assert_eq!(deltas[0]["address"], wallet.address);
+    assert_eq!(
+        delta(&deltas[0]["delta"]),
+        delta(&transactions[0]["balanceDelta"]) + delta(&transactions[2]["balanceDelta"])
+    );
+    assert!(delta(&deltas[0]["delta"]) < 0);
+    assert!(delta(&deltas[0]["delta"]) > -(ONE_EVER as i128));
+    assert_eq!(deltas[1]["address"], EMPTY_ADDRESS);
+    assert_eq!(
+        delta(&deltas[1]["delta"]),
+        delta(&transactions[1]["balanceDelta"])
+    );
+
+    let total_fees = transactions
+        .iter()
+        .map(|item| amount(&item["transaction"]["totalFees"]))
+        .sum::<u128>();
+    assert_eq!(amount(&full["totalFees"]), total_fees);
+
+    let shallow = report(json!({ "abis": [PING_ABI], "maxDepth": 1 })).ok();
+    assert_eq!(shallow["isTruncated"], true);
+    assert_eq!(shallow["transactions"].as_array().unwrap().len(), 2);
+    assert_eq!(shallow["bouncedMessages"].as_array().unwrap().len(), 1);
+
+    // Transactions which are too deep don't use up the limit
+    let shallow = report(json!({ "maxDepth": 1, "maxTransactions": 2 })).ok();
+    assert_eq!(shallow["isTruncated"], true);
+    assert_eq!(shallow["transactions"].as_array().unwrap().len(), 2);
+
+    let limited = report(json!({ "maxTransactions": 1 })).ok();
+    assert_eq!(limited["isTruncated"], true);
+    assert_eq!(limited["transactions"].as_array().unwrap().len(), 1);
+    assert!(limited["transactions"][0]["events"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+
+    let error = call_async(|port| unsafe {
+        nt_transport_simulate_transaction_tree_report(
+            port,
+            handle(&transport),
+            cstr("not a message"),
+            json_arg(&json!({ "maxDepth": 2 })),
+        )
+    })
+    .err_code();
+    assert_eq!(error, "invalidInput");
+}
+
+#[test]
+fn transaction_tree_report_depth() {
+    let emulated = emulated_transport();
+    let wallet = TestWallet::deployed(&emulated, 7, 10 * ONE_EVER);
+
+    // Every ping is bounced by its own empty account
+    let pings = ["2", "3", "4"]
+        .iter()
+        .map(|digit| {
+            let dst = format!("0:{}", digit.repeat(64));
+            let mut ping = Message::with_int_header(InternalMessageHeader {
+                ihr_disabled: true,
+                bounce: true,
+                dst: MsgAddressInt::from_str(&dst).unwrap(),
+                value: CurrencyCollection::with_grams(ONE_EVER),
+                ..Default::default()
+            });
+            ping.set_body(ping_body(0x101, 7));
+            ping
+        })
+        .collect::<Vec<_>>();
+    let message = wallet.raw_transfer(&pings);
+
+    let report = |options: Value| {
+        let transport = unsafe {
+            call_sync(nt_caching_transport_create(
+                handle(&emulated),
+                json_arg(&json!({ "contractStateTtlMs": 60000 })),
+            ))
+        }
+        .ok();
+
+        let report = call_async(|port| unsafe {
+            nt_transport_simulate_transaction_tree_report(
+                port,
+                handle(&transport),
+                json_arg(&message),
+                json_arg(&options),
+            )
+        })
+        .ok();
+        let stats = unsafe { call_sync(nt_caching_transport_stats(handle(&transport))) }.ok();
+
+        (report, stats["contractStates"]["misses"].as_u64().unwrap())
+    };
+
+    let (full, fetched) = report(json!({}));
+    assert_eq!(full["isTruncated"], false);
+    assert_eq!(full["transactions"].as_array().unwrap().len(), 7);
+    assert_eq!(fetched, 4);
+
+    // The simulation stops at the first ping, the other accounts are never fetched
+    let (shallow, fetched) = report(json!({ "maxDepth": 0 }));
+    assert_eq!(shallow["isTruncated"], true);
+    assert_eq!(shallow["transactions"].as_array().unwrap().len(), 1);
+    assert!(fetched < 4);
+}
+
+#[test]
+fn estimate_fees_breakdown() {
+    let transport = emulated_transport();
+    let wallet = TestWallet::deployed(&transport, 1, 10 * ONE_EVER);
+    let balance_before = wallet.balance();
+
+    // The empty account can't take the value, so it's bounced back to the wallet.
+    // No signature is needed for the estimate
+    let unsigned_message = wallet.prepare_transfer(EMPTY_ADDRESS, ONE_EVER, true, None);
+
+    let estimate_of = |options: Value| {
+        call_async(|port| unsafe {
+            nt_transport_estimate_fees_breakdown(
+                port,
+                handle(&transport),
+                handle(&unsigned_message),
+                json_arg(&options),
+            )
+        })
+    };
+
+    let estimate = estimate_of(json!({})).ok();
+    assert_eq!(estimate["isTruncated"], false);
+
+    let fees = &estimate["fees"];
+    assert_eq!(fees["account"], wallet.address);
+    assert!(amount(&fees["gasUsed"]) > 0);
+    assert!(amount(&fees["gasFees"]) > 0);
+    assert!(amount(&fees["forwardFees"]) > 0);
+    assert!(amount(&fees["totalFees"]) >= amount(&fees["gasFees"]));
+    assert_eq!(amount(&fees["outgoingValue"]), ONE_EVER as u128);
+    assert_eq!(fees["children"].as_array().unwrap().len(), 1);
+
+    let bounced = &fees["children"][0];
+    assert_eq!(bounced["account"], EMPTY_ADDRESS);
+    assert_eq!(amount(&bounced["gasUsed"]), 0);
+    assert!(amount(&bounced["outgoingValue"]) > 0);
+    assert!(amount(&bounced["outgoingValue"]) < ONE_EVER as u128);
+    assert_eq!(bounced["children"].as_array().unwrap().len(), 1);
+
+    let returned = &bounced["children"][0];
+    assert_eq!(returned["account"], wallet.address);
+    assert!(returned["children"].as_array().unwrap().is_empty());
+
+    // Only the fees are lost, the value comes back
+    let balance_after = amount(&estimate["balanceAfter"]);
+    assert!(balance_after < balance_before);
+    assert!(balance_after > balance_before - ONE_EVER as u128);
+
+    let limited = estimate_of(json!({ "maxTransactions": 2 })).ok();
+    assert_eq!(limited["isTruncated"], true);
+    assert_eq!(limited["fees"]["children"][0]["children"], json!([]));
+
+    let error = estimate_of(json!({ "maxTransactions": 0 })).err_code();
+    assert_eq!(error, "invalidInput");
+
+    // Storage fees for the time between the estimate and the send may differ slightly
+    wallet.send(&wallet.transfer(EMPTY_ADDRESS, ONE_EVER, true, None));
+    assert!(wallet.balance().abs_diff(balance_after) < 1_000_000);
+
+    let error = estimate_of(json!({ "maxTransactions": "all" })).err_code();
+    assert_eq!(error, "invalidInput");
+}
+
+#[test]
+fn detect_network() {
+    let detect = |global_id: i32| {
+        let transport = unsafe {
+            call_sync(nt_emulated_transport_create(json_arg(
+                &json!({ "globalId": global_id }),
+            )))
+        }
+        .ok();
+
+        call_async(|port| unsafe { nt_transport_detect_network(port, handle(&transport)) }).ok()
+    };
+
+    // The emulator is not mistaken for a real network by default
+    let emulated = unsafe { call_sync(nt_emulated_transport_create(json_arg(&json!({})))) }.ok();
+    let network =
+        call_async(|port| unsafe { nt_transport_detect_network(port, handle(&emulated)) }).ok();
+    assert_eq!(network["status"], "unknown");
+    assert_eq!(network["globalId"], EMULATED_GLOBAL_ID);
+
+    let network = detect(42);
+    assert_eq!(network["status"], "builtin");
+    assert_eq!(network["descriptor"]["networkGroup"], "mainnet");
+    assert_eq!(network["descriptor"]["currency"]["decimals"], 9);
+
+    let network = detect(7001);
+    assert_eq!(network["status"], "unknown");
+    assert_eq!(network["globalId"], 7001);
+    assert!(network["descriptor"].is_null());
+
+    let descriptor = json!({
+        "name": "Local",
+        "globalId": 7002,
+        "networkGroup": "local",
+        "supportsSignatureId": false,
+        "currency": { "symbol": "EVER", "decimals": 9 },
+    });
+    unsafe { call_sync(nt_networks_register(json_arg(&descriptor))) }.ok();
+
+    let network = detect(7002);
+    assert_eq!(network["status"], "custom");
+    assert_eq!(network["descriptor"]["name"], "Local");
+
+    let networks = unsafe { call_sync(nt_networks_get_all()) }.ok();
+    assert!(networks
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|network| network["globalId"] == 7002));
+
+    let error = unsafe {
+        call_sync(nt_networks_register(json_arg(
+            &json!({ "name": "Broken", "globalId": 7003 }),
+        )))
+    }
+    .err_code();
+    assert_eq!(error, "invalidInput");
+}
+
+/// Local HTTP server which answers every request with `status` and the body made by
+/// `respond` from the request body, received requests are passed to the returned channel.
+#[cfg(feature = "native_connections")]
+fn http_stand_in(
+    status: &'static str,
+    respond: impl Fn(&[u8]) -> Vec<u8> + Send + 'static,
+) -> (String, std::sync::mpsc::Receiver<Vec<u8>>) {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut request = Vec::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse::<usize>().unwrap();
+                }
+                request.extend_from_slice(line.as_bytes());
+                if line == "\r\n" {
+                    break;
+                }
+            }
+
+            let mut content = vec![0; content_length];
+            reader.read_exact(&mut content).unwrap();
+
+            let body = respond(&content);
+            let head = format!(
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                status,
+                body.len(),
+            );
+            stream.write_all(head.as_bytes()).unwrap();
+            stream.write_all(&body).unwrap();
+
+            request.extend_from_slice(&content);
+            if tx.send(request).is_err() {
+                break;
+            }
+        }
+    });
+
+    (url, rx)
+}
+
+#[cfg(feature = "native_connections")]
+#[test]
+fn native_connection() {
+    use crate::external::native::NativeClient;
+
+    let native_transport = |config: Value| {
+        let connection =
+            unsafe { call_sync(nt_jrpc_connection_create_native(json_arg(&config))) }.ok();
+        unsafe { call_sync(nt_jrpc_transport_create(handle(&connection))) }.ok()
+    };
+    let get_transactions = |transport: &Value| {
+        call_async(|port| unsafe {
+            nt_transport_get_transactions(
+                port,
+                handle(transport),
+                cstr(TEST_ADDRESS),
+                std::ptr::null_mut(),
+                10,
+            )
+        })
+    };
+    let text = |request: Vec<u8>| String::from_utf8_lossy(&request).to_lowercase();
+
+    let (url, requests) = http_stand_in("503 Service Unavailable", |_| Vec::new());
+    let transport = native_transport(json!({
+        "endpoint": url,
+        "headers": { "x-api-key": "secret" },
+        "retryPolicy": { "maxAttempts": 2, "initialBackoffMs": 10 },
+    }));
+
+    let error = get_contract_state(&transport).err();
+    assert_eq!(error["context"]["attempts"], "2");
+
+    let request = text(requests.recv().unwrap());
+    assert!(request.starts_with("post / "));
+    assert!(request.contains("x-api-key: secret"));
+    assert!(request.contains("content-type: application/json"));
+    assert!(request.contains("getcontractstate"));
+    assert!(requests.recv().is_ok());
+
+    let (url, requests) = http_stand_in("200 OK", |_| {
+        json!({ "jsonrpc": "2.0", "id": 1, "result": [] })
+            .to_string()
+            .into_bytes()
+    });
+    let transport = native_transport(json!({ "endpoint": url }));
+
+    let page = get_transactions(&transport).ok();
+    assert_eq!(page["transactions"], json!([]));
+    assert!(text(requests.recv().unwrap()).contains("gettransactionslist"));
+
+    let (url, requests) = http_stand_in("200 OK", |_| {
+        json!({ "data": { "transactions": [] } })
+            .to_string()
+            .into_bytes()
+    });
+    let connection = unsafe {
+        call_sync(nt_gql_connection_create_native(
+            0,
+            json_arg(&json!({ "endpoint": url })),
+        ))
+    }
+    .ok();
+    let transport = unsafe { call_sync(nt_gql_transport_create(handle(&connection))) }.ok();
+
+    let page = get_transactions(&transport).ok();
+    assert_eq!(page["transactions"], json!([]));
+    let request = text(requests.recv().unwrap());
+    assert!(request.contains("content-type: application/json"));
+    assert!(request.contains("transactions"));
+
+    // Proto bodies are posted as raw bytes, not as the base64 the connection works with
+    let (url, requests) = http_stand_in("503 Service Unavailable", |_| Vec::new());
+    let connection = unsafe {
+        call_sync(nt_proto_connection_create_native(json_arg(
+            &json!({ "endpoint": url, "retryPolicy": { "maxAttempts": 1 } }),
+        )))
+    }
+    .ok();
+    let transport = unsafe { call_sync(nt_proto_transport_create(handle(&connection))) }.ok();
+
+    get_contract_state(&transport).err();
+    let request = requests.recv().unwrap();
+    assert!(text(request.clone()).contains("content-type: application/x-protobuf"));
+    assert!(request.windows(32).any(|bytes| bytes == [0; 32]));
+
+    let (url, requests) = http_stand_in("200 OK", |body| body.to_vec());
+    let config = serde_json::from_value(json!({ "endpoint": url })).unwrap();
+    let (client, _) = NativeClient::new(ConnectionKind::Proto, config).unwrap();
+
+    let data = [0x0a, 0x00, 0xff, 0x80];
+    let response = block_on(client.post(&base64::encode(data))).unwrap();
+    assert_eq!(response, base64::encode(data));
+    assert!(requests.recv().unwrap().ends_with(&data));
+
+    let error = block_on(client.post("not base64")).unwrap_err();
+    assert!(requests.try_recv().is_err(), "{}", error);
+
+    let error = unsafe {
+        call_sync(nt_jrpc_connection_create_native(json_arg(
+            &json!({ "endpoint": "not a url" }),
+        )))
+    }
+    .err_code();
+    assert_eq!(error, "invalidInput");
+}
@@ -0,0 +1,214 @@
+use std::{thread, time::Duration};
+
+use serde_json::{json, Value};
+
+use super::{
+    call_async, call_async_with, call_sync, capture_port, cstr, emulated_transport, ffi::*, handle,
+    json_arg, null_port, unavailable_transport, unknown_handle, CallResult, TestWallet,
+    TEST_ADDRESS,
+};
+
+const PUBLIC_KEY: &str = "5866666666666666666666666666666666666666666666666666666666666666";
+
+#[test]
+fn subscribe_without_network() {
+    let transport = unavailable_transport();
+
+    let error = call_async(|port| unsafe {
+        nt_ton_wallet_subscribe(
+            port,
+            null_port(),
+            null_port(),
+            null_port(),
+            null_port(),
+            handle(&transport),
+            0,
+            cstr(PUBLIC_KEY),
+            json_arg(&json!({ "type": "walletV3" })),
+        )
+    })
+    .err();
+    assert_eq!(error["code"], "subscriptionFailed");
+    assert_eq!(error["category"], "transport");
+}
+
+#[test]
+fn invalid_subscription_arguments() {
+    let transport = unavailable_transport();
+
+    let subscribe = |public_key: &str, contract: &str| {
+        call_async(|port| unsafe {
+            nt_ton_wallet_subscribe(
+                port,
+                null_port(),
+                null_port(),
+                null_port(),
+                null_port(),
+                handle(&transport),
+                0,
+                cstr(public_key),
+                cstr(contract),
+            )
+        })
+        .err_code()
+    };
+
+    assert_eq!(
+        subscribe("00", "{\"type\":\"walletV3\"}"),
+        "invalidPublicKey"
+    );
+    assert_eq!(
+        subscribe(PUBLIC_KEY, "{\"type\":\"unknown\"}"),
+        "invalidInput"
+    );
+}
+
+#[test]
+fn released_wallet_handle() {
+    let error = call_async(|port| unsafe { nt_ton_wallet_address(port, unknown_handle()) }).err();
+    assert_eq!(error["code"], "invalidHandle");
+    assert_eq!(error["context"]["kind"], "ton_wallet");
+}
+
+#[test]
+fn subscription_scheduler() {
+    let transport = emulated_transport();
+    let wallet = TestWallet::new(&transport, 40, 10_000_000_000);
+    let transport = handle(&transport);
+
+    let (states_port, states) = capture_port();
+    let ton_wallet = call_async(|port| unsafe {
+        nt_ton_wallet_subscribe(
+            port,
+            null_port(),
+            null_port(),
+            states_port,
+            null_port(),
+            transport,
+            0,
+            cstr(wallet.public_key()),
+            json_arg(&json!({ "type": "walletV3" })),
+        )
+    })
+    .ok();
+    let ton_wallet = handle(&ton_wallet);
+
+    // Without the switch to reliable polling the wallet would be polled again in a minute
+    let scheduler = unsafe {
+        call_sync(nt_subscription_scheduler_create(
+            transport,
+            json_arg(&json!({ "manualIntervalMs": 60_000, "reliableIntervalMs": 20 })),
+        ))
+    }
+    .ok();
+    let scheduler = handle(&scheduler);
+
+    unsafe { call_sync(nt_subscription_scheduler_add(scheduler, ton_wallet)) }.ok();
+
+    // Only subscriptions can be scheduled
+    let error = unsafe { call_sync(nt_subscription_scheduler_add(scheduler, transport)) }.err();
+    assert_eq!(error["code"], "invalidHandle");
+    assert_eq!(error["context"]["actual"], "emulated_transport");
+
+    let mut state = None;
+    let result = call_async_with(
+        |port| unsafe { nt_subscription_scheduler_run(port, scheduler) },
+        |operation_id| {
+            // The first poll finds no changes
+            thread::sleep(Duration::from_millis(100));
+            assert!(states.try_recv().is_err());
+
+            let message = wallet.transfer(TEST_ADDRESS, 1_000_000_000, false, None);
+            call_async(|port| unsafe { nt_ton_wallet_send(port, ton_wallet, json_arg(&message)) })
+                .ok();
+
+            state = states.recv_timeout(Duration::from_secs(5)).ok();
+            unsafe { nt_cancel_operation(operation_id) }
+        },
+    );
+    assert!(matches!(result, CallResult::Cancelled));
+
+    // Refreshes post the new state to the port of the subscription
+    let state = serde_json::from_str::<Value>(&state.expect("State was not posted")).unwrap();
+    let balance = state["balance"].as_str().unwrap().parse::<u64>().unwrap();
+    assert!(balance < 9_000_000_000);
+    assert_eq!(state["isDeployed"], true);
+
+    unsafe { call_sync(nt_subscription_scheduler_remove(scheduler, ton_wallet)) }.ok();
+
+    let error = unsafe {
+        call_sync(nt_subscription_scheduler_create(
+            transport,
+            json_arg(&json!({ "concurrency": 0 })),
+        ))
+    }
+    .err_code();
+    assert_eq!(error, "invalidInput");
+}
+
+#[test]
+fn subscription_scheduler_shares_state_checks() {
+    let emulated = emulated_transport();
+    let wallet = TestWallet::new(&emulated, 41, 10_000_000_000);
+
+    // Every state check reaches the emulator
+    let transport = unsafe {
+        call_sync(nt_caching_transport_create(
+            handle(&emulated),
+            json_arg(&json!({ "contractStateTtlMs": 0 })),
+        ))
+    }
+    .ok();
+    let transport = handle(&transport);
+
+    // Both subscriptions follow the same account
+    let subscribe = || {
+        let ton_wallet = call_async(|port| unsafe {
+            nt_ton_wallet_subscribe(
+                port,
+                null_port(),
+                null_port(),
+                null_port(),
+                null_port(),
+                transport,
+                0,
+                cstr(wallet.public_key()),
+                json_arg(&json!({ "type": "walletV3" })),
+            )
+        })
+        .ok();
+        handle(&ton_wallet)
+    };
+    let ton_wallets = [subscribe(), subscribe()];
+
+    let scheduler = unsafe {
+        call_sync(nt_subscription_scheduler_create(
+            transport,
+            json_arg(&json!({ "manualIntervalMs": 60_000 })),
+        ))
+    }
+    .ok();
+    let scheduler = handle(&scheduler);
+
+    for ton_wallet in ton_wallets {
+        unsafe { call_sync(nt_subscription_scheduler_add(scheduler, ton_wallet)) }.ok();
+    }
+
+    let misses = || {
+        let stats = unsafe { call_sync(nt_caching_transport_stats(transport)) }.ok();
+        stats["contractStates"]["misses"].as_u64().unwrap()
+    };
+    let misses_before = misses();
+
+    let result = call_async_with(
+        |port| unsafe { nt_subscription_scheduler_run(port, scheduler) },
+        |operation_id| {
+            thread::sleep(Duration::from_millis(200));
+            unsafe { nt_cancel_operation(operation_id) }
+        },
+    );
+    assert!(matches!(result, CallResult::Cancelled));
+
+    // A single check for both subscriptions and no refreshes, since nothing changed
+    assert_eq!(misses() - misses_before, 1);
+}
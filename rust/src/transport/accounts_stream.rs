@@ -0,0 +1,255 @@
+use std::{
+    collections::HashSet,
+    os::raw::{c_char, c_longlong, c_ulonglong, c_void},
+    sync::Arc,
+};
+
+use nekoton::transport::Transport;
+use nekoton_utils::{serde_address, serde_optional_address};
+use serde::{Deserialize, Serialize};
+use ton_block::MsgAddressInt;
+use ton_types::UInt256;
+
+use crate::{
+    errors::{ErrorCode, ExecutionError},
+    execute_async,
+    host::HostPort,
+    parse_address, parse_hash,
+    transport::{
+        make_full_contract_state,
+        models::FullContractState,
+        throttled_transport::{ThrottledTransport, ThrottledTransportConfig},
+        transport_from_ptr,
+    },
+    HandleError, ToStringFromPtr,
+};
+
+const DEFAULT_BATCH_SIZE: u8 = 50;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountsStreamOptions {
+    /// Address of the last received account, the enumeration starts after it.
+    pub continuation: Option<String>,
+    /// The enumeration stops once this many accounts were posted.
+    pub limit: Option<usize>,
+    /// Accounts per request, at most 255.
+    pub batch_size: Option<u8>,
+    /// Fetch the state of every account.
+    #[serde(default)]
+    pub with_state: bool,
+    /// Limits requests of this enumeration, not limited if not set.
+    pub requests_per_second: Option<f64>,
+    pub burst: Option<u32>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeHashAccount {
+    #[serde(with = "serde_address")]
+    pub address: MsgAddressInt,
+    /// Set only with `with_state`, null if the account was deleted meanwhile.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<Option<FullContractState>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeHashAccountsBatch {
+    pub accounts: Vec<CodeHashAccount>,
+    /// Address to resume the enumeration after this batch.
+    #[serde(with = "serde_address")]
+    pub continuation: MsgAddressInt,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountsStreamSummary {
+    pub count: usize,
+    /// Set when the enumeration stopped on the limit.
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        with = "serde_optional_address"
+    )]
+    pub continuation: Option<MsgAddressInt>,
+}
+
+struct AccountsStream {
+    transport: Arc<dyn Transport>,
+    code_hash: UInt256,
+    batch_port: HostPort,
+    continuation: Option<MsgAddressInt>,
+    limit: usize,
+    batch_size: u8,
+    with_state: bool,
+}
+
+impl AccountsStream {
+    fn new(
+        transport: Arc<dyn Transport>,
+        code_hash: UInt256,
+        batch_port: HostPort,
+        options: AccountsStreamOptions,
+    ) -> Result<Self, ExecutionError> {
+        let batch_size = options.batch_size.unwrap_or(DEFAULT_BATCH_SIZE);
+        if batch_size == 0 {
+            return Err(ExecutionError::new(
+                ErrorCode::InvalidInput,
+                "Batch size must not be zero",
+            ));
+        }
+
+        let transport: Arc<dyn Transport> = match options.requests_per_second {
+            Some(requests_per_second) => Arc::new(ThrottledTransport::new(
+                transport,
+                ThrottledTransportConfig {
+                    coalesce: false,
+                    requests_per_second: Some(requests_per_second),
+                    burst: options.burst,
+                    max_queued: None,
+                },
+            )?),
+            None => transport,
+        };
+
+        Ok(Self {
+            transport,
+            code_hash,
+            batch_port,
+            continuation: options
+                .continuation
+                .as_deref()
+                .map(parse_address)
+                .transpose()?,
+            limit: options.limit.unwrap_or(usize::MAX),
+            batch_size,
+            with_state: options.with_state,
+        })
+    }
+
+    /// Accounts are requested page by page, a page which is not full is the last one.
+    async fn run(self) -> Result<AccountsStreamSummary, ExecutionError> {
+        let mut seen = HashSet::new();
+        let mut count = 0;
+
+        let mut continuation = self.continuation.clone();
+
+        while count < self.limit {
+            let page = self
+                .transport
+                .get_accounts_by_code_hash(&self.code_hash, self.batch_size, &continuation)
+                .await
+                .handle_error_as(ErrorCode::RequestFailed)?;
+
+            let is_last_page = page.len() < self.batch_size as usize;
+
+            let mut batch = Vec::new();
+            for address in page {
+                continuation = Some(address.clone());
+
+                if !seen.insert(address.clone()) {
+                    continue;
+                }
+
+                let state = match self.with_state {
+                    true => Some(self.get_state(&address).await?),
+                    false => None,
+                };
+
+                batch.push(CodeHashAccount { address, state });
+                count += 1;
+
+                if count == self.limit {
+                    break;
+                }
+            }
+
+            if let Some(last) = batch.last() {
+                let batch = CodeHashAccountsBatch {
+                    continuation: last.address.clone(),
+                    accounts: batch,
+                };
+
+                if !self.post_batch(&batch)? {
+                    log::warn!("Accounts stream port is closed");
+                    break;
+                }
+            } else if !is_last_page {
+                // The endpoint returned the same page again
+                break;
+            }
+
+            if is_last_page {
+                break;
+            }
+        }
+
+        let continuation = if count == self.limit {
+            continuation
+        } else {
+            None
+        };
+
+        Ok(AccountsStreamSummary {
+            count,
+            continuation,
+        })
+    }
+
+    async fn get_state(
+        &self,
+        address: &MsgAddressInt,
+    ) -> Result<Option<FullContractState>, ExecutionError> {
+        let raw_contract_state = self
+            .transport
+            .get_contract_state(address)
+            .await
+            .handle_error_as(ErrorCode::RequestFailed)?;
+
+        make_full_contract_state(raw_contract_state)
+    }
+
+    fn post_batch(&self, batch: &CodeHashAccountsBatch) -> Result<bool, ExecutionError> {
+        let batch = serde_json::to_string(batch).handle_error()?;
+
+        Ok(self.batch_port.post(batch))
+    }
+}
+
+/// Enumerates all accounts with the code hash and posts them in `CodeHashAccountsBatch`es
+/// to `batch_port`. The result is an `AccountsStreamSummary`, the enumeration stops when
+/// the operation is cancelled.
+#[no_mangle]
+pub unsafe extern "C" fn nt_transport_stream_accounts_by_code_hash(
+    result_port: c_longlong,
+    batch_port: c_longlong,
+    transport: *mut c_void,
+    code_hash: *mut c_char,
+    options: *mut c_char,
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let transport = transport_from_ptr(transport)?;
+        let code_hash = code_hash.to_string_from_ptr();
+        let options = options.to_string_from_ptr();
+
+        let stream = {
+            let code_hash = parse_hash(&code_hash)?;
+            let options = serde_json::from_str::<AccountsStreamOptions>(&options)
+                .handle_error_as(ErrorCode::InvalidInput)?;
+
+            AccountsStream::new(transport, code_hash, HostPort::event(batch_port), options)?
+        };
+
+        Ok(async move {
+            async fn internal_fn(
+                stream: AccountsStream,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let summary = stream.run().await?;
+
+                serde_json::to_value(summary).handle_error()
+            }
+
+            internal_fn(stream).await
+        })
+    })
+}
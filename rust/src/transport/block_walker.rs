@@ -0,0 +1,361 @@
+use std::{
+    os::raw::{c_char, c_longlong, c_ulonglong, c_void},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+use nekoton::transport::{gql::GqlTransport, models::RawTransaction, Transport};
+use nekoton_utils::{serde_address, serde_u64, serde_uint256, Clock};
+use serde::{Deserialize, Serialize};
+use ton_block::{
+    Block, BlockExtra, BlockInfo, MerkleUpdate, MsgAddressInt, Serializable, ShardAccountBlocks,
+    UnixTime32, ValueFlow,
+};
+use ton_types::UInt256;
+
+use crate::{
+    clock,
+    errors::{ErrorCode, ExecutionError},
+    execute_async, parse_address,
+    transport::{
+        gql_transport::gql_transport_from_native_ptr, models::TransportType, transport_from_ptr,
+        transport_type_from_ptr,
+    },
+    HandleError, ToStringFromPtr, CLOCK,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+const TRANSACTIONS_BATCH_SIZE: u8 = 16;
+
+/// Block-level access to any transport.
+///
+/// GQL endpoints serve real blocks. Other transports are polled for new transactions of
+/// the address, which are packed into synthetic blocks with just the account block of that
+/// address and the generation time, which is all that `handle_block` of subscriptions reads.
+pub enum BlockWalker {
+    Gql(Arc<GqlTransport>),
+    Polling(Arc<dyn Transport>),
+}
+
+impl BlockWalker {
+    pub unsafe fn from_ptr(transport: *mut c_void) -> Result<Self, ExecutionError> {
+        match transport_type_from_ptr(transport)? {
+            TransportType::Gql => gql_transport_from_native_ptr(transport).map(Self::Gql),
+            _ => transport_from_ptr(transport).map(Self::Polling),
+        }
+    }
+
+    pub fn transport(&self) -> &dyn Transport {
+        match self {
+            Self::Gql(transport) => transport.as_ref(),
+            Self::Polling(transport) => transport.as_ref(),
+        }
+    }
+
+    pub async fn get_latest_block_id(&self, address: &MsgAddressInt) -> Result<String> {
+        match self {
+            Self::Gql(transport) => Ok(transport.get_latest_block(address).await?.id),
+            Self::Polling(transport) => {
+                let latest = latest_transaction(transport.as_ref(), address).await?;
+
+                PolledBlockId::new(address.clone(), latest.clone(), latest).encode()
+            },
+        }
+    }
+
+    pub async fn get_block(&self, id: &str) -> Result<Block> {
+        match self {
+            Self::Gql(transport) => transport.get_block(id).await,
+            Self::Polling(transport) => {
+                let id = PolledBlockId::decode(id)?;
+                let transactions = new_transactions(transport.as_ref(), &id).await?;
+
+                make_block(&id, &transactions)
+            },
+        }
+    }
+
+    /// Polling transports return an empty block when nothing happened until the timeout,
+    /// so that subscriptions still see the time passing, e.g. to expire sent messages.
+    pub async fn wait_for_next_block_id(
+        &self,
+        current: &str,
+        address: &MsgAddressInt,
+        timeout: Duration,
+    ) -> Result<String> {
+        match self {
+            Self::Gql(transport) => {
+                transport
+                    .wait_for_next_block(current, address, timeout)
+                    .await
+            },
+            Self::Polling(transport) => {
+                let current = PolledBlockId::decode(current)?;
+                if &current.address != address {
+                    bail!(ExecutionError::new(
+                        ErrorCode::InvalidInput,
+                        "Block id belongs to a different address",
+                    ));
+                }
+
+                let started_at = Instant::now();
+                loop {
+                    let latest = latest_transaction(transport.as_ref(), address).await?;
+                    if latest != current.latest {
+                        return PolledBlockId::new(address.clone(), current.latest, latest)
+                            .encode();
+                    }
+
+                    let elapsed = started_at.elapsed();
+                    if elapsed >= timeout {
+                        return PolledBlockId::new(address.clone(), latest.clone(), latest)
+                            .encode();
+                    }
+
+                    tokio::time::sleep(POLL_INTERVAL.min(timeout - elapsed)).await;
+                }
+            },
+        }
+    }
+}
+
+/// Range of transactions of a single account, serialized as base64 JSON.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PolledBlockId {
+    #[serde(with = "serde_address")]
+    address: MsgAddressInt,
+    /// Latest transaction of the previous block, excluded.
+    previous: Option<PolledTransaction>,
+    /// Latest transaction of this block, included.
+    latest: Option<PolledTransaction>,
+    gen_utime: u32,
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PolledTransaction {
+    #[serde(with = "serde_u64")]
+    lt: u64,
+    #[serde(with = "serde_uint256")]
+    hash: UInt256,
+}
+
+impl PolledBlockId {
+    fn new(
+        address: MsgAddressInt,
+        previous: Option<PolledTransaction>,
+        latest: Option<PolledTransaction>,
+    ) -> Self {
+        Self {
+            address,
+            previous,
+            latest,
+            gen_utime: clock!().now_sec_u64() as u32,
+        }
+    }
+
+    fn encode(&self) -> Result<String> {
+        Ok(base64::encode(serde_json::to_vec(self)?))
+    }
+
+    fn decode(id: &str) -> Result<Self> {
+        base64::decode(id)
+            .ok()
+            .and_then(|id| serde_json::from_slice::<Self>(&id).ok())
+            .ok_or_else(|| {
+                ExecutionError::new(ErrorCode::InvalidInput, "Invalid block id")
+                    .with_context("id", id)
+                    .into()
+            })
+    }
+}
+
+async fn latest_transaction(
+    transport: &dyn Transport,
+    address: &MsgAddressInt,
+) -> Result<Option<PolledTransaction>> {
+    let transactions = transport.get_transactions(address, u64::MAX, 1).await?;
+
+    Ok(transactions.first().map(|transaction| PolledTransaction {
+        lt: transaction.data.lt,
+        hash: transaction.hash,
+    }))
+}
+
+/// Transactions of the block in the ascending order.
+async fn new_transactions(
+    transport: &dyn Transport,
+    id: &PolledBlockId,
+) -> Result<Vec<RawTransaction>> {
+    let latest = match &id.latest {
+        Some(latest) if id.previous.as_ref() != Some(latest) => latest,
+        _ => return Ok(Vec::new()),
+    };
+    let previous_lt = id
+        .previous
+        .as_ref()
+        .map(|previous| previous.lt)
+        .unwrap_or_default();
+
+    let mut transactions = Vec::new();
+
+    let mut from_lt = latest.lt;
+    while from_lt > previous_lt {
+        let batch = transport
+            .get_transactions(&id.address, from_lt, TRANSACTIONS_BATCH_SIZE)
+            .await?;
+        let is_last_batch = batch.len() < TRANSACTIONS_BATCH_SIZE as usize;
+
+        for transaction in batch {
+            if transaction.data.lt <= previous_lt {
+                break;
+            }
+            from_lt = transaction.data.prev_trans_lt;
+            transactions.push(transaction);
+        }
+
+        if is_last_batch {
+            break;
+        }
+    }
+
+    match transactions.first() {
+        Some(transaction) if transaction.hash == latest.hash => {},
+        _ => bail!("Account transactions don't match the block id"),
+    }
+
+    transactions.reverse();
+    Ok(transactions)
+}
+
+fn make_block(id: &PolledBlockId, transactions: &[RawTransaction]) -> Result<Block> {
+    let mut account_blocks = ShardAccountBlocks::default();
+    for transaction in transactions {
+        let cell = transaction.data.serialize()?;
+        account_blocks.add_serialized_transaction(&transaction.data, &cell)?;
+    }
+
+    let mut extra = BlockExtra::default();
+    extra.write_account_blocks(&account_blocks)?;
+
+    let mut info = BlockInfo::default();
+    info.set_gen_utime(UnixTime32::new(id.gen_utime));
+    if let Some(latest) = &id.latest {
+        info.set_end_lt(latest.lt + 1);
+    }
+
+    Block::with_params(
+        0,
+        info,
+        ValueFlow::default(),
+        MerkleUpdate::default(),
+        extra,
+    )
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_transport_get_latest_block_id(
+    result_port: c_longlong,
+    transport: *mut c_void,
+    address: *mut c_char,
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let block_walker = BlockWalker::from_ptr(transport)?;
+
+        let address = address.to_string_from_ptr();
+
+        Ok(async move {
+            async fn internal_fn(
+                block_walker: BlockWalker,
+                address: String,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let address = parse_address(&address)?;
+
+                let latest_block_id = block_walker
+                    .get_latest_block_id(&address)
+                    .await
+                    .handle_error_as(ErrorCode::RequestFailed)?;
+
+                serde_json::to_value(latest_block_id).handle_error()
+            }
+
+            internal_fn(block_walker, address).await
+        })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_transport_get_block(
+    result_port: c_longlong,
+    transport: *mut c_void,
+    id: *mut c_char,
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let block_walker = BlockWalker::from_ptr(transport)?;
+
+        let id = id.to_string_from_ptr();
+
+        Ok(async move {
+            async fn internal_fn(
+                block_walker: BlockWalker,
+                id: String,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let block = block_walker
+                    .get_block(&id)
+                    .await
+                    .handle_error_as(ErrorCode::RequestFailed)?;
+
+                let block = block
+                    .serialize()
+                    .and_then(|cell| ton_types::serialize_toc(&cell))
+                    .map(base64::encode)
+                    .handle_error()?;
+
+                serde_json::to_value(block).handle_error()
+            }
+
+            internal_fn(block_walker, id).await
+        })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_transport_wait_for_next_block_id(
+    result_port: c_longlong,
+    transport: *mut c_void,
+    current_block_id: *mut c_char,
+    address: *mut c_char,
+    timeout: c_ulonglong,
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let block_walker = BlockWalker::from_ptr(transport)?;
+
+        let current_block_id = current_block_id.to_string_from_ptr();
+        let address = address.to_string_from_ptr();
+
+        Ok(async move {
+            async fn internal_fn(
+                block_walker: BlockWalker,
+                current_block_id: String,
+                address: String,
+                timeout: u64,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let address = parse_address(&address)?;
+
+                let timeout = Duration::from_millis(timeout);
+
+                let next_block_id = block_walker
+                    .wait_for_next_block_id(&current_block_id, &address, timeout)
+                    .await
+                    .handle_error_as(ErrorCode::RequestFailed)?;
+
+                serde_json::to_value(next_block_id).handle_error()
+            }
+
+            internal_fn(block_walker, current_block_id, address, timeout).await
+        })
+    })
+}
@@ -0,0 +1,548 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    os::raw::{c_char, c_void},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use nekoton::{
+    core::models::NetworkCapabilities,
+    transport::{
+        models::{PollContractState, RawContractState, RawTransaction},
+        Transport, TransportInfo,
+    },
+};
+use nekoton_utils::Clock;
+use serde::{Deserialize, Serialize};
+use ton_block::{Message, MsgAddressInt};
+use ton_executor::BlockchainConfig;
+use ton_types::{Cell, UInt256};
+
+use crate::{
+    errors::{ErrorCode, ExecutionError},
+    execute_sync, ffi_handle, parse_address,
+    transport::transport_from_ptr,
+    HandleError, ToPtrAddress, ToStringFromPtr,
+};
+
+const DEFAULT_CONTRACT_STATE_TTL_MS: u64 = 5_000;
+
+const DEFAULT_CONTRACT_STATE_CAPACITY: usize = 256;
+
+const DEFAULT_TRANSACTION_CAPACITY: usize = 1024;
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CachingTransportConfig {
+    pub contract_state_ttl_ms: Option<u64>,
+    pub contract_state_capacity: Option<usize>,
+    pub transaction_capacity: Option<usize>,
+}
+
+/// Transport decorator which caches reads of the wrapped transport.
+///
+/// * contract states are kept for a short time and are reused by `poll_contract_state`
+///   while the last transaction lt is the same;
+/// * transactions never change, so they are only evicted when the cache is full;
+/// * blockchain config and capabilities are fetched once per session.
+///
+/// A sent message invalidates the state of its destination.
+pub struct CachingTransport {
+    transport: Arc<dyn Transport>,
+    contract_state_ttl: Duration,
+    contract_states: Mutex<LruCache<MsgAddressInt, CachedContractState>>,
+    transactions: Mutex<LruCache<UInt256, RawTransaction>>,
+    dst_transactions: Mutex<LruCache<UInt256, UInt256>>,
+    config: Mutex<Option<BlockchainConfig>>,
+    capabilities: Mutex<Option<NetworkCapabilities>>,
+    stats: CacheStats,
+}
+
+struct CachedContractState {
+    state: RawContractState,
+    fetched_at: Instant,
+}
+
+impl CachedContractState {
+    fn last_trans_lt(&self) -> Option<u64> {
+        match &self.state {
+            RawContractState::Exists(contract) => Some(contract.last_transaction_id.lt()),
+            RawContractState::NotExists { .. } => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CacheKind {
+    ContractStates,
+    Transactions,
+    Config,
+    Capabilities,
+}
+
+/// Part of the cache to drop.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+pub enum CacheInvalidation {
+    All,
+    Kind(CacheKind),
+    ContractState(String),
+}
+
+#[derive(Default)]
+struct CacheStats {
+    contract_states: HitCounter,
+    transactions: HitCounter,
+    config: HitCounter,
+    capabilities: HitCounter,
+}
+
+#[derive(Default)]
+struct HitCounter {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl HitCounter {
+    fn record(&self, hit: bool) {
+        let counter = if hit { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> HitCounterSnapshot {
+        HitCounterSnapshot {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HitCounterSnapshot {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStatsSnapshot {
+    pub contract_states: HitCounterSnapshot,
+    pub transactions: HitCounterSnapshot,
+    pub config: HitCounterSnapshot,
+    pub capabilities: HitCounterSnapshot,
+}
+
+/// Size-bounded map which evicts the least recently used entry.
+///
+/// Eviction scans all entries, which is fine for the few hundred items kept here.
+struct LruCache<K, V> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<K, (u64, V)>,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::with_capacity(capacity),
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        self.tick += 1;
+        let tick = self.tick;
+
+        self.entries.get_mut(key).map(|(used_at, value)| {
+            *used_at = tick;
+            &*value
+        })
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (used_at, _))| *used_at)
+                .map(|(key, _)| key.clone());
+
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+
+        self.tick += 1;
+        self.entries.insert(key, (self.tick, value));
+    }
+
+    fn remove(&mut self, key: &K) {
+        self.entries.remove(key);
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl CachingTransport {
+    pub fn new(transport: Arc<dyn Transport>, config: CachingTransportConfig) -> Self {
+        let contract_state_capacity = config
+            .contract_state_capacity
+            .unwrap_or(DEFAULT_CONTRACT_STATE_CAPACITY);
+        let transaction_capacity = config
+            .transaction_capacity
+            .unwrap_or(DEFAULT_TRANSACTION_CAPACITY);
+
+        Self {
+            transport,
+            contract_state_ttl: Duration::from_millis(
+                config
+                    .contract_state_ttl_ms
+                    .unwrap_or(DEFAULT_CONTRACT_STATE_TTL_MS),
+            ),
+            contract_states: Mutex::new(LruCache::new(contract_state_capacity)),
+            transactions: Mutex::new(LruCache::new(transaction_capacity)),
+            dst_transactions: Mutex::new(LruCache::new(transaction_capacity)),
+            config: Default::default(),
+            capabilities: Default::default(),
+            stats: Default::default(),
+        }
+    }
+
+    pub fn stats(&self) -> CacheStatsSnapshot {
+        CacheStatsSnapshot {
+            contract_states: self.stats.contract_states.snapshot(),
+            transactions: self.stats.transactions.snapshot(),
+            config: self.stats.config.snapshot(),
+            capabilities: self.stats.capabilities.snapshot(),
+        }
+    }
+
+    pub fn invalidate_contract_state(&self, address: &MsgAddressInt) {
+        lock(&self.contract_states).remove(address);
+    }
+
+    pub fn invalidate(&self, kind: CacheKind) {
+        match kind {
+            CacheKind::ContractStates => lock(&self.contract_states).clear(),
+            CacheKind::Transactions => {
+                lock(&self.transactions).clear();
+                lock(&self.dst_transactions).clear();
+            },
+            CacheKind::Config => *lock(&self.config) = None,
+            CacheKind::Capabilities => *lock(&self.capabilities) = None,
+        }
+    }
+
+    pub fn invalidate_all(&self) {
+        for kind in [
+            CacheKind::ContractStates,
+            CacheKind::Transactions,
+            CacheKind::Config,
+            CacheKind::Capabilities,
+        ] {
+            self.invalidate(kind);
+        }
+    }
+
+    /// Fresh cached state of the address.
+    fn cached_contract_state(&self, address: &MsgAddressInt) -> Option<RawContractState> {
+        let mut contract_states = lock(&self.contract_states);
+
+        let cached = contract_states.get(address)?;
+        if cached.fetched_at.elapsed() > self.contract_state_ttl {
+            contract_states.remove(address);
+            return None;
+        }
+
+        Some(cached.state.clone())
+    }
+
+    fn store_contract_state(&self, address: &MsgAddressInt, state: &RawContractState) {
+        lock(&self.contract_states).insert(
+            address.clone(),
+            CachedContractState {
+                state: state.clone(),
+                fetched_at: Instant::now(),
+            },
+        );
+    }
+
+    fn store_transaction(&self, transaction: &RawTransaction) {
+        lock(&self.transactions).insert(transaction.hash, transaction.clone());
+    }
+}
+
+#[async_trait]
+impl Transport for CachingTransport {
+    fn info(&self) -> TransportInfo {
+        self.transport.info()
+    }
+
+    async fn send_message(&self, message: &Message) -> Result<()> {
+        if let Some(dst) = message.dst() {
+            self.invalidate_contract_state(&dst);
+        }
+
+        self.transport.send_message(message).await
+    }
+
+    async fn get_contract_state(&self, address: &MsgAddressInt) -> Result<RawContractState> {
+        let cached = self.cached_contract_state(address);
+        self.stats.contract_states.record(cached.is_some());
+
+        if let Some(state) = cached {
+            return Ok(state);
+        }
+
+        let state = self.transport.get_contract_state(address).await?;
+        self.store_contract_state(address, &state);
+
+        Ok(state)
+    }
+
+    async fn get_library_cell(&self, hash: &UInt256) -> Result<Option<Cell>> {
+        self.transport.get_library_cell(hash).await
+    }
+
+    async fn poll_contract_state(
+        &self,
+        address: &MsgAddressInt,
+        last_trans_lt: u64,
+    ) -> Result<PollContractState> {
+        let unchanged = {
+            let mut contract_states = lock(&self.contract_states);
+            match contract_states.get(address) {
+                Some(cached)
+                    if cached.fetched_at.elapsed() <= self.contract_state_ttl
+                        && cached.last_trans_lt() == Some(last_trans_lt) =>
+                {
+                    match &cached.state {
+                        RawContractState::Exists(contract) => Some(contract.timings),
+                        RawContractState::NotExists { .. } => None,
+                    }
+                },
+                _ => None,
+            }
+        };
+        self.stats.contract_states.record(unchanged.is_some());
+
+        if let Some(timings) = unchanged {
+            return Ok(PollContractState::Unchanged { timings });
+        }
+
+        let state = self
+            .transport
+            .poll_contract_state(address, last_trans_lt)
+            .await?;
+
+        match &state {
+            PollContractState::Exists(contract) => {
+                self.store_contract_state(address, &RawContractState::Exists(contract.clone()))
+            },
+            PollContractState::NotExists { timings } => self
+                .store_contract_state(address, &RawContractState::NotExists { timings: *timings }),
+            PollContractState::Unchanged { .. } => {},
+        }
+
+        Ok(state)
+    }
+
+    async fn get_accounts_by_code_hash(
+        &self,
+        code_hash: &UInt256,
+        limit: u8,
+        continuation: &Option<MsgAddressInt>,
+    ) -> Result<Vec<MsgAddressInt>> {
+        self.transport
+            .get_accounts_by_code_hash(code_hash, limit, continuation)
+            .await
+    }
+
+    async fn get_transactions(
+        &self,
+        address: &MsgAddressInt,
+        from_lt: u64,
+        count: u8,
+    ) -> Result<Vec<RawTransaction>> {
+        let transactions = self
+            .transport
+            .get_transactions(address, from_lt, count)
+            .await?;
+
+        for transaction in &transactions {
+            self.store_transaction(transaction);
+        }
+
+        Ok(transactions)
+    }
+
+    async fn get_transaction(&self, id: &UInt256) -> Result<Option<RawTransaction>> {
+        let cached = lock(&self.transactions).get(id).cloned();
+        self.stats.transactions.record(cached.is_some());
+
+        if cached.is_some() {
+            return Ok(cached);
+        }
+
+        let transaction = self.transport.get_transaction(id).await?;
+        if let Some(transaction) = &transaction {
+            self.store_transaction(transaction);
+        }
+
+        Ok(transaction)
+    }
+
+    async fn get_dst_transaction(&self, message_hash: &UInt256) -> Result<Option<RawTransaction>> {
+        let hash = lock(&self.dst_transactions).get(message_hash).copied();
+        let cached = hash.and_then(|hash| lock(&self.transactions).get(&hash).cloned());
+        self.stats.transactions.record(cached.is_some());
+
+        if cached.is_some() {
+            return Ok(cached);
+        }
+
+        // Not found yet doesn't mean it won't appear later, so only found ones are cached
+        let transaction = self.transport.get_dst_transaction(message_hash).await?;
+        if let Some(transaction) = &transaction {
+            self.store_transaction(transaction);
+            lock(&self.dst_transactions).insert(*message_hash, transaction.hash);
+        }
+
+        Ok(transaction)
+    }
+
+    async fn get_latest_key_block(&self) -> Result<ton_block::Block> {
+        self.transport.get_latest_key_block().await
+    }
+
+    async fn get_capabilities(&self, clock: &dyn Clock) -> Result<NetworkCapabilities> {
+        let cached = *lock(&self.capabilities);
+        self.stats.capabilities.record(cached.is_some());
+
+        if let Some(capabilities) = cached {
+            return Ok(capabilities);
+        }
+
+        let capabilities = self.transport.get_capabilities(clock).await?;
+        *lock(&self.capabilities) = Some(capabilities);
+
+        Ok(capabilities)
+    }
+
+    async fn get_blockchain_config(
+        &self,
+        clock: &dyn Clock,
+        force: bool,
+    ) -> Result<BlockchainConfig> {
+        let cached = if force {
+            None
+        } else {
+            lock(&self.config).clone()
+        };
+        self.stats.config.record(cached.is_some());
+
+        if let Some(config) = cached {
+            return Ok(config);
+        }
+
+        let config = self.transport.get_blockchain_config(clock, force).await?;
+        *lock(&self.config) = Some(config.clone());
+
+        Ok(config)
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_caching_transport_create(
+    transport: *mut c_void,
+    config: *mut c_char,
+) -> *mut c_char {
+    execute_sync(|| {
+        let transport = transport_from_ptr(transport)?;
+        let config = config.to_string_from_ptr();
+
+        fn internal_fn(
+            transport: Arc<dyn Transport>,
+            config: String,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let config = serde_json::from_str::<CachingTransportConfig>(&config)
+                .handle_error_as(ErrorCode::InvalidInput)?;
+
+            let caching_transport = CachingTransport::new(transport, config);
+
+            let ptr = caching_transport_new(Arc::new(caching_transport));
+
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
+        }
+
+        internal_fn(transport, config)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_caching_transport_stats(caching_transport: *mut c_void) -> *mut c_char {
+    execute_sync(|| {
+        let caching_transport = caching_transport_from_native_ptr(caching_transport)?;
+
+        fn internal_fn(
+            caching_transport: Arc<CachingTransport>,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            serde_json::to_value(caching_transport.stats()).handle_error()
+        }
+
+        internal_fn(caching_transport)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_caching_transport_invalidate(
+    caching_transport: *mut c_void,
+    invalidation: *mut c_char,
+) -> *mut c_char {
+    execute_sync(|| {
+        let caching_transport = caching_transport_from_native_ptr(caching_transport)?;
+        let invalidation = invalidation.to_string_from_ptr();
+
+        fn internal_fn(
+            caching_transport: Arc<CachingTransport>,
+            invalidation: String,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let invalidation = serde_json::from_str::<CacheInvalidation>(&invalidation)
+                .handle_error_as(ErrorCode::InvalidInput)?;
+
+            match invalidation {
+                CacheInvalidation::All => caching_transport.invalidate_all(),
+                CacheInvalidation::Kind(kind) => caching_transport.invalidate(kind),
+                CacheInvalidation::ContractState(address) => {
+                    let address = parse_address(&address)?;
+                    caching_transport.invalidate_contract_state(&address);
+                },
+            }
+
+            Ok(serde_json::Value::Null)
+        }
+
+        internal_fn(caching_transport, invalidation)
+    })
+}
+
+ffi_handle!(caching_transport, Arc<CachingTransport>);
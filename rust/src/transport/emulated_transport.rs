@@ -0,0 +1,482 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    ops::Bound,
+    os::raw::{c_char, c_void},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
+
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use nekoton::{
+    core::models::{NetworkCapabilities, ReliableBehavior},
+    transport::{
+        models::{ExistingContract, PollContractState, RawContractState, RawTransaction},
+        Transport, TransportInfo,
+    },
+};
+use nekoton_abi::{GenTimings, LastTransactionId, TransactionId};
+use nekoton_utils::Clock;
+use serde::{Deserialize, Serialize};
+use ton_block::{
+    Account, ConfigParams, Deserializable, InRefValue, Message, MsgAddressInt, Serializable,
+};
+use ton_executor::{BlockchainConfig, ExecuteParams, OrdinaryExecutor, TransactionExecutor};
+use ton_types::{Cell, UInt256};
+
+use crate::{
+    clock,
+    errors::{ErrorCode, ExecutionError},
+    execute_sync, ffi_handle, HandleError, ToPtrAddress, ToStringFromPtr, CLOCK,
+};
+
+/// Not used by any public network and not registered in `networks`, so that a local
+/// emulator is never detected as a real network.
+const DEFAULT_GLOBAL_ID: i32 = 31337;
+
+const MAX_TRANSACTIONS_PER_FETCH: u8 = 50;
+
+/// Limit of transactions produced by a single sent message,
+/// stops contracts which endlessly send messages to each other.
+const MAX_TRANSACTIONS_PER_MESSAGE: usize = 1000;
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct EmulatedTransportConfig {
+    /// Base64 encoded `ConfigParams`, default config is used if not set.
+    pub config: Option<String>,
+    pub global_id: Option<i32>,
+    /// Base64 encoded accounts which exist from the start, e.g. givers.
+    #[serde(default)]
+    pub accounts: Vec<String>,
+}
+
+/// Local blockchain which keeps accounts in memory and executes messages with `ton_executor`.
+///
+/// A sent message is processed together with all internal messages it produces,
+/// the resulting transactions are put into a new block.
+pub struct EmulatedTransport {
+    config: BlockchainConfig,
+    global_id: i32,
+    state: Mutex<EmulatedState>,
+}
+
+#[derive(Default)]
+struct EmulatedState {
+    accounts: BTreeMap<MsgAddressInt, EmulatedAccount>,
+    transactions: HashMap<UInt256, RawTransaction>,
+    dst_transactions: HashMap<UInt256, UInt256>,
+    blocks: Vec<EmulatedBlock>,
+    last_lt: u64,
+}
+
+#[derive(Clone)]
+struct EmulatedAccount {
+    account: Account,
+    last_transaction_id: LastTransactionId,
+    /// Logical times and hashes in ascending order
+    transactions: Vec<(u64, UInt256)>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmulatedBlock {
+    pub seqno: u32,
+    pub gen_utime: u32,
+    #[serde(with = "nekoton_utils::serde_u64")]
+    pub start_lt: u64,
+    #[serde(with = "nekoton_utils::serde_u64")]
+    pub end_lt: u64,
+    /// Hex encoded hashes in execution order
+    pub transactions: Vec<String>,
+}
+
+impl EmulatedTransport {
+    pub fn new(config: EmulatedTransportConfig) -> Result<Self, ExecutionError> {
+        let global_id = config.global_id.unwrap_or(DEFAULT_GLOBAL_ID);
+
+        let blockchain_config = match config.config {
+            Some(config) => {
+                let params = ConfigParams::construct_from_base64(&config)
+                    .handle_error_as(ErrorCode::InvalidBoc)?;
+                BlockchainConfig::with_config(params, global_id)
+                    .handle_error_as(ErrorCode::InvalidInput)?
+            },
+            None => BlockchainConfig::default(),
+        };
+
+        let transport = Self {
+            config: blockchain_config,
+            global_id,
+            state: Default::default(),
+        };
+
+        for account in config.accounts {
+            let account =
+                Account::construct_from_base64(&account).handle_error_as(ErrorCode::InvalidBoc)?;
+            transport
+                .add_account(account)
+                .handle_error_as(ErrorCode::InvalidInput)?;
+        }
+
+        Ok(transport)
+    }
+
+    /// Puts the account into the state as is, replacing an existing one.
+    pub fn add_account(&self, account: Account) -> Result<MsgAddressInt> {
+        let address = account
+            .get_addr()
+            .cloned()
+            .context("Account doesn't have an address")?;
+
+        let latest_lt = account.last_tr_time().unwrap_or_default();
+
+        let mut state = self.state();
+        state.last_lt = state.last_lt.max(latest_lt);
+        state.accounts.insert(
+            address.clone(),
+            EmulatedAccount {
+                account,
+                last_transaction_id: LastTransactionId::Inexact { latest_lt },
+                transactions: Vec::new(),
+            },
+        );
+
+        Ok(address)
+    }
+
+    pub fn latest_block(&self) -> Option<EmulatedBlock> {
+        self.state().blocks.last().cloned()
+    }
+
+    fn state(&self) -> MutexGuard<'_, EmulatedState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Executes the message and everything it produces. Nothing is applied if any of
+    /// the transactions fails to execute, e.g. when an external message is rejected.
+    fn process(&self, message: Message) -> Result<()> {
+        let gen_utime = clock!().now_sec_u64() as u32;
+
+        let mut state = self.state();
+
+        let start_lt = state.last_lt + 1;
+        let last_tr_lt = Arc::new(AtomicU64::new(state.last_lt));
+
+        let mut accounts = HashMap::<MsgAddressInt, EmulatedAccount>::new();
+        let mut transactions = Vec::<(UInt256, UInt256, RawTransaction)>::new();
+
+        let mut queue = VecDeque::from([message]);
+        while let Some(message) = queue.pop_front() {
+            let dst = match message.dst() {
+                Some(dst) => dst,
+                None => continue,
+            };
+
+            if transactions.len() >= MAX_TRANSACTIONS_PER_MESSAGE {
+                bail!(
+                    "Message produced more than {} transactions",
+                    MAX_TRANSACTIONS_PER_MESSAGE
+                );
+            }
+
+            let mut entry = match accounts.remove(&dst) {
+                Some(entry) => entry,
+                None => state
+                    .accounts
+                    .get(&dst)
+                    .cloned()
+                    .unwrap_or_else(|| EmulatedAccount {
+                        account: Account::default(),
+                        last_transaction_id: LastTransactionId::Inexact { latest_lt: 0 },
+                        transactions: Vec::new(),
+                    }),
+            };
+
+            let params = ExecuteParams {
+                block_unixtime: gen_utime,
+                block_lt: start_lt,
+                last_tr_lt: last_tr_lt.clone(),
+                ..Default::default()
+            };
+
+            let transaction = OrdinaryExecutor::new(self.config.clone())
+                .execute_with_params(Some(&message), &mut entry.account, params)
+                .with_context(|| format!("Failed to execute message on {}", dst))?;
+
+            transaction.out_msgs.iterate(|InRefValue(message)| {
+                if message.is_internal() {
+                    queue.push_back(message);
+                }
+                Ok(true)
+            })?;
+
+            let cell = transaction.serialize()?;
+            let hash = cell.repr_hash();
+            let message_hash = message.serialize()?.repr_hash();
+
+            let id = TransactionId {
+                lt: transaction.logical_time(),
+                hash,
+            };
+            entry.last_transaction_id = LastTransactionId::Exact(id);
+            entry.transactions.push((id.lt, hash));
+            accounts.insert(dst, entry);
+
+            transactions.push((
+                message_hash,
+                hash,
+                RawTransaction {
+                    hash,
+                    data: transaction,
+                },
+            ));
+        }
+
+        let end_lt = last_tr_lt.load(Ordering::Acquire);
+
+        state.accounts.extend(accounts);
+        state.last_lt = end_lt;
+
+        let mut hashes = Vec::with_capacity(transactions.len());
+        for (message_hash, hash, transaction) in transactions {
+            state.dst_transactions.insert(message_hash, hash);
+            state.transactions.insert(hash, transaction);
+            hashes.push(hash.to_hex_string());
+        }
+
+        let seqno = state.blocks.len() as u32 + 1;
+        state.blocks.push(EmulatedBlock {
+            seqno,
+            gen_utime,
+            start_lt,
+            end_lt,
+            transactions: hashes,
+        });
+
+        Ok(())
+    }
+}
+
+impl EmulatedState {
+    fn timings(&self) -> GenTimings {
+        match self.blocks.last() {
+            Some(block) => GenTimings::Known {
+                gen_lt: block.end_lt,
+                gen_utime: block.gen_utime,
+            },
+            None => GenTimings::Unknown,
+        }
+    }
+
+    fn contract_state(&self, address: &MsgAddressInt) -> RawContractState {
+        let timings = self.timings();
+
+        match self.accounts.get(address) {
+            Some(EmulatedAccount {
+                account: Account::Account(account),
+                last_transaction_id,
+                ..
+            }) => RawContractState::Exists(ExistingContract {
+                account: account.clone(),
+                timings,
+                last_transaction_id: *last_transaction_id,
+            }),
+            _ => RawContractState::NotExists { timings },
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for EmulatedTransport {
+    fn info(&self) -> TransportInfo {
+        TransportInfo {
+            max_transactions_per_fetch: MAX_TRANSACTIONS_PER_FETCH,
+            reliable_behavior: ReliableBehavior::IntensivePolling,
+            has_key_blocks: false,
+        }
+    }
+
+    async fn send_message(&self, message: &Message) -> Result<()> {
+        self.process(message.clone())
+    }
+
+    async fn get_contract_state(&self, address: &MsgAddressInt) -> Result<RawContractState> {
+        Ok(self.state().contract_state(address))
+    }
+
+    async fn get_library_cell(&self, _hash: &UInt256) -> Result<Option<Cell>> {
+        Ok(None)
+    }
+
+    async fn poll_contract_state(
+        &self,
+        address: &MsgAddressInt,
+        last_trans_lt: u64,
+    ) -> Result<PollContractState> {
+        Ok(match self.state().contract_state(address) {
+            RawContractState::Exists(contract)
+                if contract.last_transaction_id.lt() == last_trans_lt =>
+            {
+                PollContractState::Unchanged {
+                    timings: contract.timings,
+                }
+            },
+            RawContractState::Exists(contract) => PollContractState::Exists(contract),
+            RawContractState::NotExists { timings } => PollContractState::NotExists { timings },
+        })
+    }
+
+    async fn get_accounts_by_code_hash(
+        &self,
+        code_hash: &UInt256,
+        limit: u8,
+        continuation: &Option<MsgAddressInt>,
+    ) -> Result<Vec<MsgAddressInt>> {
+        let start = match continuation {
+            Some(continuation) => Bound::Excluded(continuation.clone()),
+            None => Bound::Unbounded,
+        };
+
+        let state = self.state();
+
+        Ok(state
+            .accounts
+            .range((start, Bound::Unbounded))
+            .filter(|(_, entry)| {
+                entry
+                    .account
+                    .get_code()
+                    .map(|code| code.repr_hash())
+                    .as_ref()
+                    == Some(code_hash)
+            })
+            .map(|(address, _)| address.clone())
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn get_transactions(
+        &self,
+        address: &MsgAddressInt,
+        from_lt: u64,
+        count: u8,
+    ) -> Result<Vec<RawTransaction>> {
+        let state = self.state();
+
+        let entry = match state.accounts.get(address) {
+            Some(entry) => entry,
+            None => return Ok(Vec::new()),
+        };
+
+        Ok(entry
+            .transactions
+            .iter()
+            .rev()
+            .filter(|(lt, _)| *lt <= from_lt)
+            .take(count as usize)
+            .filter_map(|(_, hash)| state.transactions.get(hash).cloned())
+            .collect())
+    }
+
+    async fn get_transaction(&self, id: &UInt256) -> Result<Option<RawTransaction>> {
+        Ok(self.state().transactions.get(id).cloned())
+    }
+
+    async fn get_dst_transaction(&self, message_hash: &UInt256) -> Result<Option<RawTransaction>> {
+        let state = self.state();
+
+        Ok(state
+            .dst_transactions
+            .get(message_hash)
+            .and_then(|hash| state.transactions.get(hash))
+            .cloned())
+    }
+
+    async fn get_latest_key_block(&self) -> Result<ton_block::Block> {
+        bail!("Emulated transport doesn't produce key blocks")
+    }
+
+    async fn get_capabilities(&self, _clock: &dyn Clock) -> Result<NetworkCapabilities> {
+        Ok(NetworkCapabilities {
+            global_id: self.global_id,
+            raw: 0,
+        })
+    }
+
+    async fn get_blockchain_config(
+        &self,
+        _clock: &dyn Clock,
+        _force: bool,
+    ) -> Result<BlockchainConfig> {
+        Ok(self.config.clone())
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_emulated_transport_create(config: *mut c_char) -> *mut c_char {
+    execute_sync(|| {
+        let config = config.to_string_from_ptr();
+
+        fn internal_fn(config: String) -> Result<serde_json::Value, ExecutionError> {
+            let config = serde_json::from_str::<EmulatedTransportConfig>(&config)
+                .handle_error_as(ErrorCode::InvalidInput)?;
+
+            let emulated_transport = EmulatedTransport::new(config)?;
+
+            let ptr = emulated_transport_new(Arc::new(emulated_transport));
+
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
+        }
+
+        internal_fn(config)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_emulated_transport_add_account(
+    emulated_transport: *mut c_void,
+    account: *mut c_char,
+) -> *mut c_char {
+    execute_sync(|| {
+        let emulated_transport = emulated_transport_from_native_ptr(emulated_transport)?;
+        let account = account.to_string_from_ptr();
+
+        fn internal_fn(
+            emulated_transport: Arc<EmulatedTransport>,
+            account: String,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let account =
+                Account::construct_from_base64(&account).handle_error_as(ErrorCode::InvalidBoc)?;
+
+            let address = emulated_transport
+                .add_account(account)
+                .handle_error_as(ErrorCode::InvalidInput)?;
+
+            serde_json::to_value(address.to_string()).handle_error()
+        }
+
+        internal_fn(emulated_transport, account)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_emulated_transport_latest_block(
+    emulated_transport: *mut c_void,
+) -> *mut c_char {
+    execute_sync(|| {
+        let emulated_transport = emulated_transport_from_native_ptr(emulated_transport)?;
+
+        fn internal_fn(
+            emulated_transport: Arc<EmulatedTransport>,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            serde_json::to_value(emulated_transport.latest_block()).handle_error()
+        }
+
+        internal_fn(emulated_transport)
+    })
+}
+
+ffi_handle!(emulated_transport, Arc<EmulatedTransport>);
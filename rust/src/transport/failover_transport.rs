@@ -0,0 +1,428 @@
+use std::{
+    future::Future,
+    os::raw::{c_char, c_void},
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use nekoton::{
+    core::models::{NetworkCapabilities, ReliableBehavior},
+    transport::{
+        models::{PollContractState, RawContractState, RawTransaction},
+        Transport, TransportInfo,
+    },
+};
+use nekoton_abi::GenTimings;
+use nekoton_utils::Clock;
+use serde::Serialize;
+use ton_block::{Message, MsgAddressInt};
+use ton_executor::BlockchainConfig;
+use ton_types::{Cell, UInt256};
+
+use crate::{
+    errors::{ErrorCode, ExecutionError},
+    execute_sync, ffi_handle,
+    transport::transport_from_ptr,
+    HandleError, ToPtrAddress, ToPtrFromAddress, ToStringFromPtr,
+};
+
+/// Weight of the latest sample in latency and error rate averages.
+const SMOOTHING: f64 = 0.3;
+
+/// Assumed latency of endpoints which haven't answered yet.
+const UNKNOWN_LATENCY_MS: f64 = 100.0;
+
+/// Failed requests count as this many times slower at a 100% error rate.
+const ERROR_RATE_PENALTY: f64 = 10.0;
+
+/// Penalty for every second an endpoint lags behind the freshest one.
+const LAG_PENALTY_MS: f64 = 1000.0;
+
+/// An endpoint is skipped for a while after this many failures in a row.
+const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+
+const COOLDOWN: Duration = Duration::from_secs(30);
+
+type TransportFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// Routes every call to the healthiest of several transports and falls back to the next one
+/// on failure.
+///
+/// Endpoints are scored by average latency, error rate and how far behind the freshest
+/// endpoint their states are. Messages are sent through a single endpoint only, a failed
+/// send is not repeated elsewhere.
+pub struct FailoverTransport {
+    endpoints: Vec<Endpoint>,
+}
+
+struct Endpoint {
+    transport: Arc<dyn Transport>,
+    health: Mutex<EndpointHealth>,
+}
+
+#[derive(Default)]
+struct EndpointHealth {
+    latency_ms: Option<f64>,
+    error_rate: f64,
+    consecutive_failures: u32,
+    unhealthy_until: Option<Instant>,
+    gen_utime: Option<u32>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointStatus {
+    pub latency_ms: Option<f64>,
+    pub error_rate: f64,
+    pub gen_utime: Option<u32>,
+    pub is_healthy: bool,
+    pub score: f64,
+}
+
+impl Endpoint {
+    fn health(&self) -> MutexGuard<'_, EndpointHealth> {
+        self.health.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl EndpointHealth {
+    fn is_healthy(&self, now: Instant) -> bool {
+        !matches!(self.unhealthy_until, Some(until) if until > now)
+    }
+
+    /// Lower is better, unhealthy endpoints are only used when nothing else is left.
+    fn score(&self, latest_gen_utime: Option<u32>) -> f64 {
+        let lag = match (latest_gen_utime, self.gen_utime) {
+            (Some(latest), Some(gen_utime)) => latest.saturating_sub(gen_utime),
+            _ => 0,
+        };
+
+        self.latency_ms.unwrap_or(UNKNOWN_LATENCY_MS) * (1.0 + ERROR_RATE_PENALTY * self.error_rate)
+            + lag as f64 * LAG_PENALTY_MS
+    }
+
+    fn on_success(&mut self, latency: Duration) {
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+
+        self.latency_ms = Some(match self.latency_ms {
+            Some(average) => average + SMOOTHING * (latency_ms - average),
+            None => latency_ms,
+        });
+        self.error_rate -= SMOOTHING * self.error_rate;
+        self.consecutive_failures = 0;
+        self.unhealthy_until = None;
+    }
+
+    fn on_failure(&mut self, now: Instant) {
+        self.error_rate += SMOOTHING * (1.0 - self.error_rate);
+        self.consecutive_failures += 1;
+
+        if self.consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+            self.unhealthy_until = Some(now + COOLDOWN);
+        }
+    }
+
+    fn on_timings(&mut self, timings: &GenTimings) {
+        if let GenTimings::Known { gen_utime, .. } = timings {
+            self.gen_utime = Some(self.gen_utime.unwrap_or_default().max(*gen_utime));
+        }
+    }
+}
+
+impl FailoverTransport {
+    pub fn new(transports: Vec<Arc<dyn Transport>>) -> Result<Self, ExecutionError> {
+        if transports.is_empty() {
+            return Err(ExecutionError::new(
+                ErrorCode::InvalidInput,
+                "At least one transport is required",
+            ));
+        }
+
+        let endpoints = transports
+            .into_iter()
+            .map(|transport| Endpoint {
+                transport,
+                health: Default::default(),
+            })
+            .collect();
+
+        Ok(Self { endpoints })
+    }
+
+    pub fn status(&self) -> Vec<EndpointStatus> {
+        let now = Instant::now();
+        let latest_gen_utime = self.latest_gen_utime();
+
+        self.endpoints
+            .iter()
+            .map(|endpoint| {
+                let health = endpoint.health();
+                EndpointStatus {
+                    latency_ms: health.latency_ms,
+                    error_rate: health.error_rate,
+                    gen_utime: health.gen_utime,
+                    is_healthy: health.is_healthy(now),
+                    score: health.score(latest_gen_utime),
+                }
+            })
+            .collect()
+    }
+
+    fn latest_gen_utime(&self) -> Option<u32> {
+        self.endpoints
+            .iter()
+            .filter_map(|endpoint| endpoint.health().gen_utime)
+            .max()
+    }
+
+    /// Endpoint indices from the healthiest to the least healthy one.
+    fn ranked(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let latest_gen_utime = self.latest_gen_utime();
+
+        let mut ranked = self
+            .endpoints
+            .iter()
+            .enumerate()
+            .map(|(index, endpoint)| {
+                let health = endpoint.health();
+                (
+                    !health.is_healthy(now),
+                    health.score(latest_gen_utime),
+                    index,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        ranked.sort_by(|(a_unhealthy, a_score, _), (b_unhealthy, b_score, _)| {
+            a_unhealthy
+                .cmp(b_unhealthy)
+                .then_with(|| a_score.total_cmp(b_score))
+        });
+
+        ranked.into_iter().map(|(_, _, index)| index).collect()
+    }
+
+    async fn call<'a, T>(
+        &'a self,
+        index: usize,
+        f: &impl Fn(&'a dyn Transport) -> TransportFuture<'a, T>,
+    ) -> Result<T> {
+        let endpoint = &self.endpoints[index];
+
+        let started_at = Instant::now();
+        let result = f(endpoint.transport.as_ref()).await;
+
+        let mut health = endpoint.health();
+        match &result {
+            Ok(_) => health.on_success(started_at.elapsed()),
+            Err(_) => health.on_failure(Instant::now()),
+        }
+
+        result
+    }
+
+    /// Tries endpoints in order of their health until one succeeds.
+    /// Returns the index of the endpoint which answered.
+    async fn route<'a, T>(
+        &'a self,
+        f: impl Fn(&'a dyn Transport) -> TransportFuture<'a, T>,
+    ) -> Result<(usize, T)> {
+        let mut last_error = None;
+
+        for index in self.ranked() {
+            match self.call(index, &f).await {
+                Ok(result) => return Ok((index, result)),
+                Err(err) => {
+                    log::warn!("Failover transport endpoint {} failed: {:?}", index, err);
+                    last_error = Some(err);
+                },
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| anyhow!("No endpoints available")))
+    }
+
+    fn observe_timings(&self, index: usize, timings: &GenTimings) {
+        self.endpoints[index].health().on_timings(timings);
+    }
+}
+
+#[async_trait]
+impl Transport for FailoverTransport {
+    fn info(&self) -> TransportInfo {
+        let infos = self
+            .endpoints
+            .iter()
+            .map(|endpoint| endpoint.transport.info())
+            .collect::<Vec<_>>();
+
+        TransportInfo {
+            max_transactions_per_fetch: infos
+                .iter()
+                .map(|info| info.max_transactions_per_fetch)
+                .min()
+                .unwrap_or_default(),
+            // Block walking is bound to a single endpoint
+            reliable_behavior: ReliableBehavior::IntensivePolling,
+            has_key_blocks: infos.iter().all(|info| info.has_key_blocks),
+        }
+    }
+
+    async fn send_message(&self, message: &Message) -> Result<()> {
+        let index = self.ranked()[0];
+        self.call(index, &|transport| transport.send_message(message))
+            .await
+    }
+
+    async fn get_contract_state(&self, address: &MsgAddressInt) -> Result<RawContractState> {
+        let (index, state) = self
+            .route(|transport| transport.get_contract_state(address))
+            .await?;
+
+        let timings = match &state {
+            RawContractState::Exists(contract) => &contract.timings,
+            RawContractState::NotExists { timings } => timings,
+        };
+        self.observe_timings(index, timings);
+
+        Ok(state)
+    }
+
+    async fn get_library_cell(&self, hash: &UInt256) -> Result<Option<Cell>> {
+        let (_, cell) = self
+            .route(|transport| transport.get_library_cell(hash))
+            .await?;
+        Ok(cell)
+    }
+
+    async fn poll_contract_state(
+        &self,
+        address: &MsgAddressInt,
+        last_trans_lt: u64,
+    ) -> Result<PollContractState> {
+        let (index, state) = self
+            .route(|transport| transport.poll_contract_state(address, last_trans_lt))
+            .await?;
+
+        let timings = match &state {
+            PollContractState::Unchanged { timings } => timings,
+            PollContractState::NotExists { timings } => timings,
+            PollContractState::Exists(contract) => &contract.timings,
+        };
+        self.observe_timings(index, timings);
+
+        Ok(state)
+    }
+
+    async fn get_accounts_by_code_hash(
+        &self,
+        code_hash: &UInt256,
+        limit: u8,
+        continuation: &Option<MsgAddressInt>,
+    ) -> Result<Vec<MsgAddressInt>> {
+        let (_, accounts) = self
+            .route(|transport| transport.get_accounts_by_code_hash(code_hash, limit, continuation))
+            .await?;
+        Ok(accounts)
+    }
+
+    async fn get_transactions(
+        &self,
+        address: &MsgAddressInt,
+        from_lt: u64,
+        count: u8,
+    ) -> Result<Vec<RawTransaction>> {
+        let (_, transactions) = self
+            .route(|transport| transport.get_transactions(address, from_lt, count))
+            .await?;
+        Ok(transactions)
+    }
+
+    async fn get_transaction(&self, id: &UInt256) -> Result<Option<RawTransaction>> {
+        let (_, transaction) = self
+            .route(|transport| transport.get_transaction(id))
+            .await?;
+        Ok(transaction)
+    }
+
+    async fn get_dst_transaction(&self, message_hash: &UInt256) -> Result<Option<RawTransaction>> {
+        let (_, transaction) = self
+            .route(|transport| transport.get_dst_transaction(message_hash))
+            .await?;
+        Ok(transaction)
+    }
+
+    async fn get_latest_key_block(&self) -> Result<ton_block::Block> {
+        let (_, block) = self
+            .route(|transport| transport.get_latest_key_block())
+            .await?;
+        Ok(block)
+    }
+
+    async fn get_capabilities(&self, clock: &dyn Clock) -> Result<NetworkCapabilities> {
+        let (_, capabilities) = self
+            .route(|transport| transport.get_capabilities(clock))
+            .await?;
+        Ok(capabilities)
+    }
+
+    async fn get_blockchain_config(
+        &self,
+        clock: &dyn Clock,
+        force: bool,
+    ) -> Result<BlockchainConfig> {
+        let (_, config) = self
+            .route(|transport| transport.get_blockchain_config(clock, force))
+            .await?;
+        Ok(config)
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_failover_transport_create(transports: *mut c_char) -> *mut c_char {
+    execute_sync(|| {
+        let transports = transports.to_string_from_ptr();
+
+        let transports = serde_json::from_str::<Vec<String>>(&transports)
+            .handle_error_as(ErrorCode::InvalidInput)?
+            .into_iter()
+            .map(|transport| transport_from_ptr(transport.to_ptr_from_address::<c_void>()?))
+            .collect::<Result<Vec<_>, ExecutionError>>()?;
+
+        fn internal_fn(
+            transports: Vec<Arc<dyn Transport>>,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let failover_transport = FailoverTransport::new(transports)?;
+
+            let ptr = failover_transport_new(Arc::new(failover_transport));
+
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
+        }
+
+        internal_fn(transports)
+    })
+}
+
+/// Current health of every endpoint in the order they were passed on creation.
+#[no_mangle]
+pub unsafe extern "C" fn nt_failover_transport_status(
+    failover_transport: *mut c_void,
+) -> *mut c_char {
+    execute_sync(|| {
+        let failover_transport = failover_transport_from_native_ptr(failover_transport)?;
+
+        fn internal_fn(
+            failover_transport: Arc<FailoverTransport>,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            serde_json::to_value(failover_transport.status()).handle_error()
+        }
+
+        internal_fn(failover_transport)
+    })
+}
+
+ffi_handle!(failover_transport, Arc<FailoverTransport>);
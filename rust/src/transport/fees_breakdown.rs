@@ -0,0 +1,350 @@
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    os::raw::{c_char, c_longlong, c_ulonglong, c_void},
+    sync::Arc,
+};
+
+use nekoton::{
+    core::{models::Transaction, transactions_tree::TransactionsTreeStream},
+    transport::{models::RawContractState, Transport},
+};
+use nekoton_utils::serde_address;
+use serde::{Deserialize, Serialize};
+use ton_block::{
+    InRefValue, Message, MsgAddressInt, Serializable, TrComputePhase, TransactionDescr,
+    TransactionDescrOrdinary,
+};
+use ton_types::UInt256;
+
+use crate::{
+    clock,
+    crypto::unsigned_message_from_native_ptr,
+    errors::{ErrorCode, ExecutionError},
+    execute_async,
+    transport::{
+        transport_from_ptr,
+        tx_tree_report::{balance_delta, DEFAULT_MAX_TRANSACTIONS},
+    },
+    HandleError, ToStringFromPtr, CLOCK,
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeesEstimateOptions {
+    /// The simulation of the cascade stops after this many transactions, 100 by default.
+    pub max_transactions: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeesEstimate {
+    /// Fees of the transaction of the external message and its cascade.
+    pub fees: FeesBreakdown,
+    /// Balance of the message destination after the whole cascade.
+    pub balance_after: String,
+    /// Set when the cascade was cut by the transactions limit.
+    pub is_truncated: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeesBreakdown {
+    #[serde(with = "serde_address")]
+    pub account: MsgAddressInt,
+    pub storage_fees: String,
+    pub gas_fees: String,
+    pub gas_used: String,
+    pub gas_limit: String,
+    pub forward_fees: String,
+    pub action_fees: String,
+    pub total_fees: String,
+    /// Value of all outgoing internal messages.
+    pub outgoing_value: String,
+    /// Transactions of the outgoing messages.
+    pub children: Vec<FeesBreakdown>,
+}
+
+struct FeesNode {
+    breakdown: FeesBreakdown,
+    parent: Option<usize>,
+}
+
+/// Collects fees of the simulated transactions, every transaction is attached to the one
+/// which produced its in-message.
+#[derive(Default)]
+struct FeesTree {
+    nodes: Vec<FeesNode>,
+    /// Index of the transaction which produced the message.
+    sources: HashMap<UInt256, usize>,
+    balance_delta: i128,
+}
+
+impl FeesTree {
+    fn add_transaction(
+        &mut self,
+        raw: ton_block::Transaction,
+        root_account: &MsgAddressInt,
+    ) -> Result<(), ExecutionError> {
+        // Transactions whose in-message source is unknown are attached to the root, so
+        // none of them are lost
+        let parent = raw
+            .in_msg_cell()
+            .and_then(|cell| self.sources.get(&cell.repr_hash()).copied())
+            .or(if self.nodes.is_empty() { None } else { Some(0) });
+
+        let mut messages = Vec::new();
+        raw.out_msgs
+            .iterate(|InRefValue(message)| {
+                messages.push(message.serialize()?.repr_hash());
+                Ok(true)
+            })
+            .handle_error()?;
+
+        // Only ordinary transactions have a breakdown, the rest are counted by total fees
+        let fees = match raw.read_description().handle_error()? {
+            TransactionDescr::Ordinary(descr) => PhaseFees::from(&descr),
+            _ => PhaseFees::default(),
+        };
+
+        let hash = raw.serialize().handle_error()?.repr_hash();
+        let transaction = Transaction::try_from((hash, raw)).handle_error()?;
+
+        let account = transaction.in_msg.dst.clone().ok_or_else(|| {
+            ExecutionError::new(ErrorCode::ExecutionFailed, "Transaction has no account")
+        })?;
+
+        if &account == root_account {
+            self.balance_delta += balance_delta(&transaction);
+        }
+
+        let outgoing_value = transaction
+            .out_msgs
+            .iter()
+            .filter(|message| message.dst.is_some())
+            .map(|message| message.value)
+            .sum::<u128>();
+
+        let breakdown = FeesBreakdown {
+            account,
+            storage_fees: fees.storage_fees.to_string(),
+            gas_fees: fees.gas_fees.to_string(),
+            gas_used: fees.gas_used.to_string(),
+            gas_limit: fees.gas_limit.to_string(),
+            forward_fees: fees.forward_fees.to_string(),
+            action_fees: fees.action_fees.to_string(),
+            total_fees: transaction.total_fees.to_string(),
+            outgoing_value: outgoing_value.to_string(),
+            children: Vec::new(),
+        };
+
+        let index = self.nodes.len();
+        for message in messages {
+            self.sources.insert(message, index);
+        }
+
+        self.nodes.push(FeesNode { breakdown, parent });
+
+        Ok(())
+    }
+
+    /// Attaches children to parents, the first transaction is the root.
+    fn build(mut self) -> Option<FeesBreakdown> {
+        while let Some(node) = self.nodes.pop() {
+            match node.parent {
+                // Parents are always added before their children
+                Some(parent) => self.nodes[parent]
+                    .breakdown
+                    .children
+                    .insert(0, node.breakdown),
+                None => return Some(node.breakdown),
+            }
+        }
+
+        None
+    }
+}
+
+#[derive(Default)]
+struct PhaseFees {
+    storage_fees: u128,
+    gas_fees: u128,
+    gas_used: u64,
+    gas_limit: u64,
+    forward_fees: u128,
+    action_fees: u128,
+}
+
+impl From<&TransactionDescrOrdinary> for PhaseFees {
+    fn from(descr: &TransactionDescrOrdinary) -> Self {
+        let (gas_fees, gas_used, gas_limit) = match &descr.compute_ph {
+            TrComputePhase::Vm(compute) => (
+                compute.gas_fees.as_u128(),
+                compute.gas_used.as_u64(),
+                compute.gas_limit.as_u64(),
+            ),
+            TrComputePhase::Skipped(_) => (0, 0, 0),
+        };
+
+        let (forward_fees, action_fees) = match &descr.action {
+            Some(action) => (
+                action.total_fwd_fees.as_ref().map(|fees| fees.as_u128()),
+                action.total_action_fees.as_ref().map(|fees| fees.as_u128()),
+            ),
+            None => (None, None),
+        };
+
+        let storage_fees = descr
+            .storage_ph
+            .as_ref()
+            .map(|storage| storage.storage_fees_collected.as_u128())
+            .unwrap_or_default();
+
+        Self {
+            storage_fees,
+            gas_fees,
+            gas_used,
+            gas_limit,
+            forward_fees: forward_fees.unwrap_or_default(),
+            action_fees: action_fees.unwrap_or_default(),
+        }
+    }
+}
+
+/// Estimates fees of the message and of every transaction in its cascade, the result is
+/// a `FeesEstimate`. The message is signed with a dummy signature the same way as for
+/// `estimate_fees`, so no key is needed before the user confirms the transfer.
+#[no_mangle]
+pub unsafe extern "C" fn nt_transport_estimate_fees_breakdown(
+    result_port: c_longlong,
+    transport: *mut c_void,
+    unsigned_message: *mut c_void,
+    options: *mut c_char,
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let transport = transport_from_ptr(transport)?;
+        let unsigned_message = unsigned_message_from_native_ptr(unsigned_message)?;
+        let options = options.to_string_from_ptr();
+
+        let options = serde_json::from_str::<FeesEstimateOptions>(&options)
+            .handle_error_as(ErrorCode::InvalidInput)?;
+        let max_transactions = options.max_transactions.unwrap_or(DEFAULT_MAX_TRANSACTIONS);
+        if max_transactions == 0 {
+            return Err(ExecutionError::new(
+                ErrorCode::InvalidInput,
+                "Transactions limit must not be zero",
+            ));
+        }
+
+        Ok(async move {
+            async fn internal_fn(
+                transport: Arc<dyn Transport>,
+                message: Message,
+                max_transactions: usize,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let root_account = message.dst().ok_or_else(|| {
+                    ExecutionError::new(ErrorCode::InvalidInput, "Message has no destination")
+                })?;
+
+                let balance_before = match transport
+                    .get_contract_state(&root_account)
+                    .await
+                    .handle_error_as(ErrorCode::RequestFailed)?
+                {
+                    RawContractState::Exists(state) => {
+                        state.account.storage.balance.grams.as_u128()
+                    },
+                    RawContractState::NotExists { .. } => 0,
+                };
+
+                let config = transport
+                    .get_blockchain_config(clock!().as_ref(), false)
+                    .await
+                    .handle_error_as(ErrorCode::RequestFailed)?;
+
+                let mut stream = TransactionsTreeStream::new(message, config, transport, clock!());
+                stream.disable_signature_check();
+
+                let mut tree = FeesTree::default();
+                let mut is_truncated = false;
+
+                let mut processed = 0;
+                while let Some(transaction) = stream
+                    .next()
+                    .await
+                    .handle_error_as(ErrorCode::ExecutionFailed)?
+                {
+                    if processed == max_transactions {
+                        is_truncated = true;
+                        break;
+                    }
+                    processed += 1;
+
+                    tree.add_transaction(transaction, &root_account)?;
+                }
+
+                let balance_after = balance_before as i128 + tree.balance_delta;
+
+                let fees = tree.build().ok_or_else(|| {
+                    ExecutionError::new(
+                        ErrorCode::ExecutionFailed,
+                        "Message produced no transaction",
+                    )
+                })?;
+
+                let estimate = FeesEstimate {
+                    fees,
+                    balance_after: balance_after.max(0).to_string(),
+                    is_truncated,
+                };
+
+                serde_json::to_value(estimate).handle_error()
+            }
+
+            let message = unsigned_message
+                .read()
+                .await
+                .sign(&[0; 64])
+                .handle_error_as(ErrorCode::SigningFailed)?
+                .message;
+
+            internal_fn(transport, message, max_transactions).await
+        })
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::tests::{block_on, emulated_transport, raw_transport, TestWallet};
+
+    #[test]
+    fn transactions_are_never_dropped() {
+        let transport = emulated_transport();
+        let first = TestWallet::deployed(&transport, 1, 10_000_000_000);
+        let second = TestWallet::deployed(&transport, 2, 10_000_000_000);
+
+        let latest_transaction = |wallet: &TestWallet| {
+            let address = MsgAddressInt::from_str(&wallet.address).unwrap();
+            let transactions =
+                block_on(raw_transport(&transport).get_transactions(&address, u64::MAX, 1));
+            transactions.unwrap().remove(0).data
+        };
+
+        let root_account = MsgAddressInt::from_str(&first.address).unwrap();
+
+        let mut tree = FeesTree::default();
+        tree.add_transaction(latest_transaction(&first), &root_account)
+            .unwrap();
+        // Produced by a message which is not in the tree
+        tree.add_transaction(latest_transaction(&second), &root_account)
+            .unwrap();
+
+        let fees = tree.build().unwrap();
+        assert_eq!(fees.account, root_account);
+        assert_eq!(fees.children.len(), 1);
+        assert_eq!(fees.children[0].account.to_string(), second.address);
+    }
+}
@@ -6,23 +6,29 @@ use std::{
 use nekoton::transport::jrpc::JrpcTransport;
 
 use crate::{
+    errors::ExecutionError,
+    execute_sync,
     external::jrpc_connection::{jrpc_connection_from_native_ptr, JrpcConnectionImpl},
-    ffi_box, HandleError, MatchResult, ToPtrAddress,
+    ffi_handle, HandleError, ToPtrAddress,
 };
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_jrpc_transport_create(jrpc_connection: *mut c_void) -> *mut c_char {
-    let jrpc_connection = jrpc_connection_from_native_ptr(jrpc_connection).clone();
+    execute_sync(|| {
+        let jrpc_connection = jrpc_connection_from_native_ptr(jrpc_connection)?;
 
-    fn internal_fn(jrpc_connection: Arc<JrpcConnectionImpl>) -> Result<serde_json::Value, String> {
-        let jrpc_transport = JrpcTransport::new(jrpc_connection);
+        fn internal_fn(
+            jrpc_connection: Arc<JrpcConnectionImpl>,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let jrpc_transport = JrpcTransport::new(jrpc_connection);
 
-        let ptr = jrpc_transport_new(Arc::new(jrpc_transport));
+            let ptr = jrpc_transport_new(Arc::new(jrpc_transport));
 
-        serde_json::to_value(ptr.to_ptr_address()).handle_error()
-    }
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
+        }
 
-    internal_fn(jrpc_connection).match_result()
+        internal_fn(jrpc_connection)
+    })
 }
 
-ffi_box!(jrpc_transport, Arc<JrpcTransport>);
+ffi_handle!(jrpc_transport, Arc<JrpcTransport>);
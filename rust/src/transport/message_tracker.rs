@@ -0,0 +1,181 @@
+use std::{
+    convert::TryFrom,
+    os::raw::{c_char, c_longlong, c_ulonglong, c_void},
+    sync::Arc,
+    time::Duration,
+};
+
+use nekoton::{
+    core::models::Transaction,
+    crypto::SignedMessage,
+    transport::{models::RawTransaction, Transport},
+};
+use nekoton_utils::Clock;
+use serde::Serialize;
+use ton_block::{MsgAddressInt, Serializable};
+use ton_types::UInt256;
+
+use crate::{
+    clock,
+    errors::{ErrorCode, ExecutionError},
+    execute_async,
+    transport::transport_from_ptr,
+    HandleError, ToStringFromPtr, CLOCK,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+const TRANSACTIONS_BATCH_SIZE: u8 = 16;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+pub enum SentMessageStatus {
+    Confirmed(Transaction),
+    Expired,
+}
+
+/// Sends an external message and waits for the transaction it produces on the destination.
+struct MessageTracker {
+    transport: Arc<dyn Transport>,
+    message: SignedMessage,
+    message_hash: UInt256,
+    destination: MsgAddressInt,
+}
+
+impl MessageTracker {
+    fn new(transport: Arc<dyn Transport>, message: SignedMessage) -> Result<Self, ExecutionError> {
+        let message_hash = message
+            .message
+            .serialize()
+            .map(|cell| cell.repr_hash())
+            .handle_error()?;
+
+        let destination = message.message.dst().ok_or_else(|| {
+            ExecutionError::new(ErrorCode::InvalidInput, "Message has no destination")
+        })?;
+
+        Ok(Self {
+            transport,
+            message,
+            message_hash,
+            destination,
+        })
+    }
+
+    async fn run(self) -> Result<SentMessageStatus, ExecutionError> {
+        // Transactions up to this lt can't be produced by the message
+        let mut known_lt = self.latest_lt().await?;
+
+        self.transport
+            .send_message(&self.message.message)
+            .await
+            .handle_error_as(ErrorCode::SendFailed)?;
+
+        loop {
+            // The check goes after the expiration to catch transactions which were
+            // produced in time but indexed later
+            let is_expired = clock!().now_sec_u64() > self.message.expire_at as u64;
+
+            if let Some(transaction) = self.find_transaction(&mut known_lt).await? {
+                let transaction = Transaction::try_from((transaction.hash, transaction.data))
+                    .handle_error_as(ErrorCode::DecodingFailed)?;
+
+                return Ok(SentMessageStatus::Confirmed(transaction));
+            }
+
+            if is_expired {
+                return Ok(SentMessageStatus::Expired);
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    async fn latest_lt(&self) -> Result<u64, ExecutionError> {
+        let transactions = self
+            .transport
+            .get_transactions(&self.destination, u64::MAX, 1)
+            .await
+            .handle_error_as(ErrorCode::RequestFailed)?;
+
+        Ok(transactions
+            .first()
+            .map(|transaction| transaction.data.lt)
+            .unwrap_or_default())
+    }
+
+    /// Looks through the transactions newer than `known_lt` and moves it to the latest one.
+    async fn find_transaction(
+        &self,
+        known_lt: &mut u64,
+    ) -> Result<Option<RawTransaction>, ExecutionError> {
+        let mut latest_lt = None;
+
+        let mut from_lt = u64::MAX;
+        'batches: while from_lt > *known_lt {
+            let batch = self
+                .transport
+                .get_transactions(&self.destination, from_lt, TRANSACTIONS_BATCH_SIZE)
+                .await
+                .handle_error_as(ErrorCode::RequestFailed)?;
+            let is_last_batch = batch.len() < TRANSACTIONS_BATCH_SIZE as usize;
+
+            for transaction in batch {
+                // Older transactions were checked by the previous polls
+                if transaction.data.lt <= *known_lt {
+                    break 'batches;
+                }
+                latest_lt.get_or_insert(transaction.data.lt);
+                from_lt = transaction.data.prev_trans_lt;
+
+                let in_msg_hash = transaction.data.in_msg_cell().map(|cell| cell.repr_hash());
+                if in_msg_hash.as_ref() == Some(&self.message_hash) {
+                    return Ok(Some(transaction));
+                }
+            }
+
+            if is_last_batch {
+                break;
+            }
+        }
+
+        if let Some(latest_lt) = latest_lt {
+            *known_lt = latest_lt;
+        }
+
+        Ok(None)
+    }
+}
+
+/// Sends the message and resolves with a `SentMessageStatus` once the transaction of
+/// the message is found on the destination or the message expires.
+#[no_mangle]
+pub unsafe extern "C" fn nt_transport_send_and_confirm(
+    result_port: c_longlong,
+    transport: *mut c_void,
+    signed_message: *mut c_char,
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let transport = transport_from_ptr(transport)?;
+        let signed_message = signed_message.to_string_from_ptr();
+
+        let tracker = {
+            let signed_message =
+                serde_json::from_str::<SignedMessage>(&signed_message).handle_error()?;
+
+            MessageTracker::new(transport, signed_message)?
+        };
+
+        Ok(async move {
+            async fn internal_fn(
+                tracker: MessageTracker,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let status = tracker.run().await?;
+
+                serde_json::to_value(status).handle_error()
+            }
+
+            internal_fn(tracker).await
+        })
+    })
+}
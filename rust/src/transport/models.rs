@@ -13,6 +13,26 @@ pub enum TransportType {
     Jrpc,
     Gql,
     Proto,
+    Emulated,
+    Failover,
+    Caching,
+    Throttled,
+}
+
+impl TransportType {
+    /// Every transport is registered under its own handle kind.
+    pub fn from_handle_kind(kind: &str) -> Option<Self> {
+        match kind {
+            "jrpc_transport" => Some(Self::Jrpc),
+            "gql_transport" => Some(Self::Gql),
+            "proto_transport" => Some(Self::Proto),
+            "emulated_transport" => Some(Self::Emulated),
+            "failover_transport" => Some(Self::Failover),
+            "caching_transport" => Some(Self::Caching),
+            "throttled_transport" => Some(Self::Throttled),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -0,0 +1,197 @@
+use std::{
+    os::raw::{c_char, c_longlong, c_ulonglong, c_void},
+    sync::{Arc, RwLock},
+};
+
+use lazy_static::lazy_static;
+use nekoton::transport::Transport;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock,
+    errors::{ErrorCode, ExecutionError},
+    execute_async, execute_sync,
+    transport::transport_from_ptr,
+    HandleError, ToStringFromPtr, CLOCK,
+};
+
+lazy_static! {
+    static ref NETWORKS: RwLock<Vec<NetworkEntry>> = RwLock::new(builtin_networks());
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkDescriptor {
+    pub name: String,
+    pub global_id: i32,
+    /// Group of the `AccountsStorage` assets of the network.
+    pub network_group: String,
+    /// Set when signatures on the network may include the signature ID.
+    pub supports_signature_id: bool,
+    pub currency: NativeCurrency,
+    /// Template with an `{address}` placeholder.
+    pub account_explorer_url: Option<String>,
+    /// Template with a `{hash}` placeholder.
+    pub transaction_explorer_url: Option<String>,
+    /// Manifest with the tokens which are shown by default.
+    pub token_list_url: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeCurrency {
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NetworkStatus {
+    Builtin,
+    /// Registered by the app.
+    Custom,
+    Unknown,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedNetwork {
+    pub global_id: i32,
+    /// Signature ID the network currently requires.
+    pub signature_id: Option<i32>,
+    pub status: NetworkStatus,
+    /// Not set for unknown networks.
+    pub descriptor: Option<NetworkDescriptor>,
+}
+
+struct NetworkEntry {
+    descriptor: NetworkDescriptor,
+    status: NetworkStatus,
+}
+
+/// Only networks whose parameters are published by their maintainers are built in, apps
+/// register the rest with `nt_networks_register`.
+///
+/// Everscale: the global ID is the one of the mainnet config (param `global_id` of the
+/// zerostate), the group is the one the example app uses for the mainnet connections and
+/// the token list is the manifest of https://github.com/broxus/ton-assets.
+fn builtin_networks() -> Vec<NetworkEntry> {
+    let builtin = |descriptor| NetworkEntry {
+        descriptor,
+        status: NetworkStatus::Builtin,
+    };
+
+    vec![builtin(NetworkDescriptor {
+        name: "Everscale".to_owned(),
+        global_id: 42,
+        network_group: "mainnet".to_owned(),
+        supports_signature_id: false,
+        currency: NativeCurrency {
+            symbol: "EVER".to_owned(),
+            decimals: 9,
+        },
+        account_explorer_url: Some("https://everscan.io/accounts/{address}".to_owned()),
+        transaction_explorer_url: Some("https://everscan.io/transactions/{hash}".to_owned()),
+        token_list_url: Some(
+            "https://raw.githubusercontent.com/broxus/ton-assets/master/manifest.json".to_owned(),
+        ),
+    })]
+}
+
+fn find_network(global_id: i32) -> Option<(NetworkDescriptor, NetworkStatus)> {
+    NETWORKS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .find(|entry| entry.descriptor.global_id == global_id)
+        .map(|entry| (entry.descriptor.clone(), entry.status))
+}
+
+/// All networks of the registry, the result is a list of `NetworkDescriptor`s.
+#[no_mangle]
+pub unsafe extern "C" fn nt_networks_get_all() -> *mut c_char {
+    execute_sync(|| {
+        fn internal_fn() -> Result<serde_json::Value, ExecutionError> {
+            let networks = NETWORKS
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .map(|entry| entry.descriptor.clone())
+                .collect::<Vec<_>>();
+
+            serde_json::to_value(networks).handle_error()
+        }
+
+        internal_fn()
+    })
+}
+
+/// Adds a custom network, a network with the same global ID is replaced.
+#[no_mangle]
+pub unsafe extern "C" fn nt_networks_register(descriptor: *mut c_char) -> *mut c_char {
+    execute_sync(|| {
+        let descriptor = descriptor.to_string_from_ptr();
+
+        fn internal_fn(descriptor: String) -> Result<serde_json::Value, ExecutionError> {
+            let descriptor = serde_json::from_str::<NetworkDescriptor>(&descriptor)
+                .handle_error_as(ErrorCode::InvalidInput)?;
+
+            if descriptor.name.is_empty() || descriptor.network_group.is_empty() {
+                return Err(ExecutionError::new(
+                    ErrorCode::InvalidInput,
+                    "Network name and group must not be empty",
+                ));
+            }
+
+            let mut networks = NETWORKS.write().unwrap_or_else(|e| e.into_inner());
+            networks.retain(|entry| entry.descriptor.global_id != descriptor.global_id);
+            networks.push(NetworkEntry {
+                descriptor,
+                status: NetworkStatus::Custom,
+            });
+
+            Ok(serde_json::Value::Null)
+        }
+
+        internal_fn(descriptor)
+    })
+}
+
+/// Matches the global ID of the transport network against the registry, the result is
+/// a `DetectedNetwork`.
+#[no_mangle]
+pub unsafe extern "C" fn nt_transport_detect_network(
+    result_port: c_longlong,
+    transport: *mut c_void,
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let transport = transport_from_ptr(transport)?;
+
+        Ok(async move {
+            async fn internal_fn(
+                transport: Arc<dyn Transport>,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let capabilities = transport
+                    .get_capabilities(clock!().as_ref())
+                    .await
+                    .handle_error_as(ErrorCode::RequestFailed)?;
+
+                let (descriptor, status) = match find_network(capabilities.global_id) {
+                    Some((descriptor, status)) => (Some(descriptor), status),
+                    None => (None, NetworkStatus::Unknown),
+                };
+
+                let network = DetectedNetwork {
+                    global_id: capabilities.global_id,
+                    signature_id: capabilities.signature_id(),
+                    status,
+                    descriptor,
+                };
+
+                serde_json::to_value(network).handle_error()
+            }
+
+            internal_fn(transport).await
+        })
+    })
+}
@@ -6,23 +6,29 @@ use std::{
 use nekoton::transport::proto::ProtoTransport;
 
 use crate::{
+    errors::ExecutionError,
+    execute_sync,
     external::proto_connection::{proto_connection_from_native_ptr, ProtoConnectionImpl},
-    ffi_box, HandleError, MatchResult, ToPtrAddress,
+    ffi_handle, HandleError, ToPtrAddress,
 };
 
 #[no_mangle]
 pub unsafe extern "C" fn nt_proto_transport_create(proto_connection: *mut c_void) -> *mut c_char {
-    let proto_connection = proto_connection_from_native_ptr(proto_connection).clone();
+    execute_sync(|| {
+        let proto_connection = proto_connection_from_native_ptr(proto_connection)?;
 
-    fn internal_fn(proto_connection: Arc<ProtoConnectionImpl>) -> Result<serde_json::Value, String> {
-        let proto_transport = ProtoTransport::new(proto_connection);
+        fn internal_fn(
+            proto_connection: Arc<ProtoConnectionImpl>,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let proto_transport = ProtoTransport::new(proto_connection);
 
-        let ptr = proto_transport_new(Arc::new(proto_transport));
+            let ptr = proto_transport_new(Arc::new(proto_transport));
 
-        serde_json::to_value(ptr.to_ptr_address()).handle_error()
-    }
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
+        }
 
-    internal_fn(proto_connection).match_result()
+        internal_fn(proto_connection)
+    })
 }
 
-ffi_box!(proto_transport, Arc<ProtoTransport>);
+ffi_handle!(proto_transport, Arc<ProtoTransport>);
@@ -0,0 +1,531 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    hash::Hash,
+    os::raw::{c_char, c_void},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use nekoton::{
+    core::models::NetworkCapabilities,
+    transport::{
+        models::{PollContractState, RawContractState, RawTransaction},
+        Transport, TransportInfo,
+    },
+};
+use nekoton_utils::Clock;
+use serde::Deserialize;
+use tokio::sync::oneshot;
+use ton_block::{Message, MsgAddressInt};
+use ton_executor::BlockchainConfig;
+use ton_types::{Cell, UInt256};
+
+use crate::{
+    errors::{ErrorCode, ExecutionError},
+    execute_sync, ffi_handle,
+    transport::transport_from_ptr,
+    HandleError, ToPtrAddress, ToStringFromPtr,
+};
+
+/// Waiters which are not first in the queue re-check it at least this often.
+const MIN_QUEUE_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// One request in 1000 seconds, lower rates are most likely a unit mistake.
+const MIN_REQUESTS_PER_SECOND: f64 = 0.001;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThrottledTransportConfig {
+    /// Merge identical requests which are in flight at the same time.
+    #[serde(default = "default_coalesce")]
+    pub coalesce: bool,
+    /// Token bucket refill rate, requests are not limited if not set.
+    pub requests_per_second: Option<f64>,
+    /// Token bucket capacity, one request by default.
+    pub burst: Option<u32>,
+    /// Requests waiting for a token above this limit fail with `rateLimited`.
+    pub max_queued: Option<usize>,
+}
+
+fn default_coalesce() -> bool {
+    true
+}
+
+/// Transport decorator which merges identical in-flight requests and limits the request rate.
+///
+/// Sends and the requests made within [`user_initiated`], e.g. reads requested by the host,
+/// have their own queue which is always served before background requests like polling.
+pub struct ThrottledTransport {
+    transport: Arc<dyn Transport>,
+    coalesce: bool,
+    rate_limiter: Option<RateLimiter>,
+    contract_states: Coalescer<MsgAddressInt, RawContractState>,
+    polls: Coalescer<(MsgAddressInt, u64), PollContractState>,
+    transactions: Coalescer<(MsgAddressInt, u64, u8), Vec<RawTransaction>>,
+    transaction: Coalescer<UInt256, Option<RawTransaction>>,
+    dst_transaction: Coalescer<UInt256, Option<RawTransaction>>,
+    config: Coalescer<bool, BlockchainConfig>,
+    capabilities: Coalescer<(), NetworkCapabilities>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Priority {
+    User,
+    Background,
+}
+
+tokio::task_local! {
+    static PRIORITY: Priority;
+}
+
+impl Priority {
+    fn current() -> Self {
+        PRIORITY
+            .try_with(|priority| *priority)
+            .unwrap_or(Self::Background)
+    }
+}
+
+/// Requests made by `future` skip the background queue of throttled transports.
+/// Tasks spawned by `future` are not affected.
+pub async fn user_initiated<F: Future>(future: F) -> F::Output {
+    PRIORITY.scope(Priority::User, future).await
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Shares the result of a request with identical requests made while it is in flight.
+struct Coalescer<K, V> {
+    in_flight: Mutex<HashMap<K, Vec<oneshot::Sender<Result<V, ExecutionError>>>>>,
+}
+
+impl<K, V> Default for Coalescer<K, V> {
+    fn default() -> Self {
+        Self {
+            in_flight: Default::default(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> Coalescer<K, V> {
+    async fn run<F, Fut>(&self, key: K, f: F) -> Result<V>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<V>>,
+    {
+        loop {
+            let rx = {
+                let mut in_flight = lock(&self.in_flight);
+                match in_flight.get_mut(&key) {
+                    Some(waiters) => {
+                        let (tx, rx) = oneshot::channel();
+                        waiters.push(tx);
+                        Some(rx)
+                    },
+                    None => {
+                        in_flight.insert(key.clone(), Vec::new());
+                        None
+                    },
+                }
+            };
+
+            match rx {
+                Some(rx) => match rx.await {
+                    Ok(result) => return result.map_err(anyhow::Error::from),
+                    // The request was cancelled, so it is made again
+                    Err(_) => continue,
+                },
+                None => {
+                    let mut guard = InFlightGuard {
+                        coalescer: self,
+                        key: Some(key.clone()),
+                    };
+
+                    let result = f().await;
+
+                    for tx in guard.finish() {
+                        let _ = tx.send(match &result {
+                            Ok(value) => Ok(value.clone()),
+                            Err(err) => Err(match err.downcast_ref::<ExecutionError>() {
+                                Some(err) => err.clone(),
+                                None => ExecutionError::new(
+                                    ErrorCode::RequestFailed,
+                                    format!("{:#}", err),
+                                ),
+                            }),
+                        });
+                    }
+
+                    return result;
+                },
+            }
+        }
+    }
+}
+
+/// Removes the in-flight entry if the request is dropped before it completes.
+struct InFlightGuard<'a, K: Hash + Eq, V> {
+    coalescer: &'a Coalescer<K, V>,
+    key: Option<K>,
+}
+
+impl<K: Hash + Eq, V> InFlightGuard<'_, K, V> {
+    fn finish(&mut self) -> Vec<oneshot::Sender<Result<V, ExecutionError>>> {
+        match self.key.take() {
+            Some(key) => lock(&self.coalescer.in_flight)
+                .remove(&key)
+                .unwrap_or_default(),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> Drop for InFlightGuard<'_, K, V> {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Token bucket with a FIFO queue for every priority.
+struct RateLimiter {
+    requests_per_second: f64,
+    burst: f64,
+    max_queued: Option<usize>,
+    state: Mutex<RateLimiterState>,
+}
+
+struct RateLimiterState {
+    tokens: f64,
+    refilled_at: Instant,
+    next_ticket: u64,
+    user_queue: VecDeque<u64>,
+    background_queue: VecDeque<u64>,
+}
+
+impl RateLimiterState {
+    fn refill(&mut self, requests_per_second: f64, burst: f64) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+
+        self.tokens = (self.tokens + elapsed * requests_per_second).min(burst);
+        self.refilled_at = now;
+    }
+
+    fn queue(&mut self, priority: Priority) -> &mut VecDeque<u64> {
+        match priority {
+            Priority::User => &mut self.user_queue,
+            Priority::Background => &mut self.background_queue,
+        }
+    }
+
+    fn is_next(&self, priority: Priority, ticket: u64) -> bool {
+        match priority {
+            Priority::User => self.user_queue.front() == Some(&ticket),
+            Priority::Background => {
+                self.user_queue.is_empty() && self.background_queue.front() == Some(&ticket)
+            },
+        }
+    }
+}
+
+impl RateLimiter {
+    fn new(requests_per_second: f64, burst: u32, max_queued: Option<usize>) -> Self {
+        let burst = burst.max(1) as f64;
+
+        Self {
+            requests_per_second,
+            burst,
+            max_queued,
+            state: Mutex::new(RateLimiterState {
+                tokens: burst,
+                refilled_at: Instant::now(),
+                next_ticket: 0,
+                user_queue: Default::default(),
+                background_queue: Default::default(),
+            }),
+        }
+    }
+
+    async fn acquire(&self, priority: Priority) -> Result<()> {
+        let ticket = {
+            let mut state = lock(&self.state);
+            state.refill(self.requests_per_second, self.burst);
+
+            let queued = state.user_queue.len() + state.background_queue.len();
+            if queued == 0 && state.tokens >= 1.0 {
+                state.tokens -= 1.0;
+                return Ok(());
+            }
+
+            if matches!(self.max_queued, Some(max_queued) if queued >= max_queued) {
+                return Err(ExecutionError::new(
+                    ErrorCode::RateLimited,
+                    "Too many requests are waiting for the rate limit",
+                )
+                .with_context("queued", queued)
+                .into());
+            }
+
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+            state.queue(priority).push_back(ticket);
+            ticket
+        };
+
+        let _guard = TicketGuard {
+            limiter: self,
+            priority,
+            ticket,
+        };
+
+        loop {
+            let wait = {
+                let mut state = lock(&self.state);
+                state.refill(self.requests_per_second, self.burst);
+
+                let is_next = state.is_next(priority, ticket);
+                if is_next && state.tokens >= 1.0 {
+                    state.tokens -= 1.0;
+                    return Ok(());
+                }
+
+                // Only re-checked sooner if the wait can't be represented
+                let until_token = Duration::try_from_secs_f64(
+                    ((1.0 - state.tokens) / self.requests_per_second).max(0.0),
+                )
+                .unwrap_or(MIN_QUEUE_POLL_INTERVAL);
+
+                if is_next {
+                    until_token
+                } else {
+                    until_token.max(MIN_QUEUE_POLL_INTERVAL)
+                }
+            };
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Leaves the queue when the request is served or dropped.
+struct TicketGuard<'a> {
+    limiter: &'a RateLimiter,
+    priority: Priority,
+    ticket: u64,
+}
+
+impl Drop for TicketGuard<'_> {
+    fn drop(&mut self) {
+        let mut state = lock(&self.limiter.state);
+        let queue = state.queue(self.priority);
+        if let Some(position) = queue.iter().position(|ticket| *ticket == self.ticket) {
+            queue.remove(position);
+        }
+    }
+}
+
+impl ThrottledTransport {
+    pub fn new(
+        transport: Arc<dyn Transport>,
+        config: ThrottledTransportConfig,
+    ) -> Result<Self, ExecutionError> {
+        let rate_limiter = match config.requests_per_second {
+            Some(requests_per_second)
+                if requests_per_second.is_finite()
+                    && requests_per_second >= MIN_REQUESTS_PER_SECOND =>
+            {
+                Some(RateLimiter::new(
+                    requests_per_second,
+                    config.burst.unwrap_or(1),
+                    config.max_queued,
+                ))
+            },
+            Some(requests_per_second) => {
+                return Err(ExecutionError::new(
+                    ErrorCode::InvalidInput,
+                    "Requests per second must be finite and at least 0.001",
+                )
+                .with_context("requestsPerSecond", requests_per_second))
+            },
+            None => None,
+        };
+
+        Ok(Self {
+            transport,
+            coalesce: config.coalesce,
+            rate_limiter,
+            contract_states: Default::default(),
+            polls: Default::default(),
+            transactions: Default::default(),
+            transaction: Default::default(),
+            dst_transaction: Default::default(),
+            config: Default::default(),
+            capabilities: Default::default(),
+        })
+    }
+
+    async fn throttle(&self, priority: Priority) -> Result<()> {
+        match &self.rate_limiter {
+            Some(rate_limiter) => rate_limiter.acquire(priority).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Request which is merged with identical in-flight ones.
+    async fn coalesced<'a, K, V, F, Fut>(
+        &'a self,
+        coalescer: &'a Coalescer<K, V>,
+        key: K,
+        f: F,
+    ) -> Result<V>
+    where
+        K: Hash + Eq + Clone,
+        V: Clone,
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<V>>,
+    {
+        let f = &f;
+        let request = move || async move {
+            self.throttle(Priority::current()).await?;
+            f().await
+        };
+
+        if self.coalesce {
+            coalescer.run(key, request).await
+        } else {
+            request().await
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for ThrottledTransport {
+    fn info(&self) -> TransportInfo {
+        self.transport.info()
+    }
+
+    async fn send_message(&self, message: &Message) -> Result<()> {
+        self.throttle(Priority::User).await?;
+        self.transport.send_message(message).await
+    }
+
+    async fn get_contract_state(&self, address: &MsgAddressInt) -> Result<RawContractState> {
+        self.coalesced(&self.contract_states, address.clone(), || {
+            self.transport.get_contract_state(address)
+        })
+        .await
+    }
+
+    async fn get_library_cell(&self, hash: &UInt256) -> Result<Option<Cell>> {
+        self.throttle(Priority::current()).await?;
+        self.transport.get_library_cell(hash).await
+    }
+
+    async fn poll_contract_state(
+        &self,
+        address: &MsgAddressInt,
+        last_trans_lt: u64,
+    ) -> Result<PollContractState> {
+        self.coalesced(&self.polls, (address.clone(), last_trans_lt), || {
+            self.transport.poll_contract_state(address, last_trans_lt)
+        })
+        .await
+    }
+
+    async fn get_accounts_by_code_hash(
+        &self,
+        code_hash: &UInt256,
+        limit: u8,
+        continuation: &Option<MsgAddressInt>,
+    ) -> Result<Vec<MsgAddressInt>> {
+        self.throttle(Priority::current()).await?;
+        self.transport
+            .get_accounts_by_code_hash(code_hash, limit, continuation)
+            .await
+    }
+
+    async fn get_transactions(
+        &self,
+        address: &MsgAddressInt,
+        from_lt: u64,
+        count: u8,
+    ) -> Result<Vec<RawTransaction>> {
+        self.coalesced(
+            &self.transactions,
+            (address.clone(), from_lt, count),
+            || self.transport.get_transactions(address, from_lt, count),
+        )
+        .await
+    }
+
+    async fn get_transaction(&self, id: &UInt256) -> Result<Option<RawTransaction>> {
+        self.coalesced(&self.transaction, *id, || {
+            self.transport.get_transaction(id)
+        })
+        .await
+    }
+
+    async fn get_dst_transaction(&self, message_hash: &UInt256) -> Result<Option<RawTransaction>> {
+        self.coalesced(&self.dst_transaction, *message_hash, || {
+            self.transport.get_dst_transaction(message_hash)
+        })
+        .await
+    }
+
+    async fn get_latest_key_block(&self) -> Result<ton_block::Block> {
+        self.throttle(Priority::current()).await?;
+        self.transport.get_latest_key_block().await
+    }
+
+    async fn get_capabilities(&self, clock: &dyn Clock) -> Result<NetworkCapabilities> {
+        self.coalesced(&self.capabilities, (), || {
+            self.transport.get_capabilities(clock)
+        })
+        .await
+    }
+
+    async fn get_blockchain_config(
+        &self,
+        clock: &dyn Clock,
+        force: bool,
+    ) -> Result<BlockchainConfig> {
+        self.coalesced(&self.config, force, || {
+            self.transport.get_blockchain_config(clock, force)
+        })
+        .await
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn nt_throttled_transport_create(
+    transport: *mut c_void,
+    config: *mut c_char,
+) -> *mut c_char {
+    execute_sync(|| {
+        let transport = transport_from_ptr(transport)?;
+        let config = config.to_string_from_ptr();
+
+        fn internal_fn(
+            transport: Arc<dyn Transport>,
+            config: String,
+        ) -> Result<serde_json::Value, ExecutionError> {
+            let config = serde_json::from_str::<ThrottledTransportConfig>(&config)
+                .handle_error_as(ErrorCode::InvalidInput)?;
+
+            let throttled_transport = ThrottledTransport::new(transport, config)?;
+
+            let ptr = throttled_transport_new(Arc::new(throttled_transport));
+
+            serde_json::to_value(ptr.to_ptr_address()).handle_error()
+        }
+
+        internal_fn(transport, config)
+    })
+}
+
+ffi_handle!(throttled_transport, Arc<ThrottledTransport>);
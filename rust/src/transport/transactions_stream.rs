@@ -0,0 +1,273 @@
+use std::{
+    collections::HashSet,
+    convert::TryFrom,
+    os::raw::{c_char, c_longlong, c_ulonglong, c_void},
+    sync::Arc,
+};
+
+use nekoton::{
+    core::models::{Transaction, TransactionsBatchInfo, TransactionsBatchType},
+    transport::{models::RawTransaction, Transport},
+};
+use nekoton_abi::TransactionId;
+use serde::{Deserialize, Serialize};
+use ton_block::MsgAddressInt;
+
+use crate::{
+    errors::{ErrorCode, ExecutionError},
+    execute_async,
+    host::HostPort,
+    parse_address, parse_hash,
+    transport::{models::TransactionsList, transport_from_ptr},
+    HandleError, ToStringFromPtr,
+};
+
+const DEFAULT_BATCH_SIZE: u8 = 50;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionsStreamOptions {
+    /// Lt of the newest transaction to start from, the latest transaction by default.
+    pub from_lt: Option<String>,
+    /// Expected hash of the `from_lt` transaction, guards against a wrong starting point.
+    /// Requires `from_lt`.
+    pub from_hash: Option<String>,
+    /// The walk stops at the first transaction older than this lt.
+    pub min_lt: Option<String>,
+    /// The walk stops at the first transaction older than this time.
+    pub min_utime: Option<u32>,
+    /// Newer transactions are skipped.
+    pub max_utime: Option<u32>,
+    /// The walk stops once this many transactions were posted.
+    pub limit: Option<usize>,
+    pub batch_size: Option<u8>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionsStreamSummary {
+    pub count: usize,
+    /// Set when the walk stopped on the limit, can be passed as `fromLt` to resume.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub continuation: Option<TransactionId>,
+}
+
+struct TransactionsStream {
+    transport: Arc<dyn Transport>,
+    address: MsgAddressInt,
+    batch_port: HostPort,
+    from_lt: u64,
+    from_hash: Option<ton_types::UInt256>,
+    min_lt: u64,
+    min_utime: u32,
+    max_utime: u32,
+    limit: usize,
+    batch_size: u8,
+}
+
+impl TransactionsStream {
+    fn new(
+        transport: Arc<dyn Transport>,
+        address: MsgAddressInt,
+        batch_port: HostPort,
+        options: TransactionsStreamOptions,
+    ) -> Result<Self, ExecutionError> {
+        let parse_lt = |lt: Option<String>| {
+            lt.map(|lt| lt.parse::<u64>())
+                .transpose()
+                .handle_error_as(ErrorCode::InvalidInput)
+        };
+
+        let batch_size = options.batch_size.unwrap_or(DEFAULT_BATCH_SIZE);
+        if batch_size == 0 {
+            return Err(ExecutionError::new(
+                ErrorCode::InvalidInput,
+                "Batch size must not be zero",
+            ));
+        }
+
+        if options.from_hash.is_some() && options.from_lt.is_none() {
+            return Err(ExecutionError::new(
+                ErrorCode::InvalidInput,
+                "Starting transaction hash requires its lt",
+            ));
+        }
+
+        Ok(Self {
+            transport,
+            address,
+            batch_port,
+            from_lt: parse_lt(options.from_lt)?.unwrap_or(u64::MAX),
+            from_hash: options.from_hash.as_deref().map(parse_hash).transpose()?,
+            min_lt: parse_lt(options.min_lt)?.unwrap_or_default(),
+            min_utime: options.min_utime.unwrap_or_default(),
+            max_utime: options.max_utime.unwrap_or(u32::MAX),
+            limit: options.limit.unwrap_or(usize::MAX),
+            batch_size,
+        })
+    }
+
+    /// Transactions are fetched page by page, every page with matching transactions
+    /// is posted as a `TransactionsList` as soon as it arrives.
+    async fn run(self) -> Result<TransactionsStreamSummary, ExecutionError> {
+        // Pages may overlap, e.g. when the history changes between requests
+        let mut seen = HashSet::new();
+        let mut count = 0;
+
+        let mut from_lt = self.from_lt;
+        let mut next = None;
+        let mut is_first_page = true;
+
+        while from_lt > 0 && count < self.limit {
+            let page = self
+                .transport
+                .get_transactions(&self.address, from_lt, self.batch_size)
+                .await
+                .handle_error_as(ErrorCode::RequestFailed)?;
+
+            if is_first_page {
+                self.check_first_transaction(&page)?;
+                is_first_page = false;
+            }
+
+            let mut batch = Vec::new();
+            let mut reached_bound = false;
+            let mut has_new = false;
+
+            for transaction in page {
+                if !seen.insert(transaction.hash) {
+                    continue;
+                }
+                has_new = true;
+
+                if transaction.data.lt < self.min_lt || transaction.data.now < self.min_utime {
+                    reached_bound = true;
+                    break;
+                }
+
+                from_lt = transaction.data.prev_trans_lt;
+                next = previous_transaction_id(&transaction);
+
+                if transaction.data.now > self.max_utime {
+                    continue;
+                }
+
+                batch.push(transaction);
+                count += 1;
+
+                if count == self.limit {
+                    break;
+                }
+            }
+
+            if !batch.is_empty() && !self.post_batch(&batch)? {
+                log::warn!("Transactions stream port is closed");
+                break;
+            }
+
+            if reached_bound || !has_new {
+                break;
+            }
+        }
+
+        let continuation = if count == self.limit { next } else { None };
+
+        Ok(TransactionsStreamSummary {
+            count,
+            continuation,
+        })
+    }
+
+    fn check_first_transaction(&self, page: &[RawTransaction]) -> Result<(), ExecutionError> {
+        let from_hash = match &self.from_hash {
+            Some(from_hash) => from_hash,
+            None => return Ok(()),
+        };
+
+        match page.first() {
+            Some(transaction)
+                if transaction.data.lt == self.from_lt && &transaction.hash == from_hash =>
+            {
+                Ok(())
+            },
+            _ => Err(ExecutionError::new(
+                ErrorCode::InvalidInput,
+                "Starting transaction was not found",
+            )
+            .with_context("fromLt", self.from_lt)
+            .with_context("fromHash", from_hash.to_hex_string())),
+        }
+    }
+
+    fn post_batch(&self, batch: &[RawTransaction]) -> Result<bool, ExecutionError> {
+        let transactions = batch
+            .iter()
+            .cloned()
+            .filter_map(|e| Transaction::try_from((e.hash, e.data)).ok())
+            .collect::<Vec<_>>();
+
+        let last = batch.last().expect("Batch is not empty");
+        let continuation = previous_transaction_id(last);
+
+        let info = TransactionsBatchInfo {
+            min_lt: last.data.lt,
+            max_lt: batch[0].data.lt,
+            batch_type: TransactionsBatchType::Old,
+        };
+
+        let batch = TransactionsList {
+            transactions,
+            continuation,
+            info: Some(info),
+        };
+
+        let batch = serde_json::to_string(&batch).handle_error()?;
+
+        Ok(self.batch_port.post(batch))
+    }
+}
+
+fn previous_transaction_id(transaction: &RawTransaction) -> Option<TransactionId> {
+    (transaction.data.prev_trans_lt != 0).then_some(TransactionId {
+        lt: transaction.data.prev_trans_lt,
+        hash: transaction.data.prev_trans_hash,
+    })
+}
+
+/// Walks the history of the account from the newest transaction down and posts pages
+/// to `batch_port`. The result is a `TransactionsStreamSummary`, the walk stops when
+/// the operation is cancelled.
+#[no_mangle]
+pub unsafe extern "C" fn nt_transport_stream_transactions(
+    result_port: c_longlong,
+    batch_port: c_longlong,
+    transport: *mut c_void,
+    address: *mut c_char,
+    options: *mut c_char,
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let transport = transport_from_ptr(transport)?;
+        let address = address.to_string_from_ptr();
+        let options = options.to_string_from_ptr();
+
+        let stream = {
+            let address = parse_address(&address)?;
+            let options = serde_json::from_str::<TransactionsStreamOptions>(&options)
+                .handle_error_as(ErrorCode::InvalidInput)?;
+
+            TransactionsStream::new(transport, address, HostPort::event(batch_port), options)?
+        };
+
+        Ok(async move {
+            async fn internal_fn(
+                stream: TransactionsStream,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let summary = stream.run().await?;
+
+                serde_json::to_value(summary).handle_error()
+            }
+
+            internal_fn(stream).await
+        })
+    })
+}
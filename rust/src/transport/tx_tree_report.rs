@@ -0,0 +1,340 @@
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    os::raw::{c_char, c_longlong, c_ulonglong, c_void},
+    sync::Arc,
+};
+
+use nekoton::{
+    core::{models::Transaction, transactions_tree::TransactionsTreeStream},
+    crypto::SignedMessage,
+    transport::Transport,
+};
+use nekoton_abi::{guess_method_by_input, MethodName};
+use nekoton_utils::{serde_address, serde_uint256};
+use serde::{Deserialize, Serialize};
+use ton_block::{InRefValue, MsgAddressInt, Serializable};
+use ton_types::{SliceData, UInt256};
+
+use crate::{
+    clock,
+    errors::{ErrorCode, ExecutionError},
+    execute_async,
+    helpers::abi::{
+        models::{DecodedEvent, DecodedInput},
+        parse_contract_abi,
+    },
+    transport::transport_from_ptr,
+    HandleError, ToStringFromPtr, CLOCK,
+};
+
+pub const DEFAULT_MAX_TRANSACTIONS: usize = 100;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxTreeReportOptions {
+    /// ABIs to decode in-messages and events with, tried in order.
+    #[serde(default)]
+    pub abis: Vec<String>,
+    /// Deeper transactions are not simulated, the transaction of the external message
+    /// is at 0.
+    pub max_depth: Option<u32>,
+    /// The simulation stops after this many transactions within `max_depth`.
+    pub max_transactions: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxTreeReport {
+    /// Transactions in the order they were executed.
+    pub transactions: Vec<TxTreeReportItem>,
+    pub total_fees: String,
+    /// Balance change of every account touched by the tree, including fees.
+    pub balance_deltas: Vec<BalanceDelta>,
+    pub bounced_messages: Vec<BouncedMessage>,
+    /// Set when transactions were left out because of the limits.
+    pub is_truncated: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxTreeReportItem {
+    pub depth: u32,
+    #[serde(with = "serde_address")]
+    pub account: MsgAddressInt,
+    pub transaction: Transaction,
+    pub decoded_input: Option<DecodedInput>,
+    pub events: Vec<DecodedEvent>,
+    pub balance_delta: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceDelta {
+    #[serde(with = "serde_address")]
+    pub address: MsgAddressInt,
+    pub delta: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BouncedMessage {
+    /// Account which failed to process the message and sent it back.
+    #[serde(with = "serde_address")]
+    pub src: MsgAddressInt,
+    #[serde(with = "serde_address")]
+    pub dst: MsgAddressInt,
+    pub value: String,
+    /// Transaction which produced the bounced message.
+    #[serde(with = "serde_uint256")]
+    pub transaction_hash: UInt256,
+}
+
+struct TxTreeReportBuilder {
+    abis: Vec<ton_abi::Contract>,
+    max_depth: u32,
+    max_transactions: usize,
+    /// Depths of transactions which the known messages produce.
+    depths: HashMap<UInt256, u32>,
+    transactions: Vec<TxTreeReportItem>,
+    total_fees: u128,
+    balance_deltas: Vec<(MsgAddressInt, i128)>,
+    bounced_messages: Vec<BouncedMessage>,
+    is_truncated: bool,
+}
+
+impl TxTreeReportBuilder {
+    fn new(options: TxTreeReportOptions) -> Result<Self, ExecutionError> {
+        let abis = options
+            .abis
+            .iter()
+            .map(|abi| parse_contract_abi(abi))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            abis,
+            max_depth: options.max_depth.unwrap_or(u32::MAX),
+            max_transactions: options.max_transactions.unwrap_or(DEFAULT_MAX_TRANSACTIONS),
+            depths: Default::default(),
+            transactions: Default::default(),
+            total_fees: 0,
+            balance_deltas: Default::default(),
+            bounced_messages: Default::default(),
+            is_truncated: false,
+        })
+    }
+
+    async fn run(
+        mut self,
+        mut stream: TransactionsTreeStream,
+    ) -> Result<TxTreeReport, ExecutionError> {
+        stream.disable_signature_check();
+
+        let mut processed = 0;
+        while let Some(transaction) = stream
+            .next()
+            .await
+            .handle_error_as(ErrorCode::ExecutionFailed)?
+        {
+            // The stream executes messages level by level, so once a transaction is too
+            // deep, all the remaining ones are too
+            let depth = self.depth_of(&transaction);
+            if depth > self.max_depth || processed == self.max_transactions {
+                self.is_truncated = true;
+                break;
+            }
+            processed += 1;
+
+            self.add_transaction(depth, transaction)?;
+        }
+
+        let balance_deltas = self
+            .balance_deltas
+            .into_iter()
+            .map(|(address, delta)| BalanceDelta {
+                address,
+                delta: delta.to_string(),
+            })
+            .collect();
+
+        Ok(TxTreeReport {
+            transactions: self.transactions,
+            total_fees: self.total_fees.to_string(),
+            balance_deltas,
+            bounced_messages: self.bounced_messages,
+            is_truncated: self.is_truncated,
+        })
+    }
+
+    fn depth_of(&self, raw: &ton_block::Transaction) -> u32 {
+        raw.in_msg_cell()
+            .and_then(|cell| self.depths.get(&cell.repr_hash()).copied())
+            .unwrap_or_default()
+    }
+
+    fn add_transaction(
+        &mut self,
+        depth: u32,
+        raw: ton_block::Transaction,
+    ) -> Result<(), ExecutionError> {
+        raw.out_msgs
+            .iterate(|InRefValue(message)| {
+                self.depths
+                    .insert(message.serialize()?.repr_hash(), depth + 1);
+                Ok(true)
+            })
+            .handle_error()?;
+
+        let hash = raw.serialize().handle_error()?.repr_hash();
+        let transaction = Transaction::try_from((hash, raw)).handle_error()?;
+
+        let account = match &transaction.in_msg.dst {
+            Some(dst) => dst.clone(),
+            None => return Ok(()),
+        };
+
+        let balance_delta = balance_delta(&transaction);
+
+        self.total_fees += transaction.total_fees;
+        match self
+            .balance_deltas
+            .iter_mut()
+            .find(|(address, _)| address == &account)
+        {
+            Some((_, delta)) => *delta += balance_delta,
+            None => self.balance_deltas.push((account.clone(), balance_delta)),
+        }
+
+        for message in &transaction.out_msgs {
+            if let (true, Some(dst)) = (message.bounced, &message.dst) {
+                self.bounced_messages.push(BouncedMessage {
+                    src: account.clone(),
+                    dst: dst.clone(),
+                    value: message.value.to_string(),
+                    transaction_hash: hash,
+                });
+            }
+        }
+
+        let decoded_input = self.decode_input(&transaction);
+        let events = self.decode_events(&transaction);
+
+        self.transactions.push(TxTreeReportItem {
+            depth,
+            account,
+            transaction,
+            decoded_input,
+            events,
+            balance_delta: balance_delta.to_string(),
+        });
+
+        Ok(())
+    }
+
+    /// Messages which none of the ABIs can decode are left as is.
+    fn decode_input(&self, transaction: &Transaction) -> Option<DecodedInput> {
+        let body = transaction.in_msg.body.as_ref()?;
+        let body = SliceData::load_cell(body.data.clone()).ok()?;
+        let internal = transaction.in_msg.src.is_some();
+
+        self.abis.iter().find_map(|abi| {
+            let method = guess_method_by_input(abi, &body, &MethodName::Guess, internal).ok()??;
+            let input = method.decode_input(body.clone(), internal, false).ok()?;
+
+            Some(DecodedInput {
+                method: method.name.to_owned(),
+                input: nekoton_abi::make_abi_tokens(&input).ok()?,
+            })
+        })
+    }
+
+    fn decode_events(&self, transaction: &Transaction) -> Vec<DecodedEvent> {
+        transaction
+            .out_msgs
+            .iter()
+            .filter(|message| message.dst.is_none())
+            .filter_map(|message| {
+                let body = message.body.as_ref()?;
+                let body = SliceData::load_cell(body.data.clone()).ok()?;
+                let id = nekoton_abi::read_function_id(&body).ok()?;
+
+                self.abis.iter().find_map(|abi| {
+                    let event = abi.event_by_id(id).ok()?;
+                    let tokens = event.decode_input(body.clone()).ok()?;
+
+                    Some(DecodedEvent {
+                        event: event.name.to_owned(),
+                        data: nekoton_abi::make_abi_tokens(&tokens).ok()?,
+                    })
+                })
+            })
+            .collect()
+    }
+}
+
+/// Change of the account balance, the value of a bounced message is sent back so it's
+/// counted in the outgoing messages.
+pub fn balance_delta(transaction: &Transaction) -> i128 {
+    let credit = match transaction.in_msg.src {
+        Some(_) => transaction.in_msg.value as i128,
+        None => 0,
+    };
+    let debit = transaction
+        .out_msgs
+        .iter()
+        .map(|message| message.value as i128)
+        .sum::<i128>();
+
+    credit - debit - transaction.total_fees as i128
+}
+
+/// Simulates the whole tree of transactions produced by the message without sending it.
+/// The result is a `TxTreeReport`.
+#[no_mangle]
+pub unsafe extern "C" fn nt_transport_simulate_transaction_tree_report(
+    result_port: c_longlong,
+    transport: *mut c_void,
+    signed_message: *mut c_char,
+    options: *mut c_char,
+) -> c_ulonglong {
+    execute_async(result_port, move || {
+        let transport = transport_from_ptr(transport)?;
+        let signed_message = signed_message.to_string_from_ptr();
+        let options = options.to_string_from_ptr();
+
+        let (signed_message, builder) = {
+            let signed_message =
+                serde_json::from_str::<SignedMessage>(&signed_message).handle_error()?;
+            let options = serde_json::from_str::<TxTreeReportOptions>(&options)
+                .handle_error_as(ErrorCode::InvalidInput)?;
+
+            (signed_message, TxTreeReportBuilder::new(options)?)
+        };
+
+        Ok(async move {
+            async fn internal_fn(
+                transport: Arc<dyn Transport>,
+                signed_message: SignedMessage,
+                builder: TxTreeReportBuilder,
+            ) -> Result<serde_json::Value, ExecutionError> {
+                let config = transport
+                    .get_blockchain_config(clock!().as_ref(), false)
+                    .await
+                    .handle_error_as(ErrorCode::RequestFailed)?;
+
+                let stream = TransactionsTreeStream::new(
+                    signed_message.message,
+                    config,
+                    transport,
+                    clock!(),
+                );
+
+                let report = builder.run(stream).await?;
+
+                serde_json::to_value(report).handle_error()
+            }
+
+            internal_fn(transport, signed_message, builder).await
+        })
+    })
+}